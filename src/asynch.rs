@@ -0,0 +1,355 @@
+//! Async transmit and receive API.
+//!
+//! [`Can::split_async`] splits a [`Can`] instance into an [`AsyncTx`] and an [`AsyncRx`] half,
+//! whose methods are `async fn`s that can be awaited on any executor. The futures are woken from
+//! the bxCAN interrupt handlers, which have to forward the **TX**, **RX FIFO 0** and **RX FIFO 1**
+//! interrupts to the [`AsyncState`] that was passed to [`Can::split_async`]:
+//!
+//! ```no_run
+//! # use bxcan::{Can, Instance, RegisterBlock};
+//! # struct CAN1;
+//! # unsafe impl Instance for CAN1 {
+//! #     const REGISTERS: *mut RegisterBlock = 0x4000_6400 as *mut _;
+//! # }
+//! use bxcan::asynch::AsyncState;
+//!
+//! static CAN1_STATE: AsyncState<CAN1> = AsyncState::new();
+//!
+//! // Interrupt handlers of the microcontroller:
+//! fn can1_tx() {
+//!     CAN1_STATE.on_tx_interrupt();
+//! }
+//! fn can1_rx0() {
+//!     CAN1_STATE.on_rx0_interrupt();
+//! }
+//! fn can1_rx1() {
+//!     CAN1_STATE.on_rx1_interrupt();
+//! }
+//!
+//! # fn split(can: Can<CAN1>) {
+//! let (tx, rx) = can.split_async(&CAN1_STATE);
+//! # }
+//! ```
+//!
+//! If all filters store frames in the same FIFO, [`Can::split_async_fifo`] can be used instead. It
+//! only enables the interrupt of that FIFO, leaving the other one (and its interrupt vector, which
+//! may be shared with other peripherals) free for other purposes.
+//!
+//! The interrupt-related state is protected with the [`critical-section`] crate, so an
+//! implementation of it has to be linked into the final application (for example by enabling the
+//! `critical-section-single-core` feature of the `cortex-m` crate).
+//!
+//! `embedded-can` does not define async traits yet. With the `embedded-can-04` feature enabled,
+//! the [`Frame`] and [`OverrunError`] types used by this API implement its `Frame` and `Error`
+//! traits, and `AsyncCan` combines both halves into an adapter whose `transmit` and `receive`
+//! methods are async versions of the methods of `embedded_can::blocking::Can`. Drivers written
+//! against that trait can be ported to async by awaiting these methods instead.
+//!
+//! [`critical-section`]: https://docs.rs/critical-section
+
+use core::cell::RefCell;
+use core::future::poll_fn;
+use core::marker::PhantomData;
+use core::task::{Poll, Waker};
+
+use critical_section::Mutex;
+
+use crate::{
+    Can, Fifo, Frame, Instance, Interrupts, OverrunError, RegisterBlock, Rx0, Rx1, TransmitStatus,
+    Tx,
+};
+
+/// Storage for a [`Waker`] that can be shared with an interrupt handler.
+struct WakerSlot {
+    waker: Mutex<RefCell<Option<Waker>>>,
+}
+
+impl WakerSlot {
+    const fn new() -> Self {
+        Self {
+            waker: Mutex::new(RefCell::new(None)),
+        }
+    }
+
+    fn register(&self, waker: &Waker) {
+        critical_section::with(|cs| {
+            let mut slot = self.waker.borrow_ref_mut(cs);
+            match &*slot {
+                Some(old) if old.will_wake(waker) => {}
+                _ => *slot = Some(waker.clone()),
+            }
+        });
+    }
+
+    fn wake(&self) {
+        if let Some(waker) = critical_section::with(|cs| self.waker.borrow_ref_mut(cs).take()) {
+            waker.wake();
+        }
+    }
+}
+
+/// Interrupt state shared between the async halves of a CAN peripheral and its interrupt handlers.
+///
+/// This is meant to be put in a `static` and passed to [`Can::split_async`].
+pub struct AsyncState<I> {
+    tx_waker: WakerSlot,
+    rx_waker: WakerSlot,
+    _can: PhantomData<fn() -> I>,
+}
+
+impl<I: Instance> AsyncState<I> {
+    /// Creates a new, empty interrupt state.
+    pub const fn new() -> Self {
+        Self {
+            tx_waker: WakerSlot::new(),
+            rx_waker: WakerSlot::new(),
+            _can: PhantomData,
+        }
+    }
+
+    fn registers(&self) -> &RegisterBlock {
+        unsafe { &*I::register_block() }
+    }
+
+    /// Handles the **TX** interrupt.
+    ///
+    /// This clears the interrupt condition and wakes up a pending [`AsyncTx::transmit`] call.
+    pub fn on_tx_interrupt(&self) {
+        // Write-1-to-clear register, so this cannot race with other accesses.
+        self.registers()
+            .tsr
+            .write(|w| w.rqcp2().set_bit().rqcp1().set_bit().rqcp0().set_bit());
+        self.tx_waker.wake();
+    }
+
+    /// Handles the **RX FIFO 0** interrupt.
+    ///
+    /// This masks the interrupt until the next call to [`AsyncRx::receive`] and wakes up a pending
+    /// [`AsyncRx::receive`] call.
+    pub fn on_rx0_interrupt(&self) {
+        self.disable_interrupts(Interrupts::FIFO0_MESSAGE_PENDING);
+        self.rx_waker.wake();
+    }
+
+    /// Handles the **RX FIFO 1** interrupt.
+    ///
+    /// This masks the interrupt until the next call to [`AsyncRx::receive`] and wakes up a pending
+    /// [`AsyncRx::receive`] call.
+    pub fn on_rx1_interrupt(&self) {
+        self.disable_interrupts(Interrupts::FIFO1_MESSAGE_PENDING);
+        self.rx_waker.wake();
+    }
+
+    fn enable_interrupts(&self, interrupts: Interrupts) {
+        let ier = &self.registers().ier;
+        critical_section::with(|_| {
+            ier.modify(|r, w| unsafe { w.bits(r.bits() | interrupts.bits()) });
+        });
+    }
+
+    fn disable_interrupts(&self, interrupts: Interrupts) {
+        let ier = &self.registers().ier;
+        critical_section::with(|_| {
+            ier.modify(|r, w| unsafe { w.bits(r.bits() & !interrupts.bits()) });
+        });
+    }
+}
+
+impl<I: Instance> Default for AsyncState<I> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I: Instance + 'static> Can<I> {
+    /// Consumes this `Can` instance and splits it into async transmitting and receiving halves.
+    ///
+    /// This enables the **TX**, **RX FIFO 0** and **RX FIFO 1** interrupts, whose handlers have to
+    /// call the corresponding methods of `state`. See the [`asynch`][crate::asynch] module for an
+    /// example.
+    pub fn split_async(mut self, state: &'static AsyncState<I>) -> (AsyncTx<I>, AsyncRx<I>) {
+        self.enable_interrupts(Interrupts::TRANSMIT_MAILBOX_EMPTY | rx_interrupts(None));
+
+        let (tx, rx0, rx1) = self.split();
+        let rx = AsyncRx {
+            rx0,
+            rx1,
+            fifo: None,
+            state,
+        };
+        (AsyncTx { tx, state }, rx)
+    }
+
+    /// Consumes this `Can` instance and splits it into async halves that only receive from `fifo`.
+    ///
+    /// This is meant for filter configurations where all banks store frames in `fifo`. Only the
+    /// **TX** interrupt and the interrupt of `fifo` are enabled and have to be forwarded to
+    /// `state`, and [`AsyncRx::receive`] only checks `fifo`. Frames stored in the other FIFO are
+    /// not received. Otherwise, this behaves like [`Can::split_async`].
+    pub fn split_async_fifo(
+        mut self,
+        state: &'static AsyncState<I>,
+        fifo: Fifo,
+    ) -> (AsyncTx<I>, AsyncRx<I>) {
+        self.enable_interrupts(Interrupts::TRANSMIT_MAILBOX_EMPTY | rx_interrupts(Some(fifo)));
+
+        let (tx, rx0, rx1) = self.split();
+        let rx = AsyncRx {
+            rx0,
+            rx1,
+            fifo: Some(fifo),
+            state,
+        };
+        (AsyncTx { tx, state }, rx)
+    }
+}
+
+/// Returns the message pending interrupts of `fifo`, or of both FIFOs if it is `None`.
+fn rx_interrupts(fifo: Option<Fifo>) -> Interrupts {
+    match fifo {
+        Some(Fifo::Fifo0) => Interrupts::FIFO0_MESSAGE_PENDING,
+        Some(Fifo::Fifo1) => Interrupts::FIFO1_MESSAGE_PENDING,
+        None => Interrupts::FIFO0_MESSAGE_PENDING | Interrupts::FIFO1_MESSAGE_PENDING,
+    }
+}
+
+/// Async interface to the CAN transmitter part.
+pub struct AsyncTx<I: Instance + 'static> {
+    tx: Tx<I>,
+    state: &'static AsyncState<I>,
+}
+
+impl<I: Instance + 'static> AsyncTx<I> {
+    /// Puts a CAN frame in a transmit mailbox for transmission on the bus, waiting for a mailbox to
+    /// become available.
+    ///
+    /// This follows the same rules as [`Tx::transmit`]: Frames are transmitted in order of their
+    /// priority, and a lower-priority frame may be dequeued to make room for `frame`, which is
+    /// returned in the [`TransmitStatus`].
+    pub async fn transmit(&mut self, frame: &Frame) -> TransmitStatus {
+        poll_fn(|cx| {
+            self.state.tx_waker.register(cx.waker());
+
+            match self.tx.transmit(frame) {
+                Ok(status) => Poll::Ready(status),
+                Err(nb::Error::WouldBlock) => Poll::Pending,
+                Err(nb::Error::Other(infallible)) => match infallible {},
+            }
+        })
+        .await
+    }
+
+    /// Waits until no frame is pending for transmission.
+    pub async fn flush(&mut self) {
+        poll_fn(|cx| {
+            self.state.tx_waker.register(cx.waker());
+
+            if self.tx.is_idle() {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await
+    }
+
+    /// Returns the [`Tx`] half this instance wraps, for non-async use.
+    pub fn inner(&mut self) -> &mut Tx<I> {
+        &mut self.tx
+    }
+}
+
+/// Async interface to the CAN receiver part.
+pub struct AsyncRx<I: Instance + 'static> {
+    rx0: Rx0<I>,
+    rx1: Rx1<I>,
+    /// The only FIFO to receive from, if split with [`Can::split_async_fifo`].
+    fifo: Option<Fifo>,
+    state: &'static AsyncState<I>,
+}
+
+impl<I: Instance + 'static> AsyncRx<I> {
+    /// Waits for a frame to be received in either FIFO.
+    ///
+    /// FIFO 0 is checked before FIFO 1. If this instance was created by [`Can::split_async_fifo`],
+    /// only the FIFO passed to it is checked.
+    ///
+    /// Returns `Err` when a frame was lost due to buffer overrun.
+    pub async fn receive(&mut self) -> Result<Frame, OverrunError> {
+        poll_fn(|cx| {
+            self.state.rx_waker.register(cx.waker());
+
+            let result = match self.fifo {
+                Some(Fifo::Fifo0) => self.rx0.receive(),
+                Some(Fifo::Fifo1) => self.rx1.receive(),
+                None => match self.rx0.receive() {
+                    Err(nb::Error::WouldBlock) => self.rx1.receive(),
+                    result => result,
+                },
+            };
+            match result {
+                Ok(frame) => Poll::Ready(Ok(frame)),
+                Err(nb::Error::Other(e)) => Poll::Ready(Err(e)),
+                Err(nb::Error::WouldBlock) => {
+                    // If a frame arrived in the meantime, unmasking the interrupt fires it
+                    // immediately, so no wakeup can be missed.
+                    self.state.enable_interrupts(rx_interrupts(self.fifo));
+                    Poll::Pending
+                }
+            }
+        })
+        .await
+    }
+
+    /// Returns the [`Rx0`] and [`Rx1`] halves this instance wraps, for non-async use.
+    pub fn inner(&mut self) -> (&mut Rx0<I>, &mut Rx1<I>) {
+        (&mut self.rx0, &mut self.rx1)
+    }
+}
+
+/// Async adapter with the interface of the `embedded_can::blocking::Can` trait.
+///
+/// `embedded-can` has no async CAN trait yet, so this provides the methods of its blocking trait
+/// as `async fn`s, with the same frame and error types. It is meant to become an implementation of
+/// the async trait once `embedded-can` defines one.
+#[cfg(feature = "embedded-can-04")]
+pub struct AsyncCan<I: Instance + 'static> {
+    tx: AsyncTx<I>,
+    rx: AsyncRx<I>,
+}
+
+#[cfg(feature = "embedded-can-04")]
+impl<I: Instance + 'static> AsyncCan<I> {
+    /// Combines the halves returned by [`Can::split_async`] or [`Can::split_async_fifo`].
+    pub fn new(tx: AsyncTx<I>, rx: AsyncRx<I>) -> Self {
+        Self { tx, rx }
+    }
+
+    /// Puts a frame in a transmit mailbox, waiting for a mailbox to become available.
+    ///
+    /// Like the blocking `embedded_can` impl of [`Can`], a lower-priority frame dequeued to make
+    /// room for `frame` is enqueued again, so no frame is lost.
+    pub async fn transmit(&mut self, frame: &Frame) -> Result<(), embedded_can_04::ErrorKind> {
+        let mut status = self.tx.transmit(frame).await;
+        while let Some(dequeued) = status.into_dequeued_frame() {
+            status = self.tx.transmit(&dequeued).await;
+        }
+        Ok(())
+    }
+
+    /// Waits for a frame to be received, see [`AsyncRx::receive`].
+    ///
+    /// A buffer overrun is reported as [`ErrorKind::Overrun`][embedded_can_04::ErrorKind::Overrun].
+    pub async fn receive(&mut self) -> Result<Frame, embedded_can_04::ErrorKind> {
+        self.rx
+            .receive()
+            .await
+            .map_err(|_| embedded_can_04::ErrorKind::Overrun)
+    }
+
+    /// Returns the async halves this adapter wraps.
+    pub fn into_inner(self) -> (AsyncTx<I>, AsyncRx<I>) {
+        (self.tx, self.rx)
+    }
+}
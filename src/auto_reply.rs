@@ -0,0 +1,153 @@
+//! Automatic responses to remote frames.
+
+use core::convert::Infallible;
+
+use heapless::Vec;
+
+use crate::{Frame, Id, Instance, TransmitStatus, Tx};
+
+#[allow(unused_imports)] // for intra-doc links only
+use crate::Can;
+
+/// A table of data frames that are sent in response to matching remote frames.
+///
+/// Each entry is a data frame that is transmitted whenever a remote frame with the same identifier
+/// is received. Up to `N` entries can be stored.
+///
+/// When receiving through a [`Can`] instance, [`Can::receive_with_auto_reply`] answers remote
+/// frames while receiving. With the halves returned by [`Can::split`], received frames have to be
+/// passed to [`AutoReply::handle`], which is meant to be called from the **RX FIFO** interrupt
+/// handlers so that the response is enqueued with minimal latency:
+///
+/// ```no_run
+/// # use bxcan::{Instance, RegisterBlock};
+/// # struct CAN1;
+/// # unsafe impl Instance for CAN1 {
+/// #     const REGISTERS: *mut RegisterBlock = 0x4000_6400 as *mut _;
+/// # }
+/// use bxcan::{standard_id, AutoReply, Frame, Rx0, Tx};
+///
+/// fn setup(replies: &mut AutoReply<4>) {
+///     replies
+///         .set(Frame::new_data(standard_id!(0x100), [0x12, 0x34]))
+///         .unwrap();
+/// }
+///
+/// // Called from the RX FIFO 0 interrupt handler.
+/// fn can1_rx0(rx: &mut Rx0<CAN1>, tx: &mut Tx<CAN1>, replies: &AutoReply<4>) {
+///     while let Ok(frame) = rx.receive() {
+///         match replies.handle(&frame, tx) {
+///             Ok(Some(_status)) => {} // Remote frame answered.
+///             Ok(None) => { /* Process `frame` normally. */ }
+///             Err(_) => { /* No mailbox available for the response. */ }
+///         }
+///     }
+/// }
+/// ```
+pub struct AutoReply<const N: usize> {
+    entries: Vec<Frame, N>,
+}
+
+impl<const N: usize> AutoReply<N> {
+    /// Creates an empty table.
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Registers `frame` as the response to remote frames with the same identifier.
+    ///
+    /// `frame` should be a data frame. If there already is an entry for its identifier, it is
+    /// replaced and the previous frame is returned.
+    ///
+    /// Returns `Err` with `frame` if the table is full.
+    pub fn set(&mut self, frame: Frame) -> Result<Option<Frame>, Frame> {
+        match self.entries.iter_mut().find(|f| f.id() == frame.id()) {
+            Some(entry) => Ok(Some(core::mem::replace(entry, frame))),
+            None => self.entries.push(frame).map(|()| None),
+        }
+    }
+
+    /// Removes the entry for `id`, returning its frame.
+    pub fn remove(&mut self, id: impl Into<Id>) -> Option<Frame> {
+        let id = id.into();
+        let index = self.entries.iter().position(|f| f.id() == id)?;
+        Some(self.entries.swap_remove(index))
+    }
+
+    /// Returns the frame sent in response to remote frames with identifier `id`.
+    pub fn get(&self, id: impl Into<Id>) -> Option<&Frame> {
+        let id = id.into();
+        self.entries.iter().find(|f| f.id() == id)
+    }
+
+    /// Removes all entries.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Answers `received` if it is a remote frame with a registered identifier.
+    ///
+    /// The response is enqueued via [`Tx::transmit`], and its [`TransmitStatus`] is returned. Like
+    /// with any other transmission, this may dequeue a lower-priority frame, which is then handed
+    /// back to the caller in the status.
+    ///
+    /// Returns `Ok(None)` if `received` is a data frame or no entry matches its identifier, in
+    /// which case the frame should be processed by the application as usual. Returns
+    /// [`WouldBlock`][nb::Error::WouldBlock] if an entry matches, but all transmit mailboxes are
+    /// occupied by frames of higher priority.
+    pub fn handle<I>(
+        &self,
+        received: &Frame,
+        tx: &mut Tx<I>,
+    ) -> nb::Result<Option<TransmitStatus>, Infallible>
+    where
+        I: Instance,
+    {
+        if !received.is_remote_frame() {
+            return Ok(None);
+        }
+
+        match self.get(received.id()) {
+            Some(response) => tx.transmit(response).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<const N: usize> Default for AutoReply<N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExtendedId, StandardId};
+
+    #[test]
+    fn entries() {
+        let mut replies = AutoReply::<2>::new();
+        let std_id = StandardId::new(0x100).unwrap();
+        let ext_id = ExtendedId::new(0x100).unwrap();
+        let first = Frame::new_data(std_id, [1]);
+        let second = Frame::new_data(std_id, [2]);
+        let ext = Frame::new_data(ext_id, [3]);
+
+        assert_eq!(replies.set(first.clone()), Ok(None));
+        assert_eq!(replies.set(ext.clone()), Ok(None));
+        assert_eq!(replies.set(second.clone()), Ok(Some(first)));
+        assert_eq!(replies.get(std_id), Some(&second));
+        assert_eq!(replies.get(ext_id), Some(&ext));
+
+        let full = Frame::new_data(StandardId::ZERO, []);
+        assert_eq!(replies.set(full.clone()), Err(full));
+
+        assert_eq!(replies.remove(std_id), Some(second));
+        assert_eq!(replies.remove(std_id), None);
+        assert_eq!(replies.get(ext_id), Some(&ext));
+    }
+}
@@ -0,0 +1,439 @@
+//! Bit timing calculation.
+//!
+//! The [`from_bitrate`] function computes a [`BitTiming`] configuration from the peripheral clock
+//! frequency and the desired bitrate, which can then be passed to
+//! [`CanBuilder::set_bit_timing`][crate::CanBuilder::set_bit_timing] via [`BitTiming::btr`]:
+//!
+//! ```
+//! use bxcan::bit_timing;
+//!
+//! let timing = bit_timing::from_bitrate(36_000_000, 500_000, 0.875).unwrap();
+//! assert_eq!(timing.btr(), 0x0005_0008);
+//! ```
+//!
+//! HALs can implement [`PeripheralClock`] for their clock configuration type, so that users can
+//! pass it to [`from_clock`] or [`CanBuilder::set_bitrate_from_clock`][crate::CanBuilder::set_bitrate_from_clock]
+//! instead of the raw frequency.
+
+use core::fmt;
+
+// Limits of the `CAN_BTR` register fields.
+const MAX_SEG1: u8 = 16;
+const MAX_SEG2: u8 = 8;
+const MAX_SJW: u8 = 4;
+const MAX_PRESCALER: u16 = 1024;
+
+/// A bit timing configuration of the bxCAN peripheral.
+///
+/// A bit consists of a synchronization segment of 1 time quantum, followed by bit segment 1 and
+/// bit segment 2, which are separated by the sample point. The length of a single time quantum is
+/// determined by the [prescaler][BitTiming::prescaler] of the peripheral clock.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub struct BitTiming {
+    prescaler: u16,
+    seg1: u8,
+    seg2: u8,
+    sjw: u8,
+}
+
+impl BitTiming {
+    /// Creates a bit timing configuration from its raw parts.
+    ///
+    /// - `prescaler` is the peripheral clock divider (`1..=1024`).
+    /// - `seg1` is the length of bit segment 1 in time quanta (`1..=16`).
+    /// - `seg2` is the length of bit segment 2 in time quanta (`1..=8`).
+    /// - `sjw` is the resynchronization jump width in time quanta (`1..=4`).
+    ///
+    /// Returns `None` if any of the parameters is out of range.
+    pub const fn new(prescaler: u16, seg1: u8, seg2: u8, sjw: u8) -> Option<Self> {
+        // `RangeInclusive::contains` is not `const`.
+        if prescaler >= 1
+            && prescaler <= MAX_PRESCALER
+            && seg1 >= 1
+            && seg1 <= MAX_SEG1
+            && seg2 >= 1
+            && seg2 <= MAX_SEG2
+            && sjw >= 1
+            && sjw <= MAX_SJW
+        {
+            Some(Self {
+                prescaler,
+                seg1,
+                seg2,
+                sjw,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Decodes the bit timing fields of a raw `CAN_BTR` register value.
+    ///
+    /// The silent and loopback mode bits are ignored.
+    pub fn from_btr(btr: u32) -> Self {
+        Self {
+            prescaler: (btr & 0x3FF) as u16 + 1,
+            seg1: ((btr >> 16) & 0xF) as u8 + 1,
+            seg2: ((btr >> 20) & 0x7) as u8 + 1,
+            sjw: ((btr >> 24) & 0x3) as u8 + 1,
+        }
+    }
+
+    /// Returns the value of the `CAN_BTR` register for this configuration.
+    ///
+    /// This can be passed to [`CanBuilder::set_bit_timing`][crate::CanBuilder::set_bit_timing]
+    /// and [`CanConfig::set_bit_timing`][crate::CanConfig::set_bit_timing].
+    pub const fn btr(&self) -> u32 {
+        ((self.sjw - 1) as u32) << 24
+            | ((self.seg2 - 1) as u32) << 20
+            | ((self.seg1 - 1) as u32) << 16
+            | (self.prescaler - 1) as u32
+    }
+
+    /// Returns the peripheral clock divider.
+    #[inline]
+    pub fn prescaler(&self) -> u16 {
+        self.prescaler
+    }
+
+    /// Returns the length of bit segment 1 (before the sample point) in time quanta.
+    #[inline]
+    pub fn seg1(&self) -> u8 {
+        self.seg1
+    }
+
+    /// Returns the length of bit segment 2 (after the sample point) in time quanta.
+    #[inline]
+    pub fn seg2(&self) -> u8 {
+        self.seg2
+    }
+
+    /// Returns the resynchronization jump width in time quanta.
+    #[inline]
+    pub fn sjw(&self) -> u8 {
+        self.sjw
+    }
+
+    /// Returns the number of time quanta per bit.
+    #[inline]
+    pub fn quanta_per_bit(&self) -> u32 {
+        1 + u32::from(self.seg1) + u32::from(self.seg2)
+    }
+
+    /// Returns the resulting bitrate in bit/s, given the frequency of the peripheral clock.
+    pub fn bitrate(&self, pclk_hz: u32) -> u32 {
+        pclk_hz / (u32::from(self.prescaler) * self.quanta_per_bit())
+    }
+
+    /// Returns the position of the sample point as a fraction of the bit time.
+    pub fn sample_point(&self) -> f32 {
+        (1 + u32::from(self.seg1)) as f32 / self.quanta_per_bit() as f32
+    }
+}
+
+/// Error returned by [`from_bitrate`] and [`from_clock`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum BitTimingError {
+    /// The requested bitrate is 0, or higher than the peripheral clock allows.
+    InvalidBitrate,
+
+    /// The requested sample point is not between 0 and 1.
+    InvalidSamplePoint,
+
+    /// No prescaler value results in exactly the requested bitrate.
+    NoSolution,
+}
+
+impl BitTimingError {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            BitTimingError::InvalidBitrate => "invalid bitrate",
+            BitTimingError::InvalidSamplePoint => "sample point not between 0 and 1",
+            BitTimingError::NoSolution => "no bit timing achieves the exact bitrate",
+        }
+    }
+}
+
+impl fmt::Display for BitTimingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A clock configuration that provides the frequency of the clock feeding the CAN peripheral.
+///
+/// HALs can implement this for their frozen clock configuration type (typically called `Clocks`),
+/// so that users can pass it to [`CanBuilder::set_bitrate_from_clock`][crate::CanBuilder::set_bitrate_from_clock] or
+/// [`from_clock`] instead of looking up which bus the peripheral is attached to. It is also
+/// implemented for `u32`, which is taken as the frequency in Hz.
+pub trait PeripheralClock {
+    /// Returns the frequency of the clock the CAN peripheral is attached to (eg. APB1) in Hz.
+    fn can_clock_hz(&self) -> u32;
+}
+
+impl PeripheralClock for u32 {
+    #[inline]
+    fn can_clock_hz(&self) -> u32 {
+        *self
+    }
+}
+
+/// Computes a bit timing configuration for `bitrate`, with a sample point at 87.5%.
+///
+/// This calls [`from_bitrate`] with the frequency returned by `clock`.
+pub fn from_clock(clock: &impl PeripheralClock, bitrate: u32) -> Result<BitTiming, BitTimingError> {
+    from_bitrate(clock.can_clock_hz(), bitrate, 0.875)
+}
+
+/// Computes a bit timing configuration for the given peripheral clock and bitrate.
+///
+/// - `pclk_hz` is the frequency of the clock the CAN peripheral is attached to (eg. APB1), *not*
+///   the CPU clock frequency.
+/// - `bitrate` is the desired bitrate in bit/s.
+/// - `sample_point` is the desired position of the sample point as a fraction of the bit time. It
+///   should normally be `0.875` (87.5%).
+///
+/// Only configurations that achieve exactly the requested bitrate are considered. Among those, the
+/// one whose sample point is closest to `sample_point` is selected, preferring configurations with
+/// more time quanta per bit. The resynchronization jump width is always set to 1.
+///
+/// `sample_point` is rounded to the nearest permille and passed to [`from_bitrate_permille`].
+pub fn from_bitrate(
+    pclk_hz: u32,
+    bitrate: u32,
+    sample_point: f32,
+) -> Result<BitTiming, BitTimingError> {
+    if !(sample_point > 0.0 && sample_point < 1.0) {
+        return Err(BitTimingError::InvalidSamplePoint);
+    }
+
+    from_bitrate_permille(pclk_hz, bitrate, (sample_point * 1000.0 + 0.5) as u16)
+}
+
+/// Computes a bit timing configuration like [`from_bitrate`], using only integer arithmetic.
+///
+/// `sample_point` is given in permille (eg. `875` for 87.5%). Since this is a `const fn`, it can
+/// be evaluated at compile time, which the [`btr!`][crate::btr] macro does.
+pub const fn from_bitrate_permille(
+    pclk_hz: u32,
+    bitrate: u32,
+    sample_point: u16,
+) -> Result<BitTiming, BitTimingError> {
+    if bitrate == 0 || bitrate > pclk_hz / 3 {
+        return Err(BitTimingError::InvalidBitrate);
+    }
+    if sample_point == 0 || sample_point >= 1000 {
+        return Err(BitTimingError::InvalidSamplePoint);
+    }
+    let sample_point = sample_point as u32;
+
+    // The error of a candidate is `error / (1000 * quanta)`, so both values are kept to compare
+    // candidates without division.
+    let mut best: Option<(BitTiming, u32, u32)> = None;
+    let mut quanta = 1 + MAX_SEG1 as u32 + MAX_SEG2 as u32;
+    // `for` loops and iterator adapters are not available in `const fn`.
+    while quanta >= 3 {
+        // `checked_mul` fails if the divider is too large for any prescaler.
+        if let Some(divider) = bitrate.checked_mul(quanta) {
+            let prescaler = pclk_hz / divider;
+            if prescaler * divider == pclk_hz && prescaler != 0 && prescaler <= MAX_PRESCALER as u32
+            {
+                let sample_quanta = (sample_point * quanta + 500) / 1000;
+                let min_seg1 = max(quanta.saturating_sub(1 + MAX_SEG2 as u32), 1);
+                let max_seg1 = min(quanta - 2, MAX_SEG1 as u32);
+                let seg1 = min(max(sample_quanta.saturating_sub(1), min_seg1), max_seg1);
+                let seg2 = quanta - 1 - seg1;
+                if let Some(timing) = BitTiming::new(prescaler as u16, seg1 as u8, seg2 as u8, 1) {
+                    let actual = 1000 * (1 + seg1);
+                    let target = sample_point * quanta;
+                    let error = actual.abs_diff(target);
+                    let better = match best {
+                        Some((_, best_error, best_quanta)) => {
+                            error * best_quanta < best_error * quanta
+                        }
+                        None => true,
+                    };
+                    if better {
+                        best = Some((timing, error, quanta));
+                    }
+                }
+            }
+        }
+        quanta -= 1;
+    }
+
+    match best {
+        Some((timing, _, _)) => Ok(timing),
+        None => Err(BitTimingError::NoSolution),
+    }
+}
+
+const fn min(a: u32, b: u32) -> u32 {
+    if a < b {
+        a
+    } else {
+        b
+    }
+}
+
+const fn max(a: u32, b: u32) -> u32 {
+    if a > b {
+        a
+    } else {
+        b
+    }
+}
+
+/// Computes the value of the `CAN_BTR` register at compile time.
+///
+/// The sample point is given in percent, and defaults to 87.5 if omitted. The configuration is
+/// computed by [`bit_timing::from_bitrate_permille`][crate::bit_timing::from_bitrate_permille],
+/// and the result can be passed to [`CanBuilder::set_bit_timing`][crate::CanBuilder::set_bit_timing]:
+///
+/// ```
+/// use bxcan::btr;
+///
+/// const BTR_500K: u32 = btr!(pclk = 36_000_000, bitrate = 500_000, sample_point = 87.5);
+/// assert_eq!(BTR_500K, 0x0005_0008);
+/// assert_eq!(btr!(pclk = 8_000_000, bitrate = 1_000_000), 0x0005_0000);
+/// ```
+///
+/// Bitrates that cannot be achieved exactly fail to compile:
+///
+/// ```compile_fail
+/// let btr = bxcan::btr!(pclk = 36_000_000, bitrate = 833_333);
+/// ```
+#[macro_export]
+macro_rules! btr {
+    (pclk = $pclk:expr, bitrate = $bitrate:expr, sample_point = $sample_point:expr $(,)?) => {{
+        const BTR: u32 = match $crate::bit_timing::from_bitrate_permille(
+            $pclk,
+            $bitrate,
+            ($sample_point as f64 * 10.0 + 0.5) as u16,
+        ) {
+            ::core::result::Result::Ok(timing) => timing.btr(),
+            ::core::result::Result::Err($crate::bit_timing::BitTimingError::InvalidBitrate) => {
+                ::core::panic!("invalid bitrate")
+            }
+            ::core::result::Result::Err($crate::bit_timing::BitTimingError::InvalidSamplePoint) => {
+                ::core::panic!("sample point not between 0 and 100")
+            }
+            ::core::result::Result::Err(_) => {
+                ::core::panic!("no bit timing achieves the exact bitrate")
+            }
+        };
+        BTR
+    }};
+    (pclk = $pclk:expr, bitrate = $bitrate:expr $(,)?) => {
+        $crate::btr!(pclk = $pclk, bitrate = $bitrate, sample_point = 87.5)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn btr_roundtrip() {
+        let timing = BitTiming::new(1024, 16, 8, 4).unwrap();
+        assert_eq!(timing.btr(), 0x037f_03ff);
+        assert_eq!(BitTiming::from_btr(timing.btr()), timing);
+
+        let timing = BitTiming::new(1, 1, 1, 1).unwrap();
+        assert_eq!(timing.btr(), 0);
+        assert_eq!(BitTiming::from_btr(0xC000_0000), timing);
+
+        assert_eq!(BitTiming::new(0, 1, 1, 1), None);
+        assert_eq!(BitTiming::new(1, 17, 1, 1), None);
+        assert_eq!(BitTiming::new(1, 1, 9, 1), None);
+        assert_eq!(BitTiming::new(1, 1, 1, 5), None);
+    }
+
+    #[test]
+    fn const_evaluation() {
+        for &(pclk, bitrate) in &[
+            (36_000_000, 500_000),
+            (8_000_000, 1_000_000),
+            (42_000_000, 125_000),
+            (48_000_000, 250_000),
+            (16_000_000, 10_000),
+        ] {
+            assert_eq!(
+                from_bitrate_permille(pclk, bitrate, 875),
+                from_bitrate(pclk, bitrate, 0.875)
+            );
+        }
+        assert_eq!(
+            from_bitrate_permille(8_000_000, 0, 875),
+            Err(BitTimingError::InvalidBitrate)
+        );
+        assert_eq!(
+            from_bitrate_permille(8_000_000, 1_000_000, 1000),
+            Err(BitTimingError::InvalidSamplePoint)
+        );
+
+        const BTR: u32 = btr!(pclk = 36_000_000, bitrate = 500_000, sample_point = 75);
+        assert_eq!(BTR, from_bitrate(36_000_000, 500_000, 0.75).unwrap().btr());
+    }
+
+    #[test]
+    fn peripheral_clock() {
+        struct Clocks {
+            apb1: u32,
+        }
+
+        impl PeripheralClock for Clocks {
+            fn can_clock_hz(&self) -> u32 {
+                self.apb1
+            }
+        }
+
+        let clocks = Clocks { apb1: 36_000_000 };
+        assert_eq!(
+            from_clock(&clocks, 500_000),
+            from_bitrate(36_000_000, 500_000, 0.875)
+        );
+        assert_eq!(
+            from_clock(&8_000_000, 1_000_000).unwrap().btr(),
+            0x0005_0000
+        );
+    }
+
+    #[test]
+    fn common_bitrates() {
+        let timing = from_bitrate(36_000_000, 500_000, 0.875).unwrap();
+        assert_eq!(timing.btr(), 0x0005_0008);
+        assert_eq!(timing.bitrate(36_000_000), 500_000);
+
+        let timing = from_bitrate(8_000_000, 1_000_000, 0.875).unwrap();
+        assert_eq!(timing.btr(), 0x0005_0000);
+        assert_eq!(timing.sample_point(), 0.875);
+
+        let timing = from_bitrate(42_000_000, 125_000, 0.875).unwrap();
+        assert_eq!(timing.bitrate(42_000_000), 125_000);
+        assert!((timing.sample_point() - 0.875).abs() < 0.02);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            from_bitrate(8_000_000, 0, 0.875),
+            Err(BitTimingError::InvalidBitrate)
+        );
+        assert_eq!(
+            from_bitrate(8_000_000, 8_000_000, 0.875),
+            Err(BitTimingError::InvalidBitrate)
+        );
+        assert_eq!(
+            from_bitrate(8_000_000, 500_000, 1.0),
+            Err(BitTimingError::InvalidSamplePoint)
+        );
+        assert_eq!(
+            from_bitrate(8_000_000, 3_000_000 / 7, 0.875),
+            Err(BitTimingError::NoSolution)
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! Forwarding of frames between two CAN instances.
+
+use crate::{Can, Frame, Instance, OverrunError};
+
+/// A gateway that forwards frames between two CAN instances.
+///
+/// Each direction has a callback that is applied to every forwarded frame. It can rewrite the
+/// frame (eg. translate its identifier), or return `None` to drop it.
+///
+/// [`Bridge::forward_a_to_b`] and [`Bridge::forward_b_to_a`] are meant to be called from the
+/// **RX FIFO** interrupt handlers of the respective receiving instance:
+///
+/// ```no_run
+/// # use bxcan::{Instance, RegisterBlock};
+/// # struct CAN1;
+/// # unsafe impl Instance for CAN1 {
+/// #     const REGISTERS: *mut RegisterBlock = 0x4000_6400 as *mut _;
+/// # }
+/// # struct CAN2;
+/// # unsafe impl Instance for CAN2 {
+/// #     const REGISTERS: *mut RegisterBlock = 0x4000_6800 as *mut _;
+/// # }
+/// use bxcan::{Bridge, Can, Frame, Id, StandardId};
+///
+/// type Gateway = Bridge<CAN1, CAN2, fn(Frame) -> Option<Frame>, fn(Frame) -> Option<Frame>>;
+///
+/// fn new_gateway(can1: Can<CAN1>, can2: Can<CAN2>) -> Gateway {
+///     Bridge::new(
+///         can1,
+///         can2,
+///         // Move standard frames from CAN1 to the `0x100..0x200` range on CAN2.
+///         |mut frame| match frame.id() {
+///             Id::Standard(id) if id.as_raw() < 0x100 => {
+///                 frame.set_id(StandardId::new(id.as_raw() + 0x100).unwrap());
+///                 Some(frame)
+///             }
+///             _ => None,
+///         },
+///         // Forward everything from CAN2 to CAN1 unchanged.
+///         Some,
+///     )
+/// }
+///
+/// // Called from the CAN1 RX FIFO interrupt handlers.
+/// fn can1_rx(gateway: &mut Gateway) {
+///     if gateway.forward_a_to_b().is_err() {
+///         // Frames were lost in a receive FIFO of CAN1.
+///     }
+/// }
+/// ```
+pub struct Bridge<A, B, FA, FB>
+where
+    A: Instance,
+    B: Instance,
+{
+    a: Can<A>,
+    b: Can<B>,
+    a_to_b: FA,
+    b_to_a: FB,
+    dropped: u32,
+}
+
+impl<A, B, FA, FB> Bridge<A, B, FA, FB>
+where
+    A: Instance,
+    B: Instance,
+    FA: FnMut(Frame) -> Option<Frame>,
+    FB: FnMut(Frame) -> Option<Frame>,
+{
+    /// Creates a bridge between `a` and `b`.
+    ///
+    /// Frames received by `a` are passed to `a_to_b` and transmitted on `b`, and frames received by
+    /// `b` are passed to `b_to_a` and transmitted on `a`.
+    ///
+    /// Both instances should already be configured and enabled, including their filters.
+    pub fn new(a: Can<A>, b: Can<B>, a_to_b: FA, b_to_a: FB) -> Self {
+        Self {
+            a,
+            b,
+            a_to_b,
+            b_to_a,
+            dropped: 0,
+        }
+    }
+
+    /// Forwards all frames received by instance `a` to instance `b`.
+    ///
+    /// If `a` reports an overrun of one of its receive FIFOs, the error is returned immediately.
+    /// The remaining frames can be forwarded by calling this method again.
+    pub fn forward_a_to_b(&mut self) -> Result<(), OverrunError> {
+        forward(
+            &mut self.a,
+            &mut self.b,
+            &mut self.a_to_b,
+            &mut self.dropped,
+        )
+    }
+
+    /// Forwards all frames received by instance `b` to instance `a`.
+    ///
+    /// See [`Bridge::forward_a_to_b`] for details.
+    pub fn forward_b_to_a(&mut self) -> Result<(), OverrunError> {
+        forward(
+            &mut self.b,
+            &mut self.a,
+            &mut self.b_to_a,
+            &mut self.dropped,
+        )
+    }
+
+    /// Returns the number of frames that were lost because no transmit mailbox was available.
+    ///
+    /// This includes lower priority frames that were dequeued from a full set of mailboxes to make
+    /// room for a forwarded frame. Frames dropped by the callbacks are not counted. The counter
+    /// wraps around at `u32::MAX`.
+    #[inline]
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+
+    /// Returns a mutable reference to instance `a`.
+    #[inline]
+    pub fn a(&mut self) -> &mut Can<A> {
+        &mut self.a
+    }
+
+    /// Returns a mutable reference to instance `b`.
+    #[inline]
+    pub fn b(&mut self) -> &mut Can<B> {
+        &mut self.b
+    }
+
+    /// Destroys the bridge and returns both instances.
+    pub fn free(self) -> (Can<A>, Can<B>) {
+        (self.a, self.b)
+    }
+}
+
+fn forward<R: Instance, T: Instance>(
+    rx: &mut Can<R>,
+    tx: &mut Can<T>,
+    translate: &mut impl FnMut(Frame) -> Option<Frame>,
+    dropped: &mut u32,
+) -> Result<(), OverrunError> {
+    loop {
+        let frame = match rx.receive() {
+            Ok(frame) => frame,
+            Err(nb::Error::WouldBlock) => return Ok(()),
+            Err(nb::Error::Other(e)) => return Err(e),
+        };
+
+        if let Some(frame) = translate(frame) {
+            let lost = match tx.transmit(&frame) {
+                Ok(status) => status.dequeued_frame().is_some(),
+                Err(nb::Error::WouldBlock) => true,
+                Err(nb::Error::Other(infallible)) => match infallible {},
+            };
+            if lost {
+                *dropped = dropped.wrapping_add(1);
+            }
+        }
+    }
+}
@@ -0,0 +1,201 @@
+//! Fan-out of received frames to several consumers.
+
+use core::array;
+
+use crate::counting_queue::{CountingConsumer, CountingProducer, CountingQueue};
+use crate::{Frame, OverrunError};
+
+/// A set of `SUBS` receive buffers that each get a copy of every received frame.
+///
+/// This works like an [`RxRingBuffer`][crate::RxRingBuffer], but with one [`Subscriber`] per
+/// consumer of the frames, eg. one for logging and one for a control task. The
+/// [`BroadcastProducer`] is used by the **RX FIFO** interrupt handler, and each subscriber can be
+/// used by a different context, concurrently and without a critical section.
+///
+/// Each subscriber buffers up to `DEPTH - 1` frames. A subscriber that does not keep up only loses
+/// frames itself: frames that do not fit into its buffer are dropped and counted, see
+/// [`Subscriber::overflows`], while the other subscribers still receive them.
+///
+/// ```no_run
+/// # use bxcan::{Instance, RegisterBlock};
+/// # struct CAN1;
+/// # unsafe impl Instance for CAN1 {
+/// #     const REGISTERS: *mut RegisterBlock = 0x4000_6400 as *mut _;
+/// # }
+/// use bxcan::{Broadcast, BroadcastProducer, Rx0};
+///
+/// static mut BROADCAST: Broadcast<2, 16> = Broadcast::new();
+///
+/// // Called from the RX FIFO 0 interrupt handler.
+/// fn can1_rx0(rx: &mut Rx0<CAN1>, producer: &mut BroadcastProducer<'static, 2, 16>) {
+///     producer.fill(|| rx.receive()).ok();
+/// }
+///
+/// # fn main() {
+/// // Safety: `BROADCAST` is only accessed here, once.
+/// let (producer, [logger, control]) = unsafe { (*core::ptr::addr_of_mut!(BROADCAST)).split() };
+/// // Move `producer` to the interrupt handler, and `logger` and `control` to their tasks.
+/// # }
+/// ```
+pub struct Broadcast<const SUBS: usize, const DEPTH: usize> {
+    queues: [CountingQueue<Frame, DEPTH>; SUBS],
+}
+
+impl<const SUBS: usize, const DEPTH: usize> Broadcast<SUBS, DEPTH> {
+    // Only used to initialize the array, which requires a constant for non-`Copy` types.
+    #[allow(clippy::declare_interior_mutable_const)]
+    const QUEUE: CountingQueue<Frame, DEPTH> = CountingQueue::new();
+
+    /// Creates empty buffers for all subscribers.
+    pub const fn new() -> Self {
+        Self {
+            queues: [Self::QUEUE; SUBS],
+        }
+    }
+
+    /// Splits the broadcast into its producer and the subscribers.
+    pub fn split(
+        &mut self,
+    ) -> (
+        BroadcastProducer<'_, SUBS, DEPTH>,
+        [Subscriber<'_, DEPTH>; SUBS],
+    ) {
+        let mut halves = self.queues.each_mut().map(|queue| {
+            let (producer, consumer) = queue.split();
+            (Some(producer), Some(consumer))
+        });
+        let producers = array::from_fn(|i| halves[i].0.take().unwrap());
+        let subscribers = array::from_fn(|i| Subscriber {
+            consumer: halves[i].1.take().unwrap(),
+        });
+        (BroadcastProducer { producers }, subscribers)
+    }
+}
+
+impl<const SUBS: usize, const DEPTH: usize> Default for Broadcast<SUBS, DEPTH> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The producer half of a [`Broadcast`], used by the interrupt handler.
+pub struct BroadcastProducer<'a, const SUBS: usize, const DEPTH: usize> {
+    producers: [CountingProducer<'a, Frame, DEPTH>; SUBS],
+}
+
+impl<const SUBS: usize, const DEPTH: usize> BroadcastProducer<'_, SUBS, DEPTH> {
+    /// Adds a copy of `frame` to the buffer of every subscriber.
+    ///
+    /// Subscribers whose buffer is full do not receive the frame, and count it as an overflow.
+    /// Returns `true` if all subscribers received the frame.
+    pub fn push(&mut self, frame: &Frame) -> bool {
+        let mut delivered = true;
+        for producer in &mut self.producers {
+            if producer.push(frame.clone()).is_err() {
+                delivered = false;
+            }
+        }
+        delivered
+    }
+
+    /// Copies all frames returned by `receive` to the subscribers, until it returns
+    /// [`WouldBlock`][nb::Error::WouldBlock].
+    ///
+    /// This works like [`RxProducer::fill`][crate::RxProducer::fill]: if `receive` reports an
+    /// overrun of the hardware FIFO, the error is returned immediately.
+    pub fn fill(
+        &mut self,
+        mut receive: impl FnMut() -> nb::Result<Frame, OverrunError>,
+    ) -> Result<(), OverrunError> {
+        loop {
+            match receive() {
+                Ok(frame) => {
+                    self.push(&frame);
+                }
+                Err(nb::Error::WouldBlock) => return Ok(()),
+                Err(nb::Error::Other(e)) => return Err(e),
+            }
+        }
+    }
+}
+
+/// A consumer of a [`Broadcast`], which receives a copy of every frame.
+pub struct Subscriber<'a, const DEPTH: usize> {
+    consumer: CountingConsumer<'a, Frame, DEPTH>,
+}
+
+impl<const DEPTH: usize> Subscriber<'_, DEPTH> {
+    /// Removes the oldest frame from the buffer of this subscriber.
+    #[inline]
+    pub fn pop(&mut self) -> Option<Frame> {
+        self.consumer.pop()
+    }
+
+    /// Returns the number of frames in the buffer of this subscriber.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.consumer.len()
+    }
+
+    /// Returns `true` if the buffer of this subscriber contains no frames.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.consumer.is_empty()
+    }
+
+    /// Returns the total number of frames this subscriber missed because its buffer was full.
+    ///
+    /// The counter wraps around at `u32::MAX`.
+    pub fn overflows(&self) -> u32 {
+        self.consumer.overflows()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Fifo, OverrunMode, StandardId};
+
+    #[test]
+    fn independent_subscribers() {
+        let mut broadcast = Broadcast::<2, 3>::new();
+        let (mut producer, [mut fast, mut slow]) = broadcast.split();
+        let frames = [1, 2, 3].map(|byte| Frame::new_data(StandardId::ZERO, [byte]));
+
+        assert!(producer.push(&frames[0]));
+        assert_eq!(fast.pop(), Some(frames[0].clone()));
+        assert!(producer.push(&frames[1]));
+        assert_eq!(fast.pop(), Some(frames[1].clone()));
+        // `slow` is full now.
+        assert!(!producer.push(&frames[2]));
+        assert_eq!(fast.pop(), Some(frames[2].clone()));
+        assert!(fast.is_empty());
+        assert_eq!(fast.overflows(), 0);
+
+        assert_eq!(slow.len(), 2);
+        assert_eq!(slow.overflows(), 1);
+        assert_eq!(slow.pop(), Some(frames[0].clone()));
+        assert_eq!(slow.pop(), Some(frames[1].clone()));
+        assert_eq!(slow.pop(), None);
+    }
+
+    #[test]
+    fn fill() {
+        let mut broadcast = Broadcast::<3, 4>::new();
+        let (mut producer, subscribers) = broadcast.split();
+        let frame = Frame::new_data(StandardId::ZERO, []);
+
+        let mut results = IntoIterator::into_iter([
+            Ok(frame.clone()),
+            Ok(frame.clone()),
+            Err(nb::Error::WouldBlock),
+        ]);
+        assert_eq!(producer.fill(|| results.next().unwrap()), Ok(()));
+        assert!(subscribers.iter().all(|s| s.len() == 2));
+
+        let overrun = OverrunError::new(Fifo::Fifo0, OverrunMode::Discard);
+        let mut results = IntoIterator::into_iter([Err(nb::Error::Other(overrun))]);
+        assert_eq!(producer.fill(|| results.next().unwrap()), Err(overrun));
+    }
+}
@@ -0,0 +1,203 @@
+//! Bus load estimation.
+//!
+//! [`frame_bits`] computes the number of bits a frame occupies on the bus, and a [`BusLoad`]
+//! accumulates them over a time window to estimate the bus utilization:
+//!
+//! ```
+//! use bxcan::busload::BusLoad;
+//! use bxcan::{Frame, StandardId};
+//! use core::time::Duration;
+//!
+//! let mut load = BusLoad::new(500_000);
+//! for _ in 0..100 {
+//!     // Called for every transmitted and received frame.
+//!     load.record(&Frame::new_data(StandardId::new(0x100).unwrap(), [0; 8]));
+//! }
+//!
+//! // Called when the window has elapsed, eg. from a timer interrupt handler.
+//! let percent = load.percent(Duration::from_millis(100));
+//! load.reset();
+//! assert!(percent > 20.0 && percent < 30.0);
+//! ```
+
+use core::time::Duration;
+
+use crate::{Frame, Id};
+
+/// Number of bits after the CRC sequence, which are not stuffed: CRC delimiter, ACK slot, ACK
+/// delimiter, end of frame and the intermission between frames.
+const TRAILER_BITS: u32 = 1 + 1 + 1 + 7 + 3;
+
+/// Returns the number of bits `frame` occupies on the bus, including stuff bits and the
+/// intermission that follows it.
+///
+/// The stuff bits are computed from the actual contents of the frame, so this is exact for frames
+/// that are transmitted without errors.
+pub fn frame_bits(frame: &Frame) -> u32 {
+    let mut stream = BitStream::new();
+    stream.push(0, 1); // Start of frame
+
+    let rtr = u32::from(frame.is_remote_frame());
+    match frame.id() {
+        Id::Standard(id) => {
+            stream.push(u32::from(id.as_raw()), 11);
+            stream.push(rtr, 1);
+            stream.push(0, 2); // IDE, r0
+        }
+        Id::Extended(id) => {
+            stream.push(id.as_raw() >> 18, 11);
+            stream.push(0b11, 2); // SRR, IDE
+            stream.push(id.as_raw(), 18);
+            stream.push(rtr, 1);
+            stream.push(0, 2); // r1, r0
+        }
+    }
+
+    stream.push(u32::from(frame.raw_dlc()), 4);
+    if let Some(data) = frame.data() {
+        for &byte in data.iter() {
+            stream.push(u32::from(byte), 8);
+        }
+    }
+
+    let crc = u32::from(stream.crc);
+    stream.push(crc, 15);
+
+    stream.bits + stream.stuff_bits + TRAILER_BITS
+}
+
+/// The stuffed section of a frame, from the start of frame to the end of the CRC sequence.
+struct BitStream {
+    bits: u32,
+    stuff_bits: u32,
+    /// Value and length of the current run of identical bits, including stuff bits.
+    last: bool,
+    run: u32,
+    crc: u16,
+}
+
+impl BitStream {
+    fn new() -> Self {
+        Self {
+            bits: 0,
+            stuff_bits: 0,
+            last: false,
+            run: 0,
+            crc: 0,
+        }
+    }
+
+    /// Appends the `len` least significant bits of `value`, most significant bit first.
+    fn push(&mut self, value: u32, len: u32) {
+        for i in (0..len).rev() {
+            let bit = value & (1 << i) != 0;
+
+            // CRC-15 as specified by ISO 11898-1. It is also updated while pushing the CRC
+            // sequence itself, but not used afterwards.
+            let crc_next = bit ^ (self.crc & 0x4000 != 0);
+            self.crc = (self.crc << 1) & 0x7FFF;
+            if crc_next {
+                self.crc ^= 0x4599;
+            }
+
+            if self.run != 0 && bit == self.last {
+                self.run += 1;
+            } else {
+                self.last = bit;
+                self.run = 1;
+            }
+            self.bits += 1;
+
+            if self.run == 5 {
+                // A stuff bit of the opposite value is inserted, and starts a new run.
+                self.stuff_bits += 1;
+                self.last = !self.last;
+                self.run = 1;
+            }
+        }
+    }
+}
+
+/// Accumulates the bits of transmitted and received frames to estimate the bus load.
+///
+/// Every frame seen on the bus should be passed to [`BusLoad::record`]. At the end of each time
+/// window, [`BusLoad::percent`] returns the share of the available bit time that was occupied by
+/// the recorded frames, and [`BusLoad::reset`] starts a new window. See the
+/// [module documentation](self) for an example.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub struct BusLoad {
+    bitrate: u32,
+    bits: u64,
+}
+
+impl BusLoad {
+    /// Creates an accumulator for a bus running at `bitrate` bits per second.
+    pub const fn new(bitrate: u32) -> Self {
+        Self { bitrate, bits: 0 }
+    }
+
+    /// Adds the bits of `frame` to the current window.
+    pub fn record(&mut self, frame: &Frame) {
+        self.bits += u64::from(frame_bits(frame));
+    }
+
+    /// Returns the number of bits recorded in the current window.
+    #[inline]
+    pub fn bits(&self) -> u64 {
+        self.bits
+    }
+
+    /// Returns the bus load in percent, assuming that the current window has lasted for `window`.
+    ///
+    /// Since the recorded frames may not have been sent exactly within the window, the result can
+    /// exceed 100 for short windows.
+    pub fn percent(&self, window: Duration) -> f32 {
+        let available = self.bitrate as f32 * window.as_secs_f32();
+        self.bits as f32 * 100.0 / available
+    }
+
+    /// Starts a new window by clearing the recorded bits.
+    pub fn reset(&mut self) {
+        self.bits = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExtendedId, StandardId};
+
+    #[test]
+    fn stuffing() {
+        // 34 dominant bits from SOF to the end of the CRC (which is 0), with a stuff bit after
+        // every 5 of them.
+        let zero = Frame::new_data(StandardId::ZERO, []);
+        assert_eq!(frame_bits(&zero), 34 + 6 + TRAILER_BITS);
+
+        // Without stuffing, a standard frame takes 47 + 8 * n bits and an extended one 67 + 8 * n.
+        // Stuffing can add at most one bit per 4 bits after the first.
+        for data in [[0x00; 8], [0xFF; 8], [0x55; 8], [0x0F; 8]] {
+            let std = frame_bits(&Frame::new_data(StandardId::MAX, data));
+            assert!((111..=111 + 24).contains(&std), "{}", std);
+            let ext = frame_bits(&Frame::new_data(ExtendedId::MAX, data));
+            assert!((131..=131 + 29).contains(&ext), "{}", ext);
+        }
+
+        let remote = Frame::new_remote(StandardId::ZERO, 8);
+        assert!(frame_bits(&remote) >= 47);
+    }
+
+    #[test]
+    fn percent() {
+        let mut load = BusLoad::new(1_000);
+        let zero = Frame::new_data(StandardId::ZERO, []);
+        for _ in 0..10 {
+            load.record(&zero);
+        }
+        assert_eq!(load.bits(), 530);
+        assert_eq!(load.percent(Duration::from_secs(1)), 53.0);
+        load.reset();
+        assert_eq!(load.bits(), 0);
+    }
+}
@@ -0,0 +1,289 @@
+//! Frame logging in the `candump` log file format.
+//!
+//! The log files written by `candump -l` of the Linux [can-utils] contain one frame per line,
+//! prefixed with a timestamp and the interface name. They can be replayed on a Linux host with
+//! `canplayer`. [`Frame::fmt_candump`] writes frames in this format, so logs captured on the
+//! device (eg. over RTT or a UART) can be replayed directly:
+//!
+//! ```
+//! use bxcan::{Frame, StandardId};
+//! use core::time::Duration;
+//!
+//! let frame = Frame::new_data(StandardId::new(0x123).unwrap(), [0xDE, 0xAD, 0xBE, 0xEF]);
+//! let mut line = heapless::String::<64>::new();
+//! frame.fmt_candump(&mut line, Duration::from_micros(123_456_789)).unwrap();
+//! assert_eq!(line, "(123.456789) can0 123#DEADBEEF\n");
+//! ```
+//!
+//! | Frame                 | Format              |
+//! |-----------------------|---------------------|
+//! | Standard data frame   | `III#DD...`         |
+//! | Extended data frame   | `IIIIIIII#DD...`    |
+//! | Remote frame          | `III#R` or `III#RL` |
+//!
+//! `I` are the hexadecimal digits of the identifier and `DD` the data bytes in hexadecimal. The DLC
+//! `L` of remote frames is omitted if it is 0.
+//!
+//! [`Frame::parse_candump`] parses such lines back, for example in host-side replay tools and
+//! tests:
+//!
+//! ```
+//! use bxcan::{Frame, StandardId};
+//! use core::time::Duration;
+//!
+//! let (frame, timestamp) = Frame::parse_candump("(123.456789) can0 123#DEADBEEF").unwrap();
+//! assert_eq!(frame, Frame::new_data(StandardId::new(0x123).unwrap(), [0xDE, 0xAD, 0xBE, 0xEF]));
+//! assert_eq!(timestamp, Duration::from_micros(123_456_789));
+//! ```
+//!
+//! [can-utils]: https://github.com/linux-can/can-utils
+
+use core::fmt::{self, Write};
+use core::time::Duration;
+
+use crate::{Data, ExtendedId, Frame, Id, StandardId};
+
+/// The interface name written to each line. It can be mapped to a different interface when
+/// replaying, eg. with `canplayer vcan0=can0`.
+const INTERFACE: &str = "can0";
+
+impl Frame {
+    /// Writes this frame to `w` as a line of a `candump` log file, including the terminating
+    /// `\n`.
+    ///
+    /// `timestamp` is written with microsecond resolution. `candump` uses the time since the Unix
+    /// epoch, but any monotonic time base, like the time since startup, works with `canplayer`.
+    /// See the [`candump`](crate::candump) module for details.
+    pub fn fmt_candump(&self, mut w: impl Write, timestamp: Duration) -> fmt::Result {
+        write!(
+            w,
+            "({}.{:06}) {} ",
+            timestamp.as_secs(),
+            timestamp.subsec_micros(),
+            INTERFACE
+        )?;
+        match self.id() {
+            Id::Standard(id) => write!(w, "{:03X}#", id.as_raw())?,
+            Id::Extended(id) => write!(w, "{:08X}#", id.as_raw())?,
+        }
+        match self.data() {
+            Some(data) => {
+                for byte in data.iter() {
+                    write!(w, "{:02X}", byte)?;
+                }
+            }
+            None if self.dlc() == 0 => w.write_char('R')?,
+            None => write!(w, "R{:X}", self.dlc())?,
+        }
+        w.write_char('\n')
+    }
+
+    /// Parses a line of a `candump` log file, and returns the frame and its timestamp.
+    ///
+    /// The interface name is ignored, and a trailing line break is optional. Hexadecimal digits
+    /// may be upper- or lowercase, and the data bytes may be separated by `.`, like `canplayer`
+    /// accepts them. Error frames and CAN FD frames are rejected, since they cannot be represented
+    /// by a [`Frame`].
+    pub fn parse_candump(line: &str) -> Result<(Frame, Duration), ParseError> {
+        let mut fields = line.split_ascii_whitespace();
+        let (timestamp, frame) = match (fields.next(), fields.next(), fields.next(), fields.next())
+        {
+            (Some(timestamp), Some(_interface), Some(frame), None) => (timestamp, frame),
+            _ => return Err(ParseError::Malformed),
+        };
+        let timestamp = parse_timestamp(timestamp).ok_or(ParseError::InvalidTimestamp)?;
+
+        let (id, data) = frame.split_once('#').ok_or(ParseError::Malformed)?;
+        let id: Id = match id.len() {
+            3 => parse_hex(id)
+                .and_then(|id| StandardId::new(id as u16))
+                .ok_or(ParseError::InvalidId)?
+                .into(),
+            8 => parse_hex(id)
+                .and_then(ExtendedId::new)
+                .ok_or(ParseError::InvalidId)?
+                .into(),
+            _ => return Err(ParseError::InvalidId),
+        };
+        Ok((parse_data(id, data)?, timestamp))
+    }
+}
+
+/// Error returned by [`Frame::parse_candump`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum ParseError {
+    /// The line does not consist of a timestamp, an interface name and a frame.
+    Malformed,
+
+    /// The timestamp is not of the form `(seconds.fraction)`.
+    InvalidTimestamp,
+
+    /// The identifier does not have 3 or 8 hexadecimal digits, or is out of range.
+    InvalidId,
+
+    /// The data is not valid hexadecimal, longer than 8 bytes, or belongs to a CAN FD frame.
+    InvalidData,
+}
+
+impl ParseError {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            ParseError::Malformed => "malformed candump line",
+            ParseError::InvalidTimestamp => "invalid timestamp",
+            ParseError::InvalidId => "invalid identifier",
+            ParseError::InvalidData => "invalid frame data",
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+fn parse_timestamp(timestamp: &str) -> Option<Duration> {
+    let timestamp = timestamp.strip_prefix('(')?.strip_suffix(')')?;
+    let (secs, fraction) = timestamp.split_once('.')?;
+    if secs.is_empty() || !secs.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    if !(1..=9).contains(&fraction.len()) || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let secs = secs.parse().ok()?;
+    // Scale the fraction to nanoseconds.
+    let nanos = fraction.parse::<u32>().ok()? * 10u32.pow(9 - fraction.len() as u32);
+    Some(Duration::new(secs, nanos))
+}
+
+fn parse_data(id: Id, data: &str) -> Result<Frame, ParseError> {
+    if let Some(dlc) = data.strip_prefix('R') {
+        let dlc = match dlc.len() {
+            0 => 0,
+            1 => parse_hex(dlc).ok_or(ParseError::InvalidData)?,
+            _ => return Err(ParseError::InvalidData),
+        };
+        if dlc > 8 {
+            return Err(ParseError::InvalidData);
+        }
+        return Ok(Frame::new_remote(id, dlc as u8));
+    }
+
+    let mut bytes = [0; 8];
+    let mut len = 0;
+    let mut digits = data.bytes().filter(|&b| b != b'.');
+    while let Some(high) = digits.next() {
+        let low = digits.next().ok_or(ParseError::InvalidData)?;
+        let byte = bytes.get_mut(len).ok_or(ParseError::InvalidData)?;
+        *byte = parse_hex_digit(high)
+            .zip(parse_hex_digit(low))
+            .map(|(high, low)| (high << 4 | low) as u8)
+            .ok_or(ParseError::InvalidData)?;
+        len += 1;
+    }
+    let data = Data::new(&bytes[..len]).ok_or(ParseError::InvalidData)?;
+    Ok(Frame::new_data(id, data))
+}
+
+fn parse_hex(digits: &str) -> Option<u32> {
+    digits
+        .bytes()
+        .try_fold(0, |value, digit| Some(value << 4 | parse_hex_digit(digit)?))
+}
+
+fn parse_hex_digit(digit: u8) -> Option<u32> {
+    (digit as char).to_digit(16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExtendedId, StandardId};
+
+    fn format(frame: Frame, timestamp: Duration) -> heapless::String<64> {
+        let mut line = heapless::String::new();
+        frame.fmt_candump(&mut line, timestamp).unwrap();
+        line
+    }
+
+    #[test]
+    fn fmt_candump() {
+        assert_eq!(
+            format(Frame::new_data(StandardId::ZERO, []), Duration::ZERO),
+            "(0.000000) can0 000#\n"
+        );
+        assert_eq!(
+            format(
+                Frame::new_data(ExtendedId::MAX, [0x01, 0xAB]),
+                Duration::new(1_436_509_052, 249_713_999)
+            ),
+            "(1436509052.249713) can0 1FFFFFFF#01AB\n"
+        );
+        assert_eq!(
+            format(
+                Frame::new_remote(StandardId::new(0x42).unwrap(), 0),
+                Duration::from_secs(1)
+            ),
+            "(1.000000) can0 042#R\n"
+        );
+        assert_eq!(
+            format(
+                Frame::new_remote(ExtendedId::new(0x42).unwrap(), 8),
+                Duration::from_secs(1)
+            ),
+            "(1.000000) can0 00000042#R8\n"
+        );
+    }
+
+    #[test]
+    fn parse_candump() {
+        let frames = [
+            Frame::new_data(
+                StandardId::MAX,
+                [0x01, 0x23, 0x45, 0x67, 0x89, 0xAB, 0xCD, 0xEF],
+            ),
+            Frame::new_data(ExtendedId::ZERO, []),
+            Frame::new_remote(StandardId::new(0x42).unwrap(), 0),
+            Frame::new_remote(ExtendedId::MAX, 5),
+        ];
+        let timestamp = Duration::new(1_436_509_052, 249_713_000);
+        for frame in frames {
+            let line = format(frame.clone(), timestamp);
+            assert_eq!(Frame::parse_candump(&line), Ok((frame, timestamp)));
+        }
+
+        let frame = Frame::new_data(StandardId::new(0x2bc).unwrap(), [0xEF, 0x01]);
+        assert_eq!(
+            Frame::parse_candump("(1.5) vcan0 2bc#ef.01"),
+            Ok((frame, Duration::from_millis(1500)))
+        );
+    }
+
+    #[test]
+    fn parse_candump_invalid() {
+        let parse = |line| Frame::parse_candump(line).map(|_| ());
+        assert_eq!(parse(""), Err(ParseError::Malformed));
+        assert_eq!(parse("(1.0) can0"), Err(ParseError::Malformed));
+        assert_eq!(parse("(1.0) can0 123#00 R"), Err(ParseError::Malformed));
+        assert_eq!(parse("(1.0) can0 12300"), Err(ParseError::Malformed));
+        assert_eq!(parse("1.0 can0 123#"), Err(ParseError::InvalidTimestamp));
+        assert_eq!(parse("(1) can0 123#"), Err(ParseError::InvalidTimestamp));
+        assert_eq!(parse("(1.+0) can0 123#"), Err(ParseError::InvalidTimestamp));
+        assert_eq!(parse("(1.0) can0 800#"), Err(ParseError::InvalidId));
+        assert_eq!(parse("(1.0) can0 20000000#"), Err(ParseError::InvalidId));
+        assert_eq!(parse("(1.0) can0 1234#"), Err(ParseError::InvalidId));
+        assert_eq!(parse("(1.0) can0 123#0"), Err(ParseError::InvalidData));
+        assert_eq!(parse("(1.0) can0 123#0G"), Err(ParseError::InvalidData));
+        assert_eq!(parse("(1.0) can0 123#R9"), Err(ParseError::InvalidData));
+        assert_eq!(
+            parse("(1.0) can0 123#000000000000000000"),
+            Err(ParseError::InvalidData)
+        );
+        // CAN FD frame.
+        assert_eq!(parse("(1.0) can0 123##100"), Err(ParseError::InvalidData));
+    }
+}
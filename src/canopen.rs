@@ -0,0 +1,276 @@
+//! CANopen COB-IDs and frame constructors.
+//!
+//! CANopen communication objects use standard identifiers (COB-IDs) made up of a 4-bit
+//! [`FunctionCode`] and a 7-bit [`NodeId`]. The functions in this module build the frames of the
+//! predefined connection set:
+//!
+//! ```
+//! use bxcan::canopen::{self, FunctionCode, NmtState, NodeId};
+//!
+//! let node = NodeId::new(0x10).unwrap();
+//! let frame = canopen::heartbeat(node, NmtState::Operational);
+//! assert_eq!(frame.id(), FunctionCode::Heartbeat.cob_id(node).into());
+//! assert_eq!(&frame.data().unwrap()[..], [0x05]);
+//!
+//! let frame = canopen::pdo(FunctionCode::Tpdo1, node, [1, 2, 3, 4]).unwrap();
+//! assert_eq!(canopen::parse_cob_id(frame.id()), Some((FunctionCode::Tpdo1, Some(node))));
+//! ```
+
+use crate::{Data, Frame, Id, StandardId};
+
+/// A CANopen node ID (`1..=127`).
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub struct NodeId(u8);
+
+impl NodeId {
+    /// Tries to create a `NodeId` from a raw 8-bit integer.
+    ///
+    /// This will return `None` if `raw` is not in the valid range `1..=127`.
+    #[inline]
+    pub const fn new(raw: u8) -> Option<Self> {
+        if raw >= 1 && raw <= 127 {
+            Some(Self(raw))
+        } else {
+            None
+        }
+    }
+
+    /// Returns this node ID as a raw 8-bit integer.
+    #[inline]
+    pub fn as_raw(&self) -> u8 {
+        self.0
+    }
+}
+
+/// The function code in the upper 4 bits of a COB-ID.
+///
+/// The discriminants are the values of the function code.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum FunctionCode {
+    /// Network management, sent by the NMT master (COB-ID `0x000`).
+    Nmt = 0x0,
+    /// SYNC (COB-ID `0x080`) without a node ID, or emergency (EMCY, `0x080 + node`) with one.
+    SyncEmergency = 0x1,
+    /// Time stamp (COB-ID `0x100`).
+    Time = 0x2,
+    /// Transmit PDO 1 (`0x180 + node`).
+    Tpdo1 = 0x3,
+    /// Receive PDO 1 (`0x200 + node`).
+    Rpdo1 = 0x4,
+    /// Transmit PDO 2 (`0x280 + node`).
+    Tpdo2 = 0x5,
+    /// Receive PDO 2 (`0x300 + node`).
+    Rpdo2 = 0x6,
+    /// Transmit PDO 3 (`0x380 + node`).
+    Tpdo3 = 0x7,
+    /// Receive PDO 3 (`0x400 + node`).
+    Rpdo3 = 0x8,
+    /// Transmit PDO 4 (`0x480 + node`).
+    Tpdo4 = 0x9,
+    /// Receive PDO 4 (`0x500 + node`).
+    Rpdo4 = 0xA,
+    /// SDO response, sent by the server (`0x580 + node`).
+    SdoResponse = 0xB,
+    /// SDO request, sent by the client (`0x600 + node`).
+    SdoRequest = 0xC,
+    /// NMT error control, ie. heartbeat and boot-up messages (`0x700 + node`).
+    Heartbeat = 0xE,
+}
+
+impl FunctionCode {
+    /// Returns the COB-ID of this function for the node `node`.
+    pub fn cob_id(self, node: NodeId) -> StandardId {
+        // Safety: 4-bit function code and 7-bit node ID.
+        unsafe { StandardId::new_unchecked((self as u16) << 7 | u16::from(node.0)) }
+    }
+
+    /// Returns the COB-ID of this function without a node ID, as used by NMT, SYNC and TIME.
+    pub fn broadcast_cob_id(self) -> StandardId {
+        // Safety: 4-bit function code.
+        unsafe { StandardId::new_unchecked((self as u16) << 7) }
+    }
+
+    fn from_bits(bits: u16) -> Option<Self> {
+        Some(match bits {
+            0x0 => Self::Nmt,
+            0x1 => Self::SyncEmergency,
+            0x2 => Self::Time,
+            0x3 => Self::Tpdo1,
+            0x4 => Self::Rpdo1,
+            0x5 => Self::Tpdo2,
+            0x6 => Self::Rpdo2,
+            0x7 => Self::Tpdo3,
+            0x8 => Self::Rpdo3,
+            0x9 => Self::Tpdo4,
+            0xA => Self::Rpdo4,
+            0xB => Self::SdoResponse,
+            0xC => Self::SdoRequest,
+            0xE => Self::Heartbeat,
+            _ => return None,
+        })
+    }
+
+    fn is_pdo(self) -> bool {
+        (Self::Tpdo1 as u8..=Self::Rpdo4 as u8).contains(&(self as u8))
+    }
+}
+
+/// Splits a COB-ID into its function code and node ID.
+///
+/// Returns `None` for extended identifiers and for codes not used by the predefined connection
+/// set. The node ID is `None` if it is 0, as for NMT, SYNC and TIME objects.
+pub fn parse_cob_id(id: impl Into<Id>) -> Option<(FunctionCode, Option<NodeId>)> {
+    let raw = match id.into() {
+        Id::Standard(id) => id.as_raw(),
+        Id::Extended(_) => return None,
+    };
+    let function = FunctionCode::from_bits(raw >> 7)?;
+    Some((function, NodeId::new((raw & 0x7F) as u8)))
+}
+
+/// NMT commands sent by the NMT master.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub enum NmtCommand {
+    /// Start the node (enter the operational state).
+    Start = 0x01,
+    /// Stop the node.
+    Stop = 0x02,
+    /// Enter the pre-operational state.
+    EnterPreOperational = 0x80,
+    /// Reset the application of the node.
+    ResetNode = 0x81,
+    /// Reset the communication parameters of the node.
+    ResetCommunication = 0x82,
+}
+
+/// NMT states reported in heartbeat messages.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub enum NmtState {
+    /// The node has finished initialization (boot-up message).
+    BootUp = 0x00,
+    /// The node is stopped.
+    Stopped = 0x04,
+    /// The node is operational.
+    Operational = 0x05,
+    /// The node is pre-operational.
+    PreOperational = 0x7F,
+}
+
+/// Creates an NMT command frame addressed to `node`, or to all nodes if `node` is `None`.
+pub fn nmt(command: NmtCommand, node: Option<NodeId>) -> Frame {
+    let node = node.map_or(0, |node| node.0);
+    Frame::new_data(FunctionCode::Nmt.broadcast_cob_id(), [command as u8, node])
+}
+
+/// Creates a SYNC frame.
+pub fn sync() -> Frame {
+    Frame::new_data(FunctionCode::SyncEmergency.broadcast_cob_id(), [])
+}
+
+/// Creates a heartbeat frame reporting the NMT `state` of `node`.
+pub fn heartbeat(node: NodeId, state: NmtState) -> Frame {
+    Frame::new_data(FunctionCode::Heartbeat.cob_id(node), [state as u8])
+}
+
+/// Creates an emergency (EMCY) frame.
+///
+/// `error_code` is the 16-bit emergency error code and `error_register` the value of object
+/// `0x1001`. They are followed by 5 bytes of manufacturer-specific data.
+pub fn emergency(
+    node: NodeId,
+    error_code: u16,
+    error_register: u8,
+    manufacturer_data: [u8; 5],
+) -> Frame {
+    let [code_lo, code_hi] = error_code.to_le_bytes();
+    let [m0, m1, m2, m3, m4] = manufacturer_data;
+    Frame::new_data(
+        FunctionCode::SyncEmergency.cob_id(node),
+        [code_lo, code_hi, error_register, m0, m1, m2, m3, m4],
+    )
+}
+
+/// Creates a PDO frame.
+///
+/// Returns `None` if `function` is not one of the PDO function codes.
+pub fn pdo(function: FunctionCode, node: NodeId, data: impl Into<Data>) -> Option<Frame> {
+    if function.is_pdo() {
+        Some(Frame::new_data(function.cob_id(node), data))
+    } else {
+        None
+    }
+}
+
+/// Creates an SDO request frame sent by a client to the SDO server of `node`.
+pub fn sdo_request(node: NodeId, data: [u8; 8]) -> Frame {
+    Frame::new_data(FunctionCode::SdoRequest.cob_id(node), data)
+}
+
+/// Creates an SDO response frame sent by the SDO server of `node`.
+pub fn sdo_response(node: NodeId, data: [u8; 8]) -> Frame {
+    Frame::new_data(FunctionCode::SdoResponse.cob_id(node), data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cob_ids() {
+        let node = NodeId::new(0x7F).unwrap();
+        assert_eq!(NodeId::new(0), None);
+        assert_eq!(NodeId::new(0x80), None);
+
+        assert_eq!(FunctionCode::Nmt.broadcast_cob_id().as_raw(), 0x000);
+        assert_eq!(FunctionCode::SyncEmergency.cob_id(node).as_raw(), 0x0FF);
+        assert_eq!(FunctionCode::Tpdo1.cob_id(node).as_raw(), 0x1FF);
+        assert_eq!(FunctionCode::Rpdo4.cob_id(node).as_raw(), 0x57F);
+        assert_eq!(FunctionCode::SdoResponse.cob_id(node).as_raw(), 0x5FF);
+        assert_eq!(FunctionCode::SdoRequest.cob_id(node).as_raw(), 0x67F);
+        assert_eq!(FunctionCode::Heartbeat.cob_id(node).as_raw(), 0x77F);
+
+        assert_eq!(
+            parse_cob_id(StandardId::new(0x080).unwrap()),
+            Some((FunctionCode::SyncEmergency, None))
+        );
+        assert_eq!(
+            parse_cob_id(StandardId::new(0x5FF).unwrap()),
+            Some((FunctionCode::SdoResponse, Some(node)))
+        );
+        assert_eq!(parse_cob_id(StandardId::new(0x680).unwrap()), None);
+        assert_eq!(parse_cob_id(crate::ExtendedId::new(0x080).unwrap()), None);
+    }
+
+    #[test]
+    fn frames() {
+        let node = NodeId::new(5).unwrap();
+
+        let frame = nmt(NmtCommand::Start, Some(node));
+        assert_eq!(frame.id(), StandardId::ZERO.into());
+        assert_eq!(&frame.data().unwrap()[..], [0x01, 5]);
+        assert_eq!(
+            &nmt(NmtCommand::ResetNode, None).data().unwrap()[..],
+            [0x81, 0]
+        );
+
+        assert_eq!(&sync().data().unwrap()[..], []);
+
+        let frame = emergency(node, 0x8130, 0x11, [0; 5]);
+        assert_eq!(frame.id(), StandardId::new(0x085).unwrap().into());
+        assert_eq!(
+            &frame.data().unwrap()[..],
+            [0x30, 0x81, 0x11, 0, 0, 0, 0, 0]
+        );
+
+        assert!(pdo(FunctionCode::Rpdo2, node, [1]).is_some());
+        assert!(pdo(FunctionCode::SdoRequest, node, [1]).is_none());
+
+        let frame = sdo_request(node, [0x40, 0x00, 0x10, 0, 0, 0, 0, 0]);
+        assert_eq!(frame.id(), StandardId::new(0x605).unwrap().into());
+    }
+}
@@ -0,0 +1,222 @@
+//! Management of a split peripheral.
+
+use crate::filter::{BankConfig, FilterHandle, FiltersFull, MasterFilters, StaleFilterHandle};
+use crate::{
+    Can, CanConfig, ErrorState, Fifo, FilterOwner, Instance, Interrupt, Interrupts, Lec, Mode, Rx0,
+    Rx1, Tx,
+};
+
+impl<I: Instance> Can<I> {
+    /// Consumes this `Can` instance and splits it into transmitting and receiving halves, and a
+    /// [`Control`] handle for managing the peripheral.
+    ///
+    /// Like with [`Can::split`], the halves can be moved to the interrupt handlers, while the
+    /// main loop keeps the [`Control`] handle to change the mode, query the error state, modify
+    /// the filters and enable or disable interrupts.
+    pub fn split_with_control(self) -> (Tx<I>, Rx0<I>, Rx1<I>, Control<I>) {
+        // Safety: `self` is moved into the `Control`, which does not give access to the transmit
+        // mailboxes or receive FIFOs.
+        let (tx, rx0, rx1) = unsafe { (Tx::conjure(), Rx0::conjure(), Rx1::conjure()) };
+        (tx, rx0, rx1, Control { can: self })
+    }
+
+    /// Reunites the parts returned by [`Can::split_with_control`] into a `Can` instance.
+    ///
+    /// This makes the methods that need the whole peripheral, like [`Can::free`], available again.
+    /// The peripheral keeps running, and frames pending in its mailboxes and FIFOs are kept.
+    ///
+    /// Since the [`Tx`] half does not track cookies, the cookies of all pending frames are reset to
+    /// 0 (see [`Can::transmit_with_cookie`]). The error state tracked by [`Control`] is kept.
+    /// Overruns and statistics only include what was handled through [`Control`] and the `Can`
+    /// instance before the split, since the halves don't record them.
+    pub fn join(tx: Tx<I>, rx0: Rx0<I>, rx1: Rx1<I>, control: Control<I>) -> Can<I> {
+        // The halves are zero-sized and don't hold any state.
+        let _ = (tx, rx0, rx1);
+        let mut can = control.can;
+        can.cookies = [0; 3];
+        can
+    }
+}
+
+/// Handle for managing a peripheral whose transmitting and receiving halves are used elsewhere.
+///
+/// Returned by [`Can::split_with_control`], and turned back into a [`Can`] by [`Can::join`]. The
+/// methods behave like the [`Can`] methods of the same name.
+pub struct Control<I: Instance> {
+    can: Can<I>,
+}
+
+impl<I: Instance> Control<I> {
+    /// Returns a reference to the peripheral instance.
+    #[inline]
+    pub fn instance(&mut self) -> &mut I {
+        self.can.instance()
+    }
+
+    /// Configure bit timings and the mode of the peripheral.
+    ///
+    /// See [`Can::modify_config`] for details.
+    #[inline]
+    pub fn modify_config(&mut self) -> CanConfig<'_, I> {
+        self.can.modify_config()
+    }
+
+    /// Changes the mode at runtime.
+    ///
+    /// See [`Can::set_mode`] for details.
+    #[inline]
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.can.set_mode(mode)
+    }
+
+    /// Returns the currently configured mode.
+    #[inline]
+    pub fn mode(&self) -> Mode {
+        self.can.mode()
+    }
+
+    /// Configures the automatic wake-up feature.
+    ///
+    /// See [`Can::set_automatic_wakeup`] for details.
+    #[inline]
+    pub fn set_automatic_wakeup(&mut self, enabled: bool) {
+        self.can.set_automatic_wakeup(enabled)
+    }
+
+    /// Puts the peripheral in a sleep mode to save power.
+    ///
+    /// See [`Can::sleep`] for details.
+    #[inline]
+    pub fn sleep(&mut self) {
+        self.can.sleep()
+    }
+
+    /// Wakes up from sleep mode.
+    ///
+    /// See [`Can::wakeup`] for details.
+    #[inline]
+    pub fn wakeup(&mut self) {
+        self.can.wakeup()
+    }
+
+    /// Returns `true` if the peripheral is in sleep mode.
+    #[inline]
+    pub fn is_sleeping(&self) -> bool {
+        self.can.is_sleeping()
+    }
+
+    /// Starts listening for a CAN interrupt.
+    #[inline]
+    pub fn enable_interrupt(&mut self, interrupt: Interrupt) {
+        self.can.enable_interrupt(interrupt)
+    }
+
+    /// Starts listening for a set of CAN interrupts.
+    #[inline]
+    pub fn enable_interrupts(&mut self, interrupts: Interrupts) {
+        self.can.enable_interrupts(interrupts)
+    }
+
+    /// Stops listening for a CAN interrupt.
+    #[inline]
+    pub fn disable_interrupt(&mut self, interrupt: Interrupt) {
+        self.can.disable_interrupt(interrupt)
+    }
+
+    /// Stops listening for a set of CAN interrupts.
+    #[inline]
+    pub fn disable_interrupts(&mut self, interrupts: Interrupts) {
+        self.can.disable_interrupts(interrupts)
+    }
+
+    /// Returns the set of interrupt conditions that are currently active.
+    ///
+    /// See [`Can::pending_interrupts`] for details.
+    #[inline]
+    pub fn pending_interrupts(&self) -> Interrupts {
+        self.can.pending_interrupts()
+    }
+
+    /// Returns the current fault confinement state of the peripheral.
+    #[inline]
+    pub fn error_state(&self) -> ErrorState {
+        self.can.error_state()
+    }
+
+    /// Returns the value of the transmit error counter (TEC).
+    ///
+    /// See [`Can::transmit_error_counter`] for details.
+    #[inline]
+    pub fn transmit_error_counter(&self) -> u8 {
+        self.can.transmit_error_counter()
+    }
+
+    /// Returns the value of the receive error counter (REC).
+    ///
+    /// See [`Can::receive_error_counter`] for details.
+    #[inline]
+    pub fn receive_error_counter(&self) -> u8 {
+        self.can.receive_error_counter()
+    }
+
+    /// Returns the code of the last bus error detected by the peripheral.
+    ///
+    /// See [`Can::last_error_code`] for details.
+    #[inline]
+    pub fn last_error_code(&self) -> Option<Lec> {
+        self.can.last_error_code()
+    }
+
+    /// Resets the last error code to "no error".
+    #[inline]
+    pub fn clear_last_error_code(&mut self) {
+        self.can.clear_last_error_code()
+    }
+
+    /// Returns the new [`ErrorState`] if it has changed since the previous call.
+    ///
+    /// See [`Can::on_error_state_change`] for details.
+    #[inline]
+    pub fn on_error_state_change(&mut self) -> Option<ErrorState> {
+        self.can.on_error_state_change()
+    }
+
+    /// Initiates recovery from the bus-off state.
+    ///
+    /// See [`Can::recover_from_bus_off`] for details.
+    #[inline]
+    pub fn recover_from_bus_off(&mut self) {
+        self.can.recover_from_bus_off()
+    }
+}
+
+impl<I: FilterOwner> Control<I> {
+    /// Accesses the filter banks owned by this CAN peripheral.
+    ///
+    /// See [`Can::modify_filters`] for details.
+    #[inline]
+    pub fn modify_filters(&mut self) -> MasterFilters<'_, I> {
+        self.can.modify_filters()
+    }
+
+    /// Enables a filter bank with `config`, without disturbing the reception of frames through the
+    /// other banks.
+    ///
+    /// See [`Can::add_filter`] for details.
+    #[inline]
+    pub fn add_filter(
+        &mut self,
+        fifo: Fifo,
+        config: impl Into<BankConfig>,
+    ) -> Result<FilterHandle, FiltersFull> {
+        self.can.add_filter(fifo, config)
+    }
+
+    /// Disables a filter bank enabled by [`Control::add_filter`].
+    ///
+    /// See [`Can::remove_filter`] for details.
+    #[inline]
+    pub fn remove_filter(&mut self, handle: FilterHandle) -> Result<(), StaleFilterHandle> {
+        self.can.remove_filter(handle)
+    }
+}
@@ -0,0 +1,122 @@
+//! Lock-free queue that counts the items it had to drop.
+//!
+//! This is the building block of [`RxRingBuffer`], [`TxCompletionQueue`] and [`Broadcast`], which
+//! wrap it with the API for their item type.
+//!
+//! [`RxRingBuffer`]: crate::RxRingBuffer
+//! [`TxCompletionQueue`]: crate::TxCompletionQueue
+//! [`Broadcast`]: crate::Broadcast
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use heapless::spsc::{Consumer, Producer, Queue};
+
+/// A single-producer single-consumer queue holding up to `N - 1` items, which counts the items
+/// pushed while it is full.
+pub(crate) struct CountingQueue<T, const N: usize> {
+    queue: Queue<T, N>,
+    overflows: AtomicU32,
+}
+
+impl<T, const N: usize> CountingQueue<T, N> {
+    pub(crate) const fn new() -> Self {
+        Self {
+            queue: Queue::new(),
+            overflows: AtomicU32::new(0),
+        }
+    }
+
+    pub(crate) fn split(&mut self) -> (CountingProducer<'_, T, N>, CountingConsumer<'_, T, N>) {
+        let (producer, consumer) = self.queue.split();
+        (
+            CountingProducer {
+                producer,
+                overflows: &self.overflows,
+            },
+            CountingConsumer {
+                consumer,
+                overflows: &self.overflows,
+            },
+        )
+    }
+}
+
+pub(crate) struct CountingProducer<'a, T, const N: usize> {
+    producer: Producer<'a, T, N>,
+    overflows: &'a AtomicU32,
+}
+
+impl<T, const N: usize> CountingProducer<'_, T, N> {
+    /// Adds an item to the queue, or returns it in `Err` and counts an overflow if the queue is
+    /// full.
+    pub(crate) fn push(&mut self, item: T) -> Result<(), T> {
+        let result = self.producer.enqueue(item);
+        if result.is_err() {
+            // This is the only writer, so no atomic read-modify-write is needed (which is not
+            // available on all targets).
+            let overflows = self.overflows.load(Ordering::Relaxed);
+            self.overflows
+                .store(overflows.wrapping_add(1), Ordering::Relaxed);
+        }
+        result
+    }
+
+    #[inline]
+    pub(crate) fn ready(&self) -> bool {
+        self.producer.ready()
+    }
+}
+
+pub(crate) struct CountingConsumer<'a, T, const N: usize> {
+    consumer: Consumer<'a, T, N>,
+    overflows: &'a AtomicU32,
+}
+
+impl<T, const N: usize> CountingConsumer<'_, T, N> {
+    #[inline]
+    pub(crate) fn pop(&mut self) -> Option<T> {
+        self.consumer.dequeue()
+    }
+
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        self.consumer.len()
+    }
+
+    #[inline]
+    pub(crate) fn is_empty(&self) -> bool {
+        !self.consumer.ready()
+    }
+
+    /// Returns the number of dropped items, wrapping around at `u32::MAX`.
+    pub(crate) fn overflows(&self) -> u32 {
+        self.overflows.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overflow() {
+        let mut queue = CountingQueue::<u8, 3>::new();
+        let (mut producer, mut consumer) = queue.split();
+
+        assert_eq!(producer.push(1), Ok(()));
+        assert_eq!(producer.push(2), Ok(()));
+        assert!(!producer.ready());
+        assert_eq!(producer.push(3), Err(3));
+        assert_eq!(producer.push(4), Err(4));
+        assert_eq!(consumer.len(), 2);
+        assert_eq!(consumer.overflows(), 2);
+
+        assert_eq!(consumer.pop(), Some(1));
+        assert_eq!(producer.push(5), Ok(()));
+        assert_eq!(consumer.pop(), Some(2));
+        assert_eq!(consumer.pop(), Some(5));
+        assert_eq!(consumer.pop(), None);
+        assert!(consumer.is_empty());
+        assert_eq!(consumer.overflows(), 2);
+    }
+}
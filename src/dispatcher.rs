@@ -0,0 +1,228 @@
+//! Identifier-based dispatch of received frames.
+
+use heapless::Vec;
+
+use crate::{Frame, Id};
+
+/// A routing table that dispatches received frames to handlers by identifier.
+///
+/// Each route maps an identifier, or an inclusive range of identifiers, to a handler of type `H`.
+/// Up to `N` routes can be added. The handler can be anything the application uses to process a
+/// frame, eg. a function pointer, an index into a table of closures, or an enum of tasks.
+///
+/// ```
+/// use bxcan::{extended_id, standard_id, Dispatcher, Frame};
+///
+/// fn heartbeat(frame: &Frame) { /* ... */ }
+/// fn diagnostics(frame: &Frame) { /* ... */ }
+/// fn unhandled(frame: &Frame) { /* ... */ }
+///
+/// let mut dispatcher = Dispatcher::<fn(&Frame), 4>::new();
+/// dispatcher.add(standard_id!(0x700), heartbeat).unwrap();
+/// dispatcher
+///     .add_range(extended_id!(0x18DA_0000), extended_id!(0x18DA_FFFF), diagnostics)
+///     .unwrap();
+///
+/// // Called for every received frame, eg. from the RX FIFO interrupt handler.
+/// let frame = Frame::new_data(standard_id!(0x700), [0x05]);
+/// dispatcher.dispatch(&frame, |handler, frame| handler(frame), unhandled);
+/// ```
+pub struct Dispatcher<H, const N: usize> {
+    routes: Vec<Route<H>, N>,
+}
+
+struct Route<H> {
+    first: Id,
+    last: Id,
+    handler: H,
+}
+
+impl<H, const N: usize> Dispatcher<H, N> {
+    /// Creates a dispatcher without any routes.
+    pub const fn new() -> Self {
+        Self { routes: Vec::new() }
+    }
+
+    /// Routes frames with `id` to `handler`.
+    ///
+    /// Returns `Err` with `handler` if the maximum number of routes has been added already.
+    pub fn add(&mut self, id: impl Into<Id>, handler: H) -> Result<(), H> {
+        let id = id.into();
+        self.push(id, id, handler)
+    }
+
+    /// Routes frames with an identifier between `first` and `last` (inclusive) to `handler`.
+    ///
+    /// Returns `Err` with `handler` if the maximum number of routes has been added already.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if `first` and `last` are not both standard or both extended identifiers, or
+    /// if `first` is greater than `last`.
+    pub fn add_range(
+        &mut self,
+        first: impl Into<Id>,
+        last: impl Into<Id>,
+        handler: H,
+    ) -> Result<(), H> {
+        let (first, last) = (first.into(), last.into());
+        assert!(
+            matches!(
+                (first, last),
+                (Id::Standard(_), Id::Standard(_)) | (Id::Extended(_), Id::Extended(_))
+            ),
+            "identifiers must be of the same kind"
+        );
+        assert!(
+            first <= last,
+            "first identifier must not be greater than last"
+        );
+
+        self.push(first, last, handler)
+    }
+
+    fn push(&mut self, first: Id, last: Id, handler: H) -> Result<(), H> {
+        self.routes
+            .push(Route {
+                first,
+                last,
+                handler,
+            })
+            .map_err(|route| route.handler)
+    }
+
+    /// Removes all routes.
+    pub fn clear(&mut self) {
+        self.routes.clear();
+    }
+
+    /// Returns the handler of the first route that matches `id`.
+    ///
+    /// Routes are matched in the order they were added, so more specific routes should be added
+    /// before overlapping ranges.
+    pub fn lookup(&self, id: impl Into<Id>) -> Option<&H> {
+        let id = id.into();
+        self.routes
+            .iter()
+            .find(|route| (route.first..=route.last).contains(&id))
+            .map(|route| &route.handler)
+    }
+
+    /// Dispatches `frame` to the handler of the first matching route, or to `default` if no route
+    /// matches.
+    ///
+    /// `handle` is called with the handler and the frame, and is typically a closure invoking the
+    /// handler. Returns the value returned by `handle` or `default`.
+    pub fn dispatch<R>(
+        &self,
+        frame: &Frame,
+        handle: impl FnOnce(&H, &Frame) -> R,
+        default: impl FnOnce(&Frame) -> R,
+    ) -> R {
+        match self.lookup(frame.id()) {
+            Some(handler) => handle(handler, frame),
+            None => default(frame),
+        }
+    }
+
+    /// Returns the number of routes.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    /// Returns `true` if no routes have been added.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+}
+
+impl<H, const N: usize> Default for Dispatcher<H, N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExtendedId, StandardId};
+
+    #[derive(Debug, Copy, Clone, PartialEq)]
+    enum Task {
+        Heartbeat,
+        Sensors,
+        Diagnostics,
+    }
+
+    fn dispatcher() -> Dispatcher<Task, 3> {
+        let mut dispatcher = Dispatcher::new();
+        dispatcher
+            .add(StandardId::new(0x180).unwrap(), Task::Heartbeat)
+            .unwrap();
+        dispatcher
+            .add_range(
+                StandardId::new(0x100).unwrap(),
+                StandardId::new(0x1FF).unwrap(),
+                Task::Sensors,
+            )
+            .unwrap();
+        dispatcher
+            .add_range(
+                ExtendedId::new(0x100).unwrap(),
+                ExtendedId::MAX,
+                Task::Diagnostics,
+            )
+            .unwrap();
+        dispatcher
+    }
+
+    #[test]
+    fn lookup() {
+        let dispatcher = dispatcher();
+        let std = |id| StandardId::new(id).unwrap();
+        let ext = |id| ExtendedId::new(id).unwrap();
+
+        // The first matching route wins.
+        assert_eq!(dispatcher.lookup(std(0x180)), Some(&Task::Heartbeat));
+        assert_eq!(dispatcher.lookup(std(0x100)), Some(&Task::Sensors));
+        assert_eq!(dispatcher.lookup(std(0x1FF)), Some(&Task::Sensors));
+        assert_eq!(dispatcher.lookup(std(0x0FF)), None);
+        assert_eq!(dispatcher.lookup(std(0x200)), None);
+        assert_eq!(dispatcher.lookup(ext(0x180)), Some(&Task::Diagnostics));
+        assert_eq!(dispatcher.lookup(ext(0x0FF)), None);
+        assert_eq!(dispatcher.lookup(ExtendedId::MAX), Some(&Task::Diagnostics));
+    }
+
+    #[test]
+    fn dispatch() {
+        let mut dispatcher = dispatcher();
+        assert_eq!(dispatcher.len(), 3);
+        assert_eq!(
+            dispatcher.add(StandardId::ZERO, Task::Heartbeat),
+            Err(Task::Heartbeat)
+        );
+
+        let frame = Frame::new_data(StandardId::new(0x123).unwrap(), [1]);
+        let routed = dispatcher.dispatch(&frame, |task, _| Some(*task), |_| None);
+        assert_eq!(routed, Some(Task::Sensors));
+
+        let frame = Frame::new_remote(StandardId::ZERO, 0);
+        let mut unmatched = None;
+        dispatcher.dispatch(&frame, |_, _| {}, |frame| unmatched = Some(frame.clone()));
+        assert_eq!(unmatched, Some(frame));
+
+        dispatcher.clear();
+        assert!(dispatcher.is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn mixed_range() {
+        Dispatcher::<(), 1>::new()
+            .add_range(StandardId::ZERO, ExtendedId::MAX, ())
+            .ok();
+    }
+}
@@ -16,14 +16,14 @@ where
         frame: &Self::Frame,
     ) -> nb::Result<Option<Self::Frame>, Self::Error> {
         match self.transmit(frame) {
-            Ok(status) => Ok(status.dequeued_frame().cloned()),
+            Ok(status) => Ok(status.into_dequeued_frame()),
             Err(nb::Error::WouldBlock) => Err(nb::Error::WouldBlock),
             Err(nb::Error::Other(e)) => match e {},
         }
     }
 
     fn try_receive(&mut self) -> nb::Result<Self::Frame, Self::Error> {
-        self.receive()
+        self.receive().map_err(|e| e.map(|_| ()))
     }
 }
 
@@ -0,0 +1,305 @@
+//! `embedded_can` 0.4 trait impls, and conversions from and to other `embedded_can` frame types.
+
+use crate::{Can, Data, ExtendedId, Frame, Id, Instance, OverrunError, StandardId};
+use embedded_can_04 as embedded_can;
+
+impl<I> embedded_can::nb::Can for Can<I>
+where
+    I: Instance,
+{
+    type Frame = Frame;
+
+    type Error = embedded_can::ErrorKind;
+
+    fn transmit(&mut self, frame: &Self::Frame) -> nb::Result<Option<Self::Frame>, Self::Error> {
+        match self.transmit(frame) {
+            Ok(status) => Ok(status.into_dequeued_frame()),
+            Err(nb::Error::WouldBlock) => Err(nb::Error::WouldBlock),
+            Err(nb::Error::Other(e)) => match e {},
+        }
+    }
+
+    fn receive(&mut self) -> nb::Result<Self::Frame, Self::Error> {
+        self.receive()
+            .map_err(|e| e.map(|_| embedded_can::ErrorKind::Overrun))
+    }
+}
+
+impl<I> embedded_can::blocking::Can for Can<I>
+where
+    I: Instance,
+{
+    type Frame = Frame;
+
+    type Error = embedded_can::ErrorKind;
+
+    fn transmit(&mut self, frame: &Self::Frame) -> Result<(), Self::Error> {
+        // The blocking API has no way of returning a dequeued lower-priority frame, so it is
+        // enqueued again (possibly dequeuing another, even lower-priority, frame).
+        let mut frame = frame.clone();
+        loop {
+            let status = match nb::block!(self.transmit(&frame)) {
+                Ok(status) => status,
+                Err(e) => match e {},
+            };
+            match status.into_dequeued_frame() {
+                Some(dequeued) => frame = dequeued,
+                None => return Ok(()),
+            }
+        }
+    }
+
+    fn receive(&mut self) -> Result<Self::Frame, Self::Error> {
+        nb::block!(self.receive()).map_err(|_| embedded_can::ErrorKind::Overrun)
+    }
+}
+
+impl embedded_can::Error for OverrunError {
+    #[inline]
+    fn kind(&self) -> embedded_can::ErrorKind {
+        embedded_can::ErrorKind::Overrun
+    }
+}
+
+impl embedded_can::Frame for Frame {
+    fn new(id: impl Into<embedded_can::Id>, data: &[u8]) -> Option<Self> {
+        let data = Data::new(data)?;
+        Some(Frame::new_data(from_embedded_can_id(id.into()), data))
+    }
+
+    fn new_remote(id: impl Into<embedded_can::Id>, dlc: usize) -> Option<Self> {
+        if dlc <= 8 {
+            Some(Frame::new_remote(
+                from_embedded_can_id(id.into()),
+                dlc as u8,
+            ))
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn is_extended(&self) -> bool {
+        self.is_extended()
+    }
+
+    #[inline]
+    fn is_standard(&self) -> bool {
+        self.is_standard()
+    }
+
+    #[inline]
+    fn is_remote_frame(&self) -> bool {
+        self.is_remote_frame()
+    }
+
+    #[inline]
+    fn is_data_frame(&self) -> bool {
+        self.is_data_frame()
+    }
+
+    #[inline]
+    fn id(&self) -> embedded_can::Id {
+        self.id().into()
+    }
+
+    #[inline]
+    fn dlc(&self) -> usize {
+        self.dlc() as usize
+    }
+
+    fn data(&self) -> &[u8] {
+        if let Some(data) = self.data() {
+            data
+        } else {
+            &[]
+        }
+    }
+}
+
+fn from_embedded_can_id(id: embedded_can::Id) -> Id {
+    id.into()
+}
+
+impl Frame {
+    /// Converts a frame of any type implementing the [`embedded_can::Frame`] trait, eg. the
+    /// `CanFrame` of the `socketcan` crate on a Linux host.
+    ///
+    /// Returns `None` if the frame does not fit into a classic CAN frame, like a CAN FD frame with
+    /// more than 8 bytes of data.
+    ///
+    /// This and [`Frame::to_embedded_can`] are methods instead of `From`/`TryFrom` impls, since
+    /// [`Frame`] implements [`embedded_can::Frame`] itself: A blanket impl over all frame types
+    /// would overlap with the reflexive `impl<T> From<T> for T` of `core`. Host frame types that
+    /// don't implement [`embedded_can::Frame`], like those of the `canfd` crate, are not supported.
+    ///
+    /// [`embedded_can::Frame`]: embedded_can_04::Frame
+    pub fn from_embedded_can(frame: &impl embedded_can::Frame) -> Option<Self> {
+        let id = from_embedded_can_id(frame.id());
+        if frame.is_remote_frame() {
+            let dlc = frame.dlc();
+            if dlc > 8 {
+                return None;
+            }
+            Some(Frame::new_remote(id, dlc as u8))
+        } else {
+            Some(Frame::new_data(id, Data::new(frame.data())?))
+        }
+    }
+
+    /// Converts this frame to another type implementing the [`embedded_can::Frame`] trait.
+    ///
+    /// Returns `None` if `F` rejects the frame, eg. because it does not support remote frames.
+    ///
+    /// [`embedded_can::Frame`]: embedded_can_04::Frame
+    pub fn to_embedded_can<F: embedded_can::Frame>(&self) -> Option<F> {
+        let id = embedded_can::Id::from(self.id());
+        match self.data() {
+            Some(data) => F::new(id, data),
+            None => F::new_remote(id, usize::from(self.dlc())),
+        }
+    }
+}
+
+impl From<embedded_can::StandardId> for StandardId {
+    #[inline]
+    fn from(id: embedded_can::StandardId) -> Self {
+        // Safety: `embedded_can` IDs are range-checked the same way as ours.
+        unsafe { StandardId::new_unchecked(id.as_raw()) }
+    }
+}
+
+impl From<StandardId> for embedded_can::StandardId {
+    #[inline]
+    fn from(id: StandardId) -> Self {
+        // Safety: `embedded_can` IDs are range-checked the same way as ours.
+        unsafe { embedded_can::StandardId::new_unchecked(id.as_raw()) }
+    }
+}
+
+impl From<embedded_can::ExtendedId> for ExtendedId {
+    #[inline]
+    fn from(id: embedded_can::ExtendedId) -> Self {
+        // Safety: `embedded_can` IDs are range-checked the same way as ours.
+        unsafe { ExtendedId::new_unchecked(id.as_raw()) }
+    }
+}
+
+impl From<ExtendedId> for embedded_can::ExtendedId {
+    #[inline]
+    fn from(id: ExtendedId) -> Self {
+        // Safety: `embedded_can` IDs are range-checked the same way as ours.
+        unsafe { embedded_can::ExtendedId::new_unchecked(id.as_raw()) }
+    }
+}
+
+impl From<embedded_can::Id> for Id {
+    #[inline]
+    fn from(id: embedded_can::Id) -> Self {
+        match id {
+            embedded_can::Id::Standard(id) => Id::Standard(id.into()),
+            embedded_can::Id::Extended(id) => Id::Extended(id.into()),
+        }
+    }
+}
+
+impl From<Id> for embedded_can::Id {
+    #[inline]
+    fn from(id: Id) -> Self {
+        match id {
+            Id::Standard(id) => embedded_can::Id::Standard(id.into()),
+            Id::Extended(id) => embedded_can::Id::Extended(id.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_can::Frame as _;
+
+    /// A host-side frame type, like the ones of the `socketcan` crate.
+    #[derive(Debug, PartialEq)]
+    struct HostFrame {
+        id: embedded_can::Id,
+        remote: bool,
+        len: usize,
+        data: [u8; 64],
+    }
+
+    impl embedded_can::Frame for HostFrame {
+        fn new(id: impl Into<embedded_can::Id>, data: &[u8]) -> Option<Self> {
+            let mut bytes = [0; 64];
+            bytes.get_mut(..data.len())?.copy_from_slice(data);
+            Some(Self {
+                id: id.into(),
+                remote: false,
+                len: data.len(),
+                data: bytes,
+            })
+        }
+
+        fn new_remote(id: impl Into<embedded_can::Id>, dlc: usize) -> Option<Self> {
+            Some(Self {
+                id: id.into(),
+                remote: true,
+                len: dlc,
+                data: [0; 64],
+            })
+        }
+
+        fn is_extended(&self) -> bool {
+            matches!(self.id, embedded_can::Id::Extended(_))
+        }
+
+        fn is_remote_frame(&self) -> bool {
+            self.remote
+        }
+
+        fn id(&self) -> embedded_can::Id {
+            self.id
+        }
+
+        fn dlc(&self) -> usize {
+            self.len
+        }
+
+        fn data(&self) -> &[u8] {
+            &self.data[..if self.remote { 0 } else { self.len }]
+        }
+    }
+
+    #[test]
+    fn convert_frames() {
+        let frames = [
+            Frame::new_data(StandardId::MAX, [1, 2, 3]),
+            Frame::new_data(ExtendedId::MAX, [0xFF; 8]),
+            Frame::new_remote(ExtendedId::ZERO, 4),
+        ];
+        for frame in frames {
+            let host: HostFrame = frame.to_embedded_can().unwrap();
+            assert_eq!(host.is_remote_frame(), frame.is_remote_frame());
+            let back = Frame::from_embedded_can(&host).unwrap();
+            assert!(back.eq_exact(&frame));
+        }
+
+        // CAN FD frames and invalid remote frames do not fit.
+        let id = embedded_can::StandardId::ZERO;
+        let fd = HostFrame::new(id, &[0; 12]).unwrap();
+        assert_eq!(Frame::from_embedded_can(&fd), None);
+        let remote = HostFrame::new_remote(id, 9).unwrap();
+        assert_eq!(Frame::from_embedded_can(&remote), None);
+    }
+
+    #[test]
+    fn convert_ids() {
+        let id = embedded_can::ExtendedId::new(0x1234_5678).unwrap();
+        assert_eq!(ExtendedId::from(id).as_raw(), 0x1234_5678);
+        let id = Id::from(embedded_can::Id::from(id));
+        assert_eq!(id, Id::Extended(ExtendedId::new(0x1234_5678).unwrap()));
+        assert_eq!(
+            embedded_can::Id::from(Id::Standard(StandardId::MAX)),
+            embedded_can::Id::Standard(embedded_can::StandardId::MAX)
+        );
+    }
+}
@@ -0,0 +1,92 @@
+//! Error state and error reporting types.
+
+#[allow(unused_imports)] // for intra-doc links only
+use crate::Can;
+
+/// The fault confinement state of a CAN node, as defined by the CAN specification.
+///
+/// The state is determined by the transmit and receive error counters.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub enum ErrorState {
+    /// Both error counters are below 128.
+    ///
+    /// The node takes part in bus communication normally, and signals detected errors with active
+    /// error flags. This is the state after initialization.
+    ErrorActive,
+
+    /// At least one of the error counters has exceeded 127.
+    ///
+    /// The node still takes part in bus communication, but only signals errors with passive error
+    /// flags, and waits for an additional suspend period after transmitting.
+    ErrorPassive,
+
+    /// The transmit error counter has exceeded 255.
+    ///
+    /// The node does not take part in bus communication anymore until it has recovered, either
+    /// automatically (if automatic bus-off management is enabled), or after calling
+    /// [`Can::recover_from_bus_off`].
+    BusOff,
+}
+
+/// The last error code, describing the most recent bus error detected by the peripheral.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub enum Lec {
+    /// More than 5 equal bits in a row were detected, violating the bit stuffing rule.
+    Stuff,
+    /// A fixed-format part of the frame contained an illegal bit.
+    Form,
+    /// A transmitted frame was not acknowledged by any other node.
+    Ack,
+    /// The peripheral wanted to send a recessive bit, but monitored a dominant one.
+    BitRecessive,
+    /// The peripheral wanted to send a dominant bit, but monitored a recessive one.
+    BitDominant,
+    /// The CRC of a received frame did not match.
+    Crc,
+    /// The error code was set to 7 by software.
+    ///
+    /// The peripheral never sets this code itself, so it can be used to detect whether a new error
+    /// has been recorded since the code was written.
+    SoftwareSet,
+}
+
+impl Lec {
+    /// Decodes the `LEC` field of `CAN_ESR`. Returns `None` for "no error".
+    pub(crate) fn from_bits(bits: u8) -> Option<Self> {
+        Some(match bits & 0b111 {
+            0 => return None,
+            1 => Self::Stuff,
+            2 => Self::Form,
+            3 => Self::Ack,
+            4 => Self::BitRecessive,
+            5 => Self::BitDominant,
+            6 => Self::Crc,
+            _ => Self::SoftwareSet,
+        })
+    }
+
+    /// Encodes this code for the `LEC` field of `CAN_ESR`.
+    pub(crate) fn to_bits(self) -> u8 {
+        self as u8 + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lec_from_bits() {
+        assert_eq!(Lec::from_bits(0), None);
+        assert_eq!(Lec::from_bits(1), Some(Lec::Stuff));
+        assert_eq!(Lec::from_bits(3), Some(Lec::Ack));
+        assert_eq!(Lec::from_bits(6), Some(Lec::Crc));
+        assert_eq!(Lec::from_bits(7), Some(Lec::SoftwareSet));
+
+        for bits in 1..=7 {
+            assert_eq!(Lec::from_bits(bits).unwrap().to_bits(), bits);
+        }
+    }
+}
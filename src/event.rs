@@ -0,0 +1,196 @@
+//! Decoded interrupt events.
+
+use heapless::Vec;
+
+use crate::{ErrorState, Fifo, Lec, Mailbox, OverrunError};
+
+#[allow(unused_imports)] // for intra-doc links only
+use crate::{Can, Interrupt};
+
+/// An event of the status change and error (SCE) interrupt, returned by [`Can::on_sce_interrupt`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub enum SceEvent {
+    /// The peripheral detected bus activity while in sleep mode ([`Interrupt::Wakeup`]).
+    Wakeup,
+    /// The peripheral has entered sleep mode ([`Interrupt::Sleep`]).
+    SleepAcknowledged,
+    /// The fault confinement state of the peripheral has changed.
+    ///
+    /// See [`Can::on_error_state_change`] for details.
+    ErrorStateChanged(ErrorState),
+    /// One of the error counters has reached the warning limit of 96 ([`Interrupt::ErrorWarning`]).
+    ///
+    /// This is only reported once until the counters have dropped below the limit again.
+    ErrorWarning,
+    /// A bus error was detected ([`Interrupt::LastErrorCode`]).
+    ///
+    /// This is only reported if [`Interrupt::LastErrorCode`] is enabled.
+    BusError(Lec),
+}
+
+/// An event of a receive FIFO interrupt, returned by [`Can::on_rx_interrupt`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub enum RxEvent {
+    /// A frame was lost because the FIFO was full ([`Interrupt::Fifo0Overrun`] or
+    /// [`Interrupt::Fifo1Overrun`]).
+    Overrun(OverrunError),
+    /// The FIFO holds 3 frames ([`Interrupt::Fifo0Full`] or [`Interrupt::Fifo1Full`]).
+    Full,
+    /// At least one frame is pending in the FIFO ([`Interrupt::Fifo0MessagePending`] or
+    /// [`Interrupt::Fifo1MessagePending`]).
+    ///
+    /// This is reported until all frames have been received from the FIFO.
+    FramePending,
+}
+
+/// An event of the transmit interrupt, returned by [`Can::on_tx_interrupt`].
+///
+/// Each event reports the completion of a transmission request in a mailbox.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub enum TxEvent {
+    /// The frame was transmitted successfully.
+    Transmitted(Mailbox),
+    /// The frame lost arbitration, and automatic retransmission is disabled.
+    ArbitrationLost(Mailbox),
+    /// A transmission error occurred, and automatic retransmission is disabled.
+    Error(Mailbox),
+    /// The transmission was aborted, eg. by [`Can::abort`] or because a higher priority frame
+    /// replaced it.
+    Aborted(Mailbox),
+}
+
+impl TxEvent {
+    /// Returns the mailbox whose transmission request was completed.
+    #[inline]
+    pub fn mailbox(&self) -> Mailbox {
+        match *self {
+            TxEvent::Transmitted(mailbox)
+            | TxEvent::ArbitrationLost(mailbox)
+            | TxEvent::Error(mailbox)
+            | TxEvent::Aborted(mailbox) => mailbox,
+        }
+    }
+}
+
+/// Error events buffered by [`Can::on_sce_interrupt_buffered`], returned by
+/// [`Can::take_error_events`].
+///
+/// Events of the same kind are coalesced: only the latest error state and the first bus error
+/// since the previous call to `take_error_events` are kept.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub struct ErrorEvents {
+    error_state: Option<ErrorState>,
+    bus_off: bool,
+    warning: bool,
+    bus_error: Option<Lec>,
+}
+
+impl ErrorEvents {
+    pub(crate) fn record(&mut self, event: SceEvent) {
+        match event {
+            SceEvent::ErrorStateChanged(state) => {
+                self.error_state = Some(state);
+                self.bus_off |= state == ErrorState::BusOff;
+            }
+            SceEvent::ErrorWarning => self.warning = true,
+            SceEvent::BusError(lec) => {
+                self.bus_error.get_or_insert(lec);
+            }
+            SceEvent::Wakeup | SceEvent::SleepAcknowledged => {}
+        }
+    }
+
+    /// Returns the new error state, if it has changed.
+    ///
+    /// If it has changed several times, this is the latest state.
+    #[inline]
+    pub fn error_state(&self) -> Option<ErrorState> {
+        self.error_state
+    }
+
+    /// Returns `true` if the peripheral has entered [`ErrorState::BusOff`], even if it has
+    /// recovered since then.
+    #[inline]
+    pub fn entered_bus_off(&self) -> bool {
+        self.bus_off
+    }
+
+    /// Returns `true` if one of the error counters has reached the warning limit.
+    #[inline]
+    pub fn warning(&self) -> bool {
+        self.warning
+    }
+
+    /// Returns the code of the first bus error.
+    #[inline]
+    pub fn bus_error(&self) -> Option<Lec> {
+        self.bus_error
+    }
+
+    /// Returns `true` if no error event has occurred.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// An event of any bxCAN interrupt, returned by [`Can::poll_all_events`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub enum Event {
+    /// An event of the **TX** interrupt.
+    Tx(TxEvent),
+    /// An event of the **RX FIFO** interrupt of a FIFO.
+    Rx(Fifo, RxEvent),
+    /// An event of the **SCE** interrupt.
+    Sce(SceEvent),
+}
+
+/// The events of all interrupt sources, returned by [`Can::poll_all_events`].
+///
+/// This is an iterator over the decoded events, in the order **TX**, **RX FIFO 0**, **RX FIFO
+/// 1**, **SCE**.
+#[derive(Debug, Clone)]
+pub struct Events {
+    /// 3 mailboxes, up to 3 events per FIFO, and 5 distinct SCE events.
+    events: Vec<Event, 14>,
+    next: usize,
+}
+
+impl Events {
+    pub(crate) fn new() -> Self {
+        Self {
+            events: Vec::new(),
+            next: 0,
+        }
+    }
+
+    pub(crate) fn push(&mut self, event: Event) {
+        // The capacity covers all events that can be pending at once.
+        self.events.push(event).ok();
+    }
+
+    pub(crate) fn is_full(&self) -> bool {
+        self.events.is_full()
+    }
+
+    /// Returns `true` if no events are left.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.next == self.events.len()
+    }
+}
+
+impl Iterator for Events {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Event> {
+        let event = self.events.get(self.next).copied()?;
+        self.next += 1;
+        Some(event)
+    }
+}
@@ -0,0 +1,319 @@
+//! CAN FD frame types for gateways.
+//!
+//! The bxCAN peripheral only supports classic CAN, so the frames in this module cannot be
+//! transmitted or received by it. They are meant for applications that bridge bxCAN to an external
+//! CAN FD controller, and want to use the same identifier and frame vocabulary on both sides.
+//!
+//! An [`FdFrame`] carries up to 64 data bytes. Frames with up to 8 bytes can be converted to and
+//! from classic [`Frame`]s with `TryFrom`. Longer frames can be split into classic frames with
+//! [`FdFrame::segments`], and reassembled with [`FdFrame::from_segments`]:
+//!
+//! ```
+//! use bxcan::fd::{FdData, FdFrame};
+//! use bxcan::StandardId;
+//!
+//! let data = FdData::new(&[0xAA; 20]).unwrap();
+//! let frame = FdFrame::new(StandardId::new(0x123).unwrap(), data);
+//!
+//! let segments: heapless::Vec<_, 8> = frame.segments().collect();
+//! assert_eq!(segments.len(), 3);
+//! assert_eq!(FdFrame::from_segments(&segments), Some(frame));
+//! ```
+//!
+//! The segmentation does not add any protocol information to the classic frames, so it is up to
+//! the application to delimit the segments of consecutive frames, eg. with a transport protocol.
+
+use core::convert::TryFrom;
+use core::ops::Deref;
+
+use crate::{Data, Frame, Id};
+
+/// Payload of a CAN FD frame.
+///
+/// Contains 0 to 64 bytes of data. CAN FD only supports lengths of 0 to 8, 12, 16, 20, 24, 32, 48
+/// and 64 bytes on the bus, so other lengths are padded by the controller to the length given by
+/// [`FdData::dlc`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct FdData {
+    len: u8,
+    /// Bytes past `len` are always 0.
+    bytes: [u8; 64],
+}
+
+impl FdData {
+    /// Creates a data payload from a raw byte slice.
+    ///
+    /// Returns `None` if `data` contains more than 64 bytes.
+    pub fn new(data: &[u8]) -> Option<Self> {
+        if data.len() > 64 {
+            return None;
+        }
+
+        let mut bytes = [0; 64];
+        bytes[..data.len()].copy_from_slice(data);
+        Some(Self {
+            len: data.len() as u8,
+            bytes,
+        })
+    }
+
+    /// Creates an empty data payload containing 0 bytes.
+    #[inline]
+    pub const fn empty() -> Self {
+        Self {
+            len: 0,
+            bytes: [0; 64],
+        }
+    }
+
+    /// Returns the data length code (DLC) of a CAN FD frame carrying this payload.
+    ///
+    /// This is the code of the smallest length supported on the bus that can hold the payload.
+    pub fn dlc(&self) -> u8 {
+        match self.len {
+            0..=8 => self.len,
+            9..=12 => 9,
+            13..=16 => 10,
+            17..=20 => 11,
+            21..=24 => 12,
+            25..=32 => 13,
+            33..=48 => 14,
+            _ => 15,
+        }
+    }
+}
+
+impl Deref for FdData {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        &self.bytes[..usize::from(self.len)]
+    }
+}
+
+impl AsRef<[u8]> for FdData {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl From<Data> for FdData {
+    fn from(data: Data) -> Self {
+        let mut fd = Self::empty();
+        fd.bytes[..data.len()].copy_from_slice(&data);
+        fd.len = data.len() as u8;
+        fd
+    }
+}
+
+#[cfg(feature = "unstable-defmt")]
+impl defmt::Format for FdData {
+    fn format(&self, fmt: defmt::Formatter<'_>) {
+        self.as_ref().format(fmt)
+    }
+}
+
+/// A CAN FD data frame.
+///
+/// CAN FD does not have remote frames. Besides the identifier and up to 64 data bytes, a CAN FD
+/// frame carries the bit rate switch (BRS) flag, which selects the faster data bit rate for the
+/// data phase, and the error state indicator (ESI) flag, which is set by error passive
+/// transmitters.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub struct FdFrame {
+    id: Id,
+    data: FdData,
+    brs: bool,
+    esi: bool,
+}
+
+impl FdFrame {
+    /// Creates a new CAN FD frame, with the BRS and ESI flags cleared.
+    pub fn new(id: impl Into<Id>, data: impl Into<FdData>) -> Self {
+        Self {
+            id: id.into(),
+            data: data.into(),
+            brs: false,
+            esi: false,
+        }
+    }
+
+    /// Returns the frame identifier.
+    #[inline]
+    pub fn id(&self) -> Id {
+        self.id
+    }
+
+    /// Returns the frame data.
+    #[inline]
+    pub fn data(&self) -> &FdData {
+        &self.data
+    }
+
+    /// Returns the data length code (DLC), see [`FdData::dlc`].
+    #[inline]
+    pub fn dlc(&self) -> u8 {
+        self.data.dlc()
+    }
+
+    /// Returns `true` if the data phase is transmitted with the data bit rate (BRS flag).
+    #[inline]
+    pub fn bit_rate_switch(&self) -> bool {
+        self.brs
+    }
+
+    /// Sets or clears the bit rate switch (BRS) flag.
+    #[inline]
+    pub fn set_bit_rate_switch(&mut self, brs: bool) {
+        self.brs = brs;
+    }
+
+    /// Returns `true` if the transmitter was error passive (ESI flag).
+    #[inline]
+    pub fn error_state_indicator(&self) -> bool {
+        self.esi
+    }
+
+    /// Sets or clears the error state indicator (ESI) flag.
+    #[inline]
+    pub fn set_error_state_indicator(&mut self, esi: bool) {
+        self.esi = esi;
+    }
+
+    /// Splits the data of this frame into classic data frames of up to 8 bytes, with the same
+    /// identifier.
+    ///
+    /// All frames but the last one carry 8 bytes. A frame without data results in a single empty
+    /// classic frame. The BRS and ESI flags are not preserved.
+    pub fn segments(&self) -> impl Iterator<Item = Frame> + '_ {
+        let count = self.data.len().div_ceil(8);
+        (0..count.max(1)).map(move |i| {
+            let end = self.data.len().min(i * 8 + 8);
+            // Each segment has at most 8 bytes.
+            let data = Data::new(&self.data[i * 8..end]).unwrap();
+            Frame::new_data(self.id, data)
+        })
+    }
+
+    /// Reassembles a frame from classic data frames, eg. the ones returned by
+    /// [`FdFrame::segments`].
+    ///
+    /// The data of the `segments` is concatenated. Returns `None` if there are no segments, if
+    /// their identifiers differ, if one of them is a remote frame, or if they carry more than 64
+    /// bytes in total.
+    pub fn from_segments<'a>(segments: impl IntoIterator<Item = &'a Frame>) -> Option<Self> {
+        let mut segments = segments.into_iter();
+        let first = segments.next()?;
+        let mut frame = Self::try_from(first.clone()).ok()?;
+        for segment in segments {
+            if segment.id() != frame.id {
+                return None;
+            }
+            let data = segment.data()?;
+            let end = usize::from(frame.data.len) + data.len();
+            frame
+                .data
+                .bytes
+                .get_mut(usize::from(frame.data.len)..end)?
+                .copy_from_slice(data);
+            frame.data.len = end as u8;
+        }
+        Some(frame)
+    }
+}
+
+/// Converts a classic data frame to a CAN FD frame.
+///
+/// Fails and returns the frame back if it is a remote frame.
+impl TryFrom<Frame> for FdFrame {
+    type Error = Frame;
+
+    fn try_from(frame: Frame) -> Result<Self, Frame> {
+        match frame.data() {
+            Some(data) => Ok(Self::new(frame.id(), *data)),
+            None => Err(frame),
+        }
+    }
+}
+
+/// Converts a CAN FD frame to a classic data frame.
+///
+/// Fails and returns the frame back if it carries more than 8 bytes. The BRS and ESI flags are not
+/// preserved.
+impl TryFrom<FdFrame> for Frame {
+    type Error = FdFrame;
+
+    fn try_from(frame: FdFrame) -> Result<Self, FdFrame> {
+        match Data::new(&frame.data) {
+            Some(data) => Ok(Frame::new_data(frame.id, data)),
+            None => Err(frame),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExtendedId, StandardId};
+
+    #[test]
+    fn dlc() {
+        let dlc = |len| FdData::new(&[0; 64][..len]).unwrap().dlc();
+        assert_eq!(dlc(0), 0);
+        assert_eq!(dlc(8), 8);
+        assert_eq!(dlc(9), 9);
+        assert_eq!(dlc(12), 9);
+        assert_eq!(dlc(13), 10);
+        assert_eq!(dlc(24), 12);
+        assert_eq!(dlc(25), 13);
+        assert_eq!(dlc(48), 14);
+        assert_eq!(dlc(64), 15);
+        assert_eq!(FdData::new(&[0; 65]), None);
+    }
+
+    #[test]
+    fn classic_conversion() {
+        let frame = Frame::new_data(ExtendedId::MAX, [1, 2, 3]);
+        let mut fd = FdFrame::try_from(frame.clone()).unwrap();
+        assert_eq!(fd.id(), frame.id());
+        assert_eq!(&**fd.data(), &[1, 2, 3]);
+        assert!(!fd.bit_rate_switch());
+
+        fd.set_bit_rate_switch(true);
+        assert_eq!(Frame::try_from(fd), Ok(frame));
+
+        let remote = Frame::new_remote(StandardId::ZERO, 1);
+        assert_eq!(FdFrame::try_from(remote.clone()), Err(remote));
+
+        let long = FdFrame::new(StandardId::ZERO, FdData::new(&[0; 9]).unwrap());
+        assert_eq!(Frame::try_from(long.clone()), Err(long));
+    }
+
+    #[test]
+    fn segments() {
+        let id = StandardId::new(0x42).unwrap();
+        let mut bytes = [0; 64];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+
+        for len in [0, 1, 8, 9, 16, 63, 64] {
+            let frame = FdFrame::new(id, FdData::new(&bytes[..len]).unwrap());
+            let segments: heapless::Vec<Frame, 8> = frame.segments().collect();
+            assert_eq!(segments.len(), len.div_ceil(8).max(1));
+            assert!(segments.iter().all(|s| s.id() == Id::from(id)));
+            assert_eq!(FdFrame::from_segments(&segments), Some(frame));
+        }
+
+        assert_eq!(FdFrame::from_segments(&[]), None);
+        let full = Frame::new_data(id, [0; 8]);
+        assert_eq!(FdFrame::from_segments([&full; 9]), None);
+        let other = Frame::new_data(StandardId::ZERO, [0; 8]);
+        assert_eq!(FdFrame::from_segments(&[full.clone(), other]), None);
+        let remote = Frame::new_remote(id, 0);
+        assert_eq!(FdFrame::from_segments(&[full, remote]), None);
+    }
+}
@@ -3,7 +3,11 @@
 use core::marker::PhantomData;
 
 use crate::pac::can::RegisterBlock;
-use crate::{ExtendedId, FilterOwner, Id, Instance, MasterInstance, StandardId};
+use heapless::Vec;
+
+use crate::{
+    ExtendedId, Fifo, FilterOwner, Frame, Id, Instance, MasterInstance, OverrunError, StandardId,
+};
 
 const F32_RTR: u32 = 0b010; // set the RTR bit to match remote frames
 const F32_IDE: u32 = 0b100; // set the IDE bit to match extended identifiers
@@ -25,7 +29,7 @@ pub struct ListEntry16(u16);
 pub struct ListEntry32(u32);
 
 /// A 16-bit identifier mask.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 #[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
 pub struct Mask16 {
     id: u16,
@@ -33,7 +37,7 @@ pub struct Mask16 {
 }
 
 /// A 32-bit identifier mask.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 #[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
 pub struct Mask32 {
     id: u32,
@@ -100,6 +104,28 @@ impl Mask16 {
         }
     }
 
+    /// Creates a 16-bit identifier mask that accepts all frames whose standard ID starts with the
+    /// `bits` most significant bits of `base`.
+    ///
+    /// For example, a `base` of `0x120` with 7 `bits` accepts the IDs `0x120..=0x12F`.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if `bits` is greater than 11.
+    pub fn frames_with_std_id_prefix(base: StandardId, bits: u8) -> Self {
+        Self::frames_with_std_id(base, std_prefix_mask(bits))
+    }
+
+    /// Creates the tightest 16-bit identifier mask that accepts all frames with any of the given
+    /// standard IDs.
+    ///
+    /// The mask requires all bits that are equal in all `ids`, so it may also accept IDs not
+    /// contained in `ids`. Returns `None` if `ids` is empty.
+    pub fn covering_std_ids(ids: &[StandardId]) -> Option<Self> {
+        let (id, mask) = covering_std_mask(ids)?;
+        Some(Self::frames_with_std_id(id, mask))
+    }
+
     /// Make the filter accept data frames only.
     pub fn data_frames_only(&mut self) -> &mut Self {
         self.id &= !F16_RTR; // RTR = 0
@@ -138,6 +164,34 @@ impl Mask32 {
         }
     }
 
+    /// Creates a 32-bit identifier mask that accepts all frames whose extended ID starts with the
+    /// `bits` most significant bits of `base`.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if `bits` is greater than 29.
+    pub fn frames_with_ext_id_prefix(base: ExtendedId, bits: u8) -> Self {
+        assert!(bits <= 29);
+        let mask = !(u32::MAX >> bits) >> 3;
+        // Safety: `mask` only has the lower 29 bits set.
+        Self::frames_with_ext_id(base, unsafe { ExtendedId::new_unchecked(mask) })
+    }
+
+    /// Creates the tightest 32-bit identifier mask that accepts all frames with any of the given
+    /// extended IDs.
+    ///
+    /// The mask requires all bits that are equal in all `ids`, so it may also accept IDs not
+    /// contained in `ids`. Returns `None` if `ids` is empty.
+    pub fn covering_ext_ids(ids: &[ExtendedId]) -> Option<Self> {
+        let (first, rest) = ids.split_first()?;
+        let differing = rest
+            .iter()
+            .fold(0, |bits, id| bits | (id.as_raw() ^ first.as_raw()));
+        // Safety: `ExtendedId::MAX` only has the lower 29 bits set.
+        let mask = unsafe { ExtendedId::new_unchecked(ExtendedId::MAX.as_raw() & !differing) };
+        Some(Self::frames_with_ext_id(*first, mask))
+    }
+
     /// Creates a 32-bit identifier mask that accepts all frames with the given standard
     /// ID and mask combination.
     ///
@@ -153,6 +207,27 @@ impl Mask32 {
         }
     }
 
+    /// Creates a 32-bit identifier mask that accepts all frames whose standard ID starts with the
+    /// `bits` most significant bits of `base`.
+    ///
+    /// See [`Mask16::frames_with_std_id_prefix`] for details.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if `bits` is greater than 11.
+    pub fn frames_with_std_id_prefix(base: StandardId, bits: u8) -> Self {
+        Self::frames_with_std_id(base, std_prefix_mask(bits))
+    }
+
+    /// Creates the tightest 32-bit identifier mask that accepts all frames with any of the given
+    /// standard IDs.
+    ///
+    /// See [`Mask16::covering_std_ids`] for details.
+    pub fn covering_std_ids(ids: &[StandardId]) -> Option<Self> {
+        let (id, mask) = covering_std_mask(ids)?;
+        Some(Self::frames_with_std_id(id, mask))
+    }
+
     /// Make the filter accept data frames only.
     pub fn data_frames_only(&mut self) -> &mut Self {
         self.id &= !F32_RTR; // RTR = 0
@@ -168,13 +243,36 @@ impl Mask32 {
     }
 }
 
+/// Returns a standard ID mask with the `bits` most significant bits set.
+fn std_prefix_mask(bits: u8) -> StandardId {
+    assert!(bits <= 11);
+    let mask = !(u16::MAX >> bits) >> 5;
+    // Safety: `mask` only has the lower 11 bits set.
+    unsafe { StandardId::new_unchecked(mask) }
+}
+
+/// Returns the first of `ids` and a mask of the bits that are equal in all `ids`.
+fn covering_std_mask(ids: &[StandardId]) -> Option<(StandardId, StandardId)> {
+    let (first, rest) = ids.split_first()?;
+    let differing = rest
+        .iter()
+        .fold(0, |bits, id| bits | (id.as_raw() ^ first.as_raw()));
+    // Safety: `StandardId::MAX` only has the lower 11 bits set.
+    let mask = unsafe { StandardId::new_unchecked(StandardId::MAX.as_raw() & !differing) };
+    Some((*first, mask))
+}
+
 /// The configuration of a filter bank.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 #[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
 pub enum BankConfig {
+    /// Accepts frames matching any of up to 4 standard IDs.
     List16([ListEntry16; 4]),
+    /// Accepts frames matching any of up to 2 standard or extended IDs.
     List32([ListEntry32; 2]),
+    /// Accepts frames matching either of 2 standard ID masks.
     Mask16([Mask16; 2]),
+    /// Accepts frames matching a single standard or extended ID mask.
     Mask32(Mask32),
 }
 
@@ -206,6 +304,127 @@ impl From<Mask32> for BankConfig {
     }
 }
 
+/// The bxCAN peripheral has at most 28 filter banks, as the filter registers have one bit per bank.
+const MAX_FILTER_BANKS: u8 = 28;
+
+/// Checks [`FilterOwner::NUM_FILTER_BANKS`] of `I` at compile time.
+struct BankCount<I>(PhantomData<I>);
+
+impl<I: FilterOwner> BankCount<I> {
+    const VALID: () = assert!(
+        I::NUM_FILTER_BANKS >= 1 && I::NUM_FILTER_BANKS <= MAX_FILTER_BANKS,
+        "`FilterOwner::NUM_FILTER_BANKS` must be in range 1..=28"
+    );
+}
+
+/// Checks a filter bank index against [`FilterOwner::NUM_FILTER_BANKS`] of `I` at compile time.
+struct BankIndex<I, const INDEX: u8>(PhantomData<I>);
+
+impl<I: FilterOwner, const INDEX: u8> BankIndex<I, INDEX> {
+    const VALID: () = assert!(
+        INDEX < I::NUM_FILTER_BANKS,
+        "filter bank index out of range for `FilterOwner::NUM_FILTER_BANKS`"
+    );
+}
+
+/// A filter bank enabled by [`Can::add_filter`].
+///
+/// Pass it to [`Can::remove_filter`] to disable the bank again. The handle becomes stale when the
+/// filters are modified through [`Can::modify_filters`], since the bank may have been disabled,
+/// reused or assigned to the slave instance.
+///
+/// [`Can::add_filter`]: crate::Can::add_filter
+/// [`Can::remove_filter`]: crate::Can::remove_filter
+/// [`Can::modify_filters`]: crate::Can::modify_filters
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub struct FilterHandle {
+    index: u8,
+    /// Value of the filter generation of the `Can` when the bank was enabled.
+    generation: u32,
+}
+
+impl FilterHandle {
+    /// Returns the index of the filter bank.
+    #[inline]
+    pub fn index(&self) -> u8 {
+        self.index
+    }
+}
+
+/// Error returned by [`Can::add_filter`] when all filter banks are in use.
+///
+/// [`Can::add_filter`]: crate::Can::add_filter
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub struct FiltersFull;
+
+/// Error returned by [`Can::remove_filter`] when the filters were modified through
+/// [`Can::modify_filters`] after the [`FilterHandle`] was created.
+///
+/// [`Can::remove_filter`]: crate::Can::remove_filter
+/// [`Can::modify_filters`]: crate::Can::modify_filters
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub struct StaleFilterHandle;
+
+/// Enables `config` in a free filter bank of `I`, entering filter initialization mode only if the
+/// mode, scale or FIFO assignment of the bank has to be changed.
+///
+/// `generation` is stored in the returned handle, and has to be passed to [`remove_bank`] again.
+pub(crate) unsafe fn add_bank<I: FilterOwner>(
+    fifo: Fifo,
+    config: BankConfig,
+    generation: u32,
+) -> Result<FilterHandle, FiltersFull> {
+    #[allow(clippy::let_unit_value)]
+    let () = BankCount::<I>::VALID;
+
+    let can = &*I::register_block();
+    let mut banks = master_banks::<I>(can);
+    let (index, needs_init) = banks.find_free(fifo, &config).ok_or(FiltersFull)?;
+
+    if needs_init {
+        can.fmr.modify(|_, w| w.finit().set_bit());
+    }
+    banks.enable(index, fifo, config);
+    if needs_init {
+        can.fmr.modify(|_, w| w.finit().clear_bit());
+    }
+
+    Ok(FilterHandle { index, generation })
+}
+
+/// Disables a filter bank enabled by [`add_bank`], without entering filter initialization mode.
+///
+/// Returns [`StaleFilterHandle`] if `handle` was not created with `generation`.
+pub(crate) unsafe fn remove_bank<I: FilterOwner>(
+    handle: FilterHandle,
+    generation: u32,
+) -> Result<(), StaleFilterHandle> {
+    if handle.generation != generation {
+        return Err(StaleFilterHandle);
+    }
+
+    let can = &*I::register_block();
+    master_banks::<I>(can).disable(handle.index);
+    Ok(())
+}
+
+/// Returns `true` if any filter bank owned by the master instance `I` is enabled.
+pub(crate) fn has_enabled_bank<I: FilterOwner>(can: &RegisterBlock) -> bool {
+    let banks = master_banks::<I>(can);
+    can.fa1r.read().bits() & filter_bitmask(banks.start_idx, banks.bank_count) != 0
+}
+
+fn master_banks<I: FilterOwner>(can: &RegisterBlock) -> FilterBanks<'_> {
+    FilterBanks {
+        start_idx: 0,
+        bank_count: can.fmr.read().can2sb().bits().min(I::NUM_FILTER_BANKS),
+        can,
+    }
+}
+
 /// Interface to the filter banks of a CAN peripheral.
 pub struct MasterFilters<'a, I: FilterOwner> {
     /// Number of assigned filter banks.
@@ -219,7 +438,10 @@ pub struct MasterFilters<'a, I: FilterOwner> {
 // exists.
 impl<I: FilterOwner> MasterFilters<'_, I> {
     pub(crate) unsafe fn new() -> Self {
-        let can = &*I::REGISTERS;
+        #[allow(clippy::let_unit_value)]
+        let () = BankCount::<I>::VALID;
+
+        let can = &*I::register_block();
 
         // Enable initialization mode.
         can.fmr.modify(|_, w| w.finit().set_bit());
@@ -238,7 +460,7 @@ impl<I: FilterOwner> MasterFilters<'_, I> {
     }
 
     fn registers(&self) -> &RegisterBlock {
-        unsafe { &*I::REGISTERS }
+        unsafe { &*I::register_block() }
     }
 
     fn banks_imm(&self) -> FilterBanks<'_> {
@@ -274,16 +496,92 @@ impl<I: FilterOwner> MasterFilters<'_, I> {
     }
 
     /// Configures a filter bank according to `config` and enables it.
-    pub fn enable_bank(&mut self, index: u8, config: impl Into<BankConfig>) -> &mut Self {
-        self.banks_imm().enable(index, config.into());
+    ///
+    /// Frames accepted by the bank are stored in the receive FIFO given by `fifo`.
+    ///
+    /// If `index` is out of bounds, this will panic.
+    pub fn enable_bank(
+        &mut self,
+        index: u8,
+        fifo: Fifo,
+        config: impl Into<BankConfig>,
+    ) -> &mut Self {
+        self.banks_imm().enable(index, fifo, config.into());
         self
     }
+
+    /// Configures the filter bank `INDEX` according to `config` and enables it, like
+    /// [`MasterFilters::enable_bank`].
+    ///
+    /// Using an index that is not below [`FilterOwner::NUM_FILTER_BANKS`] fails to compile:
+    ///
+    /// ```compile_fail
+    /// # use bxcan::{FilterOwner, Instance, RegisterBlock};
+    /// # struct CAN1;
+    /// # unsafe impl Instance for CAN1 {
+    /// #     const REGISTERS: *mut RegisterBlock = 0x4000_6400 as *mut _;
+    /// # }
+    /// # unsafe impl FilterOwner for CAN1 {
+    /// #     const NUM_FILTER_BANKS: u8 = 14;
+    /// # }
+    /// use bxcan::{filter::Mask32, Can, Fifo};
+    ///
+    /// fn setup(can: &mut Can<CAN1>) {
+    ///     can.modify_filters()
+    ///         .enable_bank_const::<14>(Fifo::Fifo0, Mask32::accept_all());
+    /// }
+    /// # fn main() { let _ = setup as fn(&mut Can<CAN1>); }
+    /// ```
+    ///
+    /// Since the banks can be split between the master and slave instance at runtime, this still
+    /// panics if bank `INDEX` is currently assigned to the slave instance.
+    pub fn enable_bank_const<const INDEX: u8>(
+        &mut self,
+        fifo: Fifo,
+        config: impl Into<BankConfig>,
+    ) -> &mut Self {
+        let () = BankIndex::<I, INDEX>::VALID;
+
+        self.enable_bank(INDEX, fifo, config)
+    }
+
+    /// Reads back the configuration of an enabled filter bank.
+    ///
+    /// Returns the FIFO and configuration the bank was enabled with, or `None` if the bank is
+    /// disabled.
+    ///
+    /// If `index` is out of bounds, this will panic.
+    pub fn get(&self, index: u8) -> Option<(Fifo, BankConfig)> {
+        self.banks_imm().get(index)
+    }
+
+    /// Returns an iterator over the index, FIFO and configuration of all enabled filter banks.
+    pub fn iter(&self) -> impl Iterator<Item = (u8, Fifo, BankConfig)> + '_ {
+        self.banks_imm().iter()
+    }
 }
 
 impl<I: MasterInstance> MasterFilters<'_, I> {
     /// Sets the index at which the filter banks owned by the slave peripheral start.
+    ///
+    /// Banks that are moved from one peripheral to the other are disabled, so that neither
+    /// peripheral receives frames through a filter configured for the other one.
+    ///
+    /// If `split_index` is greater than [`FilterOwner::NUM_FILTER_BANKS`], this will panic.
     pub fn set_split(&mut self, split_index: u8) -> &mut Self {
         assert!(split_index <= I::NUM_FILTER_BANKS);
+        let (start_idx, end_idx) = if split_index < self.bank_count {
+            (split_index, self.bank_count)
+        } else {
+            (self.bank_count, split_index)
+        };
+        FilterBanks {
+            start_idx,
+            bank_count: end_idx - start_idx,
+            can: self.registers(),
+        }
+        .clear();
+
         self.registers()
             .fmr
             .modify(|_, w| unsafe { w.can2sb().bits(split_index) });
@@ -321,7 +619,7 @@ pub struct SlaveFilters<'a, I: Instance> {
 
 impl<I: Instance> SlaveFilters<'_, I> {
     fn registers(&self) -> &RegisterBlock {
-        unsafe { &*I::REGISTERS }
+        unsafe { &*I::register_block() }
     }
 
     fn banks_imm(&self) -> FilterBanks<'_> {
@@ -357,10 +655,161 @@ impl<I: Instance> SlaveFilters<'_, I> {
     }
 
     /// Configures a filter bank according to `config` and enables it.
-    pub fn enable_bank(&mut self, index: u8, config: impl Into<BankConfig>) -> &mut Self {
-        self.banks_imm().enable(index, config.into());
+    ///
+    /// Frames accepted by the bank are stored in the receive FIFO given by `fifo`.
+    ///
+    /// If `index` is out of bounds, this will panic.
+    pub fn enable_bank(
+        &mut self,
+        index: u8,
+        fifo: Fifo,
+        config: impl Into<BankConfig>,
+    ) -> &mut Self {
+        self.banks_imm().enable(index, fifo, config.into());
         self
     }
+
+    /// Reads back the configuration of an enabled filter bank.
+    ///
+    /// Returns the FIFO and configuration the bank was enabled with, or `None` if the bank is
+    /// disabled.
+    ///
+    /// If `index` is out of bounds, this will panic.
+    pub fn get(&self, index: u8) -> Option<(Fifo, BankConfig)> {
+        self.banks_imm().get(index)
+    }
+
+    /// Returns an iterator over the index, FIFO and configuration of all enabled filter banks.
+    pub fn iter(&self) -> impl Iterator<Item = (u8, Fifo, BankConfig)> + '_ {
+        self.banks_imm().iter()
+    }
+}
+
+impl BankConfig {
+    fn is_list(&self) -> bool {
+        matches!(self, BankConfig::List16(_) | BankConfig::List32(_))
+    }
+
+    fn is_32bit(&self) -> bool {
+        matches!(self, BankConfig::List32(_) | BankConfig::Mask32(_))
+    }
+
+    /// Returns `true` if a filter bank with this configuration accepts `frame`.
+    fn accepts(&self, frame: &Frame) -> bool {
+        let id32 = frame.id.0;
+        // The 16-bit filter layout contains STID, RTR, IDE and the 3 upper bits of EXID.
+        let id16 = ((id32 >> 16) & 0xFFE0) as u16
+            | if frame.id.rtr() { F16_RTR } else { 0 }
+            | if frame.id.is_extended() { F16_IDE } else { 0 }
+            | ((id32 >> 18) & 0b111) as u16;
+
+        match self {
+            BankConfig::List16(entries) => entries.iter().any(|entry| entry.0 == id16),
+            BankConfig::List32(entries) => entries.iter().any(|entry| entry.0 == id32),
+            BankConfig::Mask16(masks) => masks.iter().any(|m| id16 & m.mask == m.id & m.mask),
+            BankConfig::Mask32(m) => id32 & m.mask == m.id & m.mask,
+        }
+    }
+}
+
+/// Filters received frames in software.
+///
+/// This is useful when all hardware filter banks are in use: The hardware filters can be
+/// configured to accept a superset of the wanted frames, which are then filtered further in
+/// software. A `SoftwareFilter` holds up to `N` bank configurations, and accepts a frame if any of
+/// them matches it, using the same rules as the hardware filter banks. Like the hardware, an empty
+/// filter rejects all frames.
+///
+/// ```
+/// use bxcan::filter::{Mask32, SoftwareFilter};
+/// use bxcan::{ExtendedId, Frame, StandardId};
+///
+/// let mut filter = SoftwareFilter::<4>::new();
+/// let id = StandardId::new(0x100).unwrap();
+/// filter.add(Mask32::frames_with_std_id(id, StandardId::new(0x700).unwrap())).unwrap();
+///
+/// let frame = Frame::new_data(StandardId::new(0x123).unwrap(), []);
+/// assert_eq!(filter.filter(frame.clone()), Some(frame));
+/// let frame = Frame::new_data(ExtendedId::new(0x123).unwrap(), []);
+/// assert_eq!(filter.filter(frame), None);
+/// assert_eq!(filter.rejected(), 1);
+/// ```
+pub struct SoftwareFilter<const N: usize> {
+    banks: Vec<BankConfig, N>,
+    rejected: u32,
+}
+
+impl<const N: usize> SoftwareFilter<N> {
+    /// Creates an empty filter that rejects all frames.
+    pub const fn new() -> Self {
+        Self {
+            banks: Vec::new(),
+            rejected: 0,
+        }
+    }
+
+    /// Adds a bank configuration to the filter.
+    ///
+    /// Returns `Err` with `config` if the filter already holds `N` configurations.
+    pub fn add(&mut self, config: impl Into<BankConfig>) -> Result<(), BankConfig> {
+        self.banks.push(config.into())
+    }
+
+    /// Removes all bank configurations, so that all frames are rejected.
+    pub fn clear(&mut self) {
+        self.banks.clear();
+    }
+
+    /// Returns `true` if any of the configured banks accepts `frame`.
+    pub fn accepts(&self, frame: &Frame) -> bool {
+        self.banks.iter().any(|bank| bank.accepts(frame))
+    }
+
+    /// Returns `frame` if it is accepted, or counts it as rejected and returns `None`.
+    pub fn filter(&mut self, frame: Frame) -> Option<Frame> {
+        if self.accepts(&frame) {
+            Some(frame)
+        } else {
+            self.rejected = self.rejected.wrapping_add(1);
+            None
+        }
+    }
+
+    /// Returns the next accepted frame returned by `receive`, discarding rejected frames.
+    ///
+    /// `receive` is typically a closure calling [`Rx0::receive`][crate::Rx0::receive] or
+    /// [`Rx1::receive`][crate::Rx1::receive]. Errors are passed through.
+    pub fn receive(
+        &mut self,
+        mut receive: impl FnMut() -> nb::Result<Frame, OverrunError>,
+    ) -> nb::Result<Frame, OverrunError> {
+        loop {
+            if let Some(frame) = self.filter(receive()?) {
+                return Ok(frame);
+            }
+        }
+    }
+
+    /// Returns the number of frames rejected by [`SoftwareFilter::filter`] and
+    /// [`SoftwareFilter::receive`].
+    ///
+    /// The counter wraps around at `u32::MAX`.
+    #[inline]
+    pub fn rejected(&self) -> u32 {
+        self.rejected
+    }
+
+    /// Resets the counter of rejected frames to 0.
+    pub fn reset_rejected(&mut self) {
+        self.rejected = 0;
+    }
+}
+
+impl<const N: usize> Default for SoftwareFilter<N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 struct FilterBanks<'a> {
@@ -369,7 +818,7 @@ struct FilterBanks<'a> {
     can: &'a RegisterBlock,
 }
 
-impl FilterBanks<'_> {
+impl<'a> FilterBanks<'a> {
     fn clear(&mut self) {
         let mask = filter_bitmask(self.start_idx, self.bank_count);
 
@@ -384,6 +833,86 @@ impl FilterBanks<'_> {
         assert!((self.start_idx..self.start_idx + self.bank_count).contains(&index));
     }
 
+    fn get(&self, index: u8) -> Option<(Fifo, BankConfig)> {
+        self.assert_bank_index(index);
+
+        let bit = 1 << index;
+        if self.can.fa1r.read().bits() & bit == 0 {
+            return None;
+        }
+
+        let fifo = if self.can.ffa1r.read().bits() & bit == 0 {
+            Fifo::Fifo0
+        } else {
+            Fifo::Fifo1
+        };
+        let list = self.can.fm1r.read().bits() & bit != 0;
+        let scale32 = self.can.fs1r.read().bits() & bit != 0;
+
+        let bank = &self.can.fb[usize::from(index)];
+        let fxr1 = bank.fr1.read().bits();
+        let fxr2 = bank.fr2.read().bits();
+        let lo = |fxr: u32| fxr as u16;
+        let hi = |fxr: u32| (fxr >> 16) as u16;
+        let config = match (list, scale32) {
+            (true, false) => BankConfig::List16([
+                ListEntry16(lo(fxr1)),
+                ListEntry16(hi(fxr1)),
+                ListEntry16(lo(fxr2)),
+                ListEntry16(hi(fxr2)),
+            ]),
+            (true, true) => BankConfig::List32([ListEntry32(fxr1), ListEntry32(fxr2)]),
+            (false, false) => BankConfig::Mask16([
+                Mask16 {
+                    id: lo(fxr1),
+                    mask: hi(fxr1),
+                },
+                Mask16 {
+                    id: lo(fxr2),
+                    mask: hi(fxr2),
+                },
+            ]),
+            (false, true) => BankConfig::Mask32(Mask32 {
+                id: fxr1,
+                mask: fxr2,
+            }),
+        };
+        Some((fifo, config))
+    }
+
+    /// Returns the index of a disabled bank, and whether filter initialization mode is needed to
+    /// configure it for `fifo` and `config`.
+    ///
+    /// Banks that can be configured without it are preferred.
+    fn find_free(&self, fifo: Fifo, config: &BankConfig) -> Option<(u8, bool)> {
+        let active = self.can.fa1r.read().bits();
+        let fifo1 = self.can.ffa1r.read().bits();
+        let list = self.can.fm1r.read().bits();
+        let scale32 = self.can.fs1r.read().bits();
+
+        let mut found = None;
+        for index in self.start_idx..self.start_idx + self.bank_count {
+            let bit = 1 << index;
+            if active & bit != 0 {
+                continue;
+            }
+
+            let matches = (fifo1 & bit != 0) == (fifo == Fifo::Fifo1)
+                && (list & bit != 0) == config.is_list()
+                && (scale32 & bit != 0) == config.is_32bit();
+            if matches {
+                return Some((index, false));
+            }
+            found = found.or(Some((index, true)));
+        }
+        found
+    }
+
+    fn iter(self) -> impl Iterator<Item = (u8, Fifo, BankConfig)> + 'a {
+        (self.start_idx..self.start_idx + self.bank_count)
+            .filter_map(move |index| self.get(index).map(|(fifo, config)| (index, fifo, config)))
+    }
+
     fn disable(&mut self, index: u8) {
         self.assert_bank_index(index);
 
@@ -392,11 +921,24 @@ impl FilterBanks<'_> {
             .modify(|r, w| unsafe { w.bits(r.bits() & !(1 << index)) })
     }
 
-    fn enable(&mut self, index: u8, config: BankConfig) {
+    fn enable(&mut self, index: u8, fifo: Fifo, config: BankConfig) {
         self.assert_bank_index(index);
 
+        // The bank must be deactivated while it is being reconfigured.
+        self.disable(index);
+
+        // Configure FIFO assignment.
+        self.can.ffa1r.modify(|r, w| {
+            let mut bits = r.bits();
+            match fifo {
+                Fifo::Fifo0 => bits &= !(1 << index),
+                Fifo::Fifo1 => bits |= 1 << index,
+            }
+            unsafe { w.bits(bits) }
+        });
+
         // Configure mode.
-        let mode = matches!(config, BankConfig::List16(_) | BankConfig::List32(_));
+        let mode = config.is_list();
         self.can.fm1r.modify(|r, w| {
             let mut bits = r.bits();
             if mode {
@@ -408,7 +950,7 @@ impl FilterBanks<'_> {
         });
 
         // Configure scale.
-        let scale = matches!(config, BankConfig::List32(_) | BankConfig::Mask32(_));
+        let scale = config.is_32bit();
         self.can.fs1r.modify(|r, w| {
             let mut bits = r.bits();
             if scale {
@@ -469,4 +1011,124 @@ mod tests {
         assert_eq!(filter_bitmask(8, 1), 0x100);
         assert_eq!(filter_bitmask(8, 4), 0xf00);
     }
+
+    #[test]
+    fn mask_helpers() {
+        let std = |id| Frame::new_data(StandardId::new(id).unwrap(), []);
+        let ext = |id| Frame::new_data(ExtendedId::new(id).unwrap(), []);
+
+        let base = StandardId::new(0x120).unwrap();
+        let configs: [BankConfig; 2] = [
+            [Mask16::frames_with_std_id_prefix(base, 7); 2].into(),
+            Mask32::frames_with_std_id_prefix(base, 7).into(),
+        ];
+        for config in &configs {
+            assert!(config.accepts(&std(0x120)) && config.accepts(&std(0x12F)));
+            assert!(!config.accepts(&std(0x130)) && !config.accepts(&std(0x110)));
+            assert!(!config.accepts(&ext(0x120)));
+        }
+        assert!(BankConfig::from(Mask32::frames_with_std_id_prefix(base, 0)).accepts(&std(0x7FF)));
+
+        let config = BankConfig::from(Mask32::frames_with_ext_id_prefix(
+            ExtendedId::new(0x1800_0000).unwrap(),
+            2,
+        ));
+        assert!(config.accepts(&ext(0x1FFF_FFFF)));
+        assert!(!config.accepts(&ext(0x0FFF_FFFF)) && !config.accepts(&std(0)));
+
+        let ids = [0x101, 0x103, 0x105].map(|id| StandardId::new(id).unwrap());
+        let config = BankConfig::from(Mask32::covering_std_ids(&ids).unwrap());
+        for id in [0x101, 0x103, 0x105, 0x107] {
+            assert!(config.accepts(&std(id)));
+        }
+        assert!(!config.accepts(&std(0x100)) && !config.accepts(&std(0x109)));
+        let config = BankConfig::from([Mask16::covering_std_ids(&ids[..1]).unwrap(); 2]);
+        assert!(config.accepts(&std(0x101)) && !config.accepts(&std(0x103)));
+        assert!(Mask16::covering_std_ids(&[]).is_none());
+
+        let ids = [0x1_0000, 0x1_0001].map(|id| ExtendedId::new(id).unwrap());
+        let config = BankConfig::from(Mask32::covering_ext_ids(&ids).unwrap());
+        assert!(config.accepts(&ext(0x1_0000)) && config.accepts(&ext(0x1_0001)));
+        assert!(!config.accepts(&ext(0x1_0002)));
+    }
+
+    #[test]
+    fn software_filter() {
+        let std_id = StandardId::new(0x123).unwrap();
+        let ext_id = ExtendedId::new(0x123 << 18 | 0x3_8000).unwrap();
+        let std_data = Frame::new_data(std_id, []);
+        let std_remote = Frame::new_remote(std_id, 0);
+        let ext_data = Frame::new_data(ext_id, []);
+
+        let mut filter = SoftwareFilter::<4>::new();
+        assert!(!filter.accepts(&std_data));
+
+        filter.add(Mask32::accept_all()).unwrap();
+        assert!(filter.accepts(&std_data) && filter.accepts(&ext_data));
+
+        filter.clear();
+        filter
+            .add(Mask32::frames_with_ext_id(ext_id, ExtendedId::MAX))
+            .unwrap();
+        assert!(filter.accepts(&ext_data));
+        assert!(!filter.accepts(&std_data));
+
+        filter.clear();
+        filter
+            .add([ListEntry32::remote_frames_with_id(std_id); 2])
+            .unwrap();
+        assert!(filter.accepts(&std_remote));
+        assert!(!filter.accepts(&std_data));
+
+        filter.clear();
+        filter
+            .add([ListEntry16::data_frames_with_id(std_id); 4])
+            .unwrap();
+        assert!(filter.accepts(&std_data));
+        assert!(!filter.accepts(&std_remote));
+        assert!(!filter.accepts(&ext_data));
+
+        filter.clear();
+        let mut mask = Mask16::frames_with_std_id(std_id, StandardId::new(0x7F0).unwrap());
+        filter
+            .add([
+                *mask.remote_frames_only(),
+                Mask16::frames_with_std_id(StandardId::ZERO, StandardId::MAX),
+            ])
+            .unwrap();
+        assert!(filter.accepts(&Frame::new_remote(StandardId::new(0x12F).unwrap(), 0)));
+        assert!(!filter.accepts(&std_data));
+        assert!(!filter.accepts(&ext_data));
+
+        assert_eq!(filter.filter(std_data), None);
+        assert_eq!(filter.rejected(), 1);
+        filter.reset_rejected();
+        assert_eq!(filter.rejected(), 0);
+    }
+
+    #[test]
+    fn software_filter_receive() {
+        let mut filter = SoftwareFilter::<1>::new();
+        filter
+            .add(Mask32::frames_with_std_id(
+                StandardId::ZERO,
+                StandardId::MAX,
+            ))
+            .unwrap();
+        let accepted = Frame::new_data(StandardId::ZERO, [1]);
+        let rejected = Frame::new_data(StandardId::MAX, [2]);
+
+        let mut results = IntoIterator::into_iter([
+            Ok(rejected.clone()),
+            Ok(accepted.clone()),
+            Ok(rejected),
+            Err(nb::Error::WouldBlock),
+        ]);
+        assert_eq!(filter.receive(|| results.next().unwrap()), Ok(accepted));
+        assert_eq!(
+            filter.receive(|| results.next().unwrap()),
+            Err(nb::Error::WouldBlock)
+        );
+        assert_eq!(filter.rejected(), 2);
+    }
 }
@@ -1,17 +1,27 @@
 #[cfg(test)]
 mod tests;
 
+use core::array::TryFromSliceError;
 use core::cmp::Ordering;
+use core::convert::{TryFrom, TryInto};
+use core::hash::{Hash, Hasher};
 use core::ops::{Deref, DerefMut};
 
-use crate::{Id, IdReg};
+use crate::{ExtendedId, Id, IdReg, StandardId};
 
 /// A CAN data or remote frame.
+///
+/// Two frames compare equal if they have the same identifier, are both data frames with the same
+/// data, or are both remote frames. The DLC of remote frames is not compared, and neither is a
+/// [raw DLC](Frame::raw_dlc) greater than 8. Use [`Frame::eq_exact`] to compare those as well. The
+/// `Hash` implementation is consistent with this equality.
 #[derive(Clone, Debug, Eq)]
 #[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
 pub struct Frame {
     pub(crate) id: IdReg,
     pub(crate) data: Data,
+    /// The DLC field as transmitted on the bus (`0..=15`).
+    raw_dlc: u8,
 }
 
 impl Frame {
@@ -22,9 +32,11 @@ impl Frame {
             Id::Extended(id) => IdReg::new_extended(id),
         };
 
+        let data = data.into();
         Self {
             id,
-            data: data.into(),
+            raw_dlc: data.len,
+            data,
         }
     }
 
@@ -39,11 +51,39 @@ impl Frame {
         let mut frame = Self::new_data(id, []);
         // Just extend the data length, even with no data present. The API does not hand out this
         // `Data` object.
-        frame.data.len = dlc;
+        frame.set_raw_dlc(dlc);
         frame.id = frame.id.with_rtr(true);
         frame
     }
 
+    /// Replaces the identifier of this frame, keeping its data or DLC.
+    pub fn set_id(&mut self, id: impl Into<Id>) {
+        let id = match id.into() {
+            Id::Standard(id) => IdReg::new_standard(id),
+            Id::Extended(id) => IdReg::new_extended(id),
+        };
+        self.id = id.with_rtr(self.id.rtr());
+    }
+
+    /// Replaces the payload of this frame.
+    ///
+    /// If this is a remote frame, it is turned into a data frame.
+    pub fn set_data(&mut self, data: impl Into<Data>) {
+        self.data = data.into();
+        self.raw_dlc = self.data.len;
+        self.id = self.id.with_rtr(false);
+    }
+
+    /// Turns this frame into a remote frame (`true`) or a data frame (`false`).
+    ///
+    /// The DLC is kept. A remote frame turned into a data frame carries `dlc` zero bytes.
+    pub fn set_rtr(&mut self, rtr: bool) {
+        if rtr != self.is_remote_frame() {
+            self.data.bytes = [0; 8];
+        }
+        self.id = self.id.with_rtr(rtr);
+    }
+
     /// Returns true if this frame is an extended frame.
     #[inline]
     pub fn is_extended(&self) -> bool {
@@ -80,16 +120,55 @@ impl Frame {
         FramePriority(self.id)
     }
 
-    /// Returns the data length code (DLC) which is in the range 0..8.
+    /// Compares the priority of this frame with the priority of `other`.
+    ///
+    /// Returns [`Ordering::Greater`] if this frame has a higher priority, ie. would win the
+    /// arbitration against `other`. This is a shorthand for comparing the [`FramePriority`]s of
+    /// both frames, and can be used to sort frames or keep them in a priority queue.
+    #[inline]
+    pub fn cmp_priority(&self, other: &Self) -> Ordering {
+        self.priority().cmp(&other.priority())
+    }
+
+    /// Returns the data length code (DLC) which is in the range `0..=8`.
     ///
     /// For data frames the DLC value always matches the length of the data.
     /// Remote frames do not carry any data, yet the DLC can be greater than 0.
+    ///
+    /// Received frames with a DLC of 9 to 15 are reported as 8, see [`Frame::raw_dlc`].
     #[inline]
     pub fn dlc(&self) -> u8 {
         self.data.len() as u8
     }
 
-    /// Returns the frame data (0..8 bytes in length) if this is a data frame.
+    /// Returns the DLC field as received from the bus, which is in the range `0..=15`.
+    ///
+    /// The CAN specification allows DLC values of 9 to 15, which all mean that the frame carries 8
+    /// bytes. Such frames are received with 8 bytes of data and a [`Frame::dlc`] of 8, and this
+    /// method returns the original value. Frames created by this crate always have a `raw_dlc` that
+    /// matches their `dlc`.
+    ///
+    /// The raw DLC is transmitted unchanged, so received frames can be forwarded as they are.
+    #[inline]
+    pub fn raw_dlc(&self) -> u8 {
+        self.raw_dlc
+    }
+
+    /// Sets the DLC field from a mailbox register, clamping the data length to 8 bytes.
+    pub(crate) fn set_raw_dlc(&mut self, dlc: u8) {
+        self.raw_dlc = dlc & 0xF;
+        self.data.len = self.raw_dlc.min(8);
+    }
+
+    /// Returns `true` if both frames are equal, including the DLC of remote frames and the raw DLC.
+    ///
+    /// Unlike `==`, this distinguishes remote frames with different DLCs, and frames received with
+    /// a DLC of 8 and greater than 8. Data bytes beyond the DLC are not compared.
+    pub fn eq_exact(&self, other: &Self) -> bool {
+        self == other && self.raw_dlc == other.raw_dlc
+    }
+
+    /// Returns the frame data (0 to 8 bytes in length) if this is a data frame.
     ///
     /// If this is a remote frame, returns `None`.
     pub fn data(&self) -> Option<&Data> {
@@ -99,8 +178,89 @@ impl Frame {
             None
         }
     }
+
+    /// Encodes this frame in the 16-byte layout of the Linux SocketCAN `struct can_frame`.
+    ///
+    /// The `can_id` field is encoded in little-endian byte order, the native order of the x86 and
+    /// ARM hosts the frame is usually tunneled to. The data of remote frames is all zeroes.
+    pub fn to_socketcan_bytes(&self) -> [u8; 16] {
+        let can_id = match self.id() {
+            Id::Standard(id) => u32::from(id.as_raw()),
+            Id::Extended(id) => id.as_raw() | SOCKETCAN_EFF_FLAG,
+        };
+        let can_id = if self.is_remote_frame() {
+            can_id | SOCKETCAN_RTR_FLAG
+        } else {
+            can_id
+        };
+
+        let mut bytes = [0; 16];
+        bytes[0..4].copy_from_slice(&can_id.to_le_bytes());
+        bytes[4] = self.dlc();
+        if let Some(data) = self.data() {
+            bytes[8..8 + data.len()].copy_from_slice(data);
+        }
+        bytes
+    }
+
+    /// Returns the transmit mailbox register values encoding this frame.
+    ///
+    /// The result can be computed once and passed to [`Tx::transmit_raw`] for every transmission
+    /// of the frame.
+    ///
+    /// [`Tx::transmit_raw`]: crate::Tx::transmit_raw
+    pub fn registers(&self) -> MailboxRegisters {
+        MailboxRegisters {
+            tir: self.id.0,
+            tdtr: u32::from(self.raw_dlc),
+            tdlr: u32::from_ne_bytes([
+                self.data.bytes[0],
+                self.data.bytes[1],
+                self.data.bytes[2],
+                self.data.bytes[3],
+            ]),
+            tdhr: u32::from_ne_bytes([
+                self.data.bytes[4],
+                self.data.bytes[5],
+                self.data.bytes[6],
+                self.data.bytes[7],
+            ]),
+        }
+    }
+
+    /// Decodes a frame from the layout of the Linux SocketCAN `struct can_frame`.
+    ///
+    /// This is the inverse of [`Frame::to_socketcan_bytes`]. Returns `None` if `bytes` describes an
+    /// error frame, or if its length field is greater than 8.
+    pub fn from_socketcan_bytes(bytes: &[u8; 16]) -> Option<Self> {
+        let can_id = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        let len = bytes[4];
+        if can_id & SOCKETCAN_ERR_FLAG != 0 || len > 8 {
+            return None;
+        }
+
+        let id: Id = if can_id & SOCKETCAN_EFF_FLAG != 0 {
+            ExtendedId::new(can_id & ExtendedId::MAX.as_raw())?.into()
+        } else {
+            StandardId::new((can_id & u32::from(StandardId::MAX.as_raw())) as u16)?.into()
+        };
+
+        if can_id & SOCKETCAN_RTR_FLAG != 0 {
+            Some(Self::new_remote(id, len))
+        } else {
+            Some(Self::new_data(
+                id,
+                Data::new(&bytes[8..8 + usize::from(len)])?,
+            ))
+        }
+    }
 }
 
+// Flags in the `can_id` field of a SocketCAN `struct can_frame`.
+const SOCKETCAN_EFF_FLAG: u32 = 0x8000_0000;
+const SOCKETCAN_RTR_FLAG: u32 = 0x4000_0000;
+const SOCKETCAN_ERR_FLAG: u32 = 0x2000_0000;
+
 impl PartialEq for Frame {
     fn eq(&self, other: &Self) -> bool {
         match (self.data(), other.data()) {
@@ -111,6 +271,52 @@ impl PartialEq for Frame {
     }
 }
 
+impl Hash for Frame {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+        if let Some(data) = self.data() {
+            data.hash(state);
+        }
+    }
+}
+
+/// The values of the transmit mailbox registers encoding a [`Frame`], as returned by
+/// [`Frame::registers`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub struct MailboxRegisters {
+    tir: u32,
+    tdtr: u32,
+    tdlr: u32,
+    tdhr: u32,
+}
+
+impl MailboxRegisters {
+    /// Returns the value of the identifier register (`CAN_TIxR`), without the `TXRQ` bit.
+    #[inline]
+    pub fn tir(&self) -> u32 {
+        self.tir
+    }
+
+    /// Returns the value of the data length control and time stamp register (`CAN_TDTxR`).
+    #[inline]
+    pub fn tdtr(&self) -> u32 {
+        self.tdtr
+    }
+
+    /// Returns the value of the data low register (`CAN_TDLxR`), containing data bytes 0 to 3.
+    #[inline]
+    pub fn tdlr(&self) -> u32 {
+        self.tdlr
+    }
+
+    /// Returns the value of the data high register (`CAN_TDHxR`), containing data bytes 4 to 7.
+    #[inline]
+    pub fn tdhr(&self) -> u32 {
+        self.tdhr
+    }
+}
+
 /// Priority of a CAN frame.
 ///
 /// The priority of a frame is determined by the bits that are part of the *arbitration field*.
@@ -121,6 +327,7 @@ impl PartialEq for Frame {
 /// This struct wraps the *arbitration field* and implements `PartialOrd` and `Ord` accordingly,
 /// ordering higher priorities greater than lower ones.
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
 pub struct FramePriority(IdReg);
 
 /// Ordering is based on the Identifier and frame type (data vs. remote) and can be used to sort
@@ -137,6 +344,35 @@ impl PartialOrd for FramePriority {
     }
 }
 
+impl FramePriority {
+    /// Returns the raw value of the arbitration field.
+    ///
+    /// The value uses the layout of the `CAN_TIxR` register: The identifier is stored in bits
+    /// 31 to 21 (standard) or 31 to 3 (extended), followed by the *IDE* bit (bit 2) and the *RTR*
+    /// bit (bit 1). Bit 0 is always 0.
+    ///
+    /// In this layout, **lower** raw values have **higher** priority, so the raw value can be used
+    /// as a sort key in an ascending queue.
+    #[inline]
+    pub fn as_raw(&self) -> u32 {
+        self.0 .0
+    }
+
+    /// Creates a priority from a raw value returned by [`FramePriority::as_raw`].
+    ///
+    /// Returns `None` if `raw` is not a valid arbitration field: Bit 0 must be 0, and the bits of
+    /// the extended identifier must be 0 if the *IDE* bit is not set.
+    pub fn from_raw(raw: u32) -> Option<Self> {
+        const EXID_MASK: u32 = 0x001F_FFF8;
+
+        let extended = raw & IdReg::IDE_MASK != 0;
+        if raw & 1 != 0 || (!extended && raw & EXID_MASK != 0) {
+            return None;
+        }
+        Some(Self(IdReg(raw)))
+    }
+}
+
 impl PartialEq for FramePriority {
     fn eq(&self, other: &Self) -> bool {
         self.cmp(other) == Ordering::Equal
@@ -150,7 +386,8 @@ impl Eq for FramePriority {}
 /// Contains 0 to 8 Bytes of data.
 ///
 /// `Data` implements `From<[u8; N]>` for all `N` up to 8, which provides a convenient lossless
-/// conversion from fixed-length arrays.
+/// conversion from fixed-length arrays. The reverse conversion is provided by
+/// `TryFrom<&Data> for [u8; N]`, which fails unless the payload has exactly `N` bytes.
 #[derive(Debug, Copy, Clone)]
 pub struct Data {
     pub(crate) len: u8,
@@ -185,6 +422,103 @@ impl Data {
             bytes: [0; 8],
         }
     }
+
+    /// Appends as many bytes of `data` as fit into the payload.
+    ///
+    /// Returns the number of bytes that were appended. This is less than `data.len()` if the
+    /// payload would otherwise exceed 8 bytes.
+    pub fn extend_from_slice(&mut self, data: &[u8]) -> usize {
+        let start = usize::from(self.len);
+        let count = data.len().min(8 - start);
+        self.bytes[start..start + count].copy_from_slice(&data[..count]);
+        self.len += count as u8;
+        count
+    }
+
+    /// Extracts a signal of `len` bits starting at bit `start_bit` from the payload.
+    ///
+    /// Bits are numbered like in DBC files: Bit `n` is bit `n % 8` of byte `n / 8`, where bit 0 is
+    /// the least significant bit of a byte. For [`ByteOrder::Intel`] signals, `start_bit` is the
+    /// least significant bit of the signal, for [`ByteOrder::Motorola`] signals it is the most
+    /// significant bit.
+    ///
+    /// The value is returned unsigned and unscaled.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if `len` is not in the range `1..=64`, or if the signal does not fit into
+    /// the payload.
+    pub fn get_bits(&self, start_bit: u8, len: u8, order: ByteOrder) -> u64 {
+        let shift = self.signal_shift(start_bit, len, order);
+        (order.load(self.bytes) >> shift) & signal_mask(len)
+    }
+
+    /// Stores a signal of `len` bits starting at bit `start_bit` in the payload.
+    ///
+    /// See [`Data::get_bits`] for the bit numbering. Bits of `value` above `len` are ignored, and
+    /// the other bits of the payload are left unchanged.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if `len` is not in the range `1..=64`, or if the signal does not fit into
+    /// the payload.
+    pub fn set_bits(&mut self, start_bit: u8, len: u8, order: ByteOrder, value: u64) {
+        let shift = self.signal_shift(start_bit, len, order);
+        let mask = signal_mask(len) << shift;
+        let raw = order.load(self.bytes) & !mask | (value << shift) & mask;
+        self.bytes = order.store(raw);
+    }
+
+    /// Returns the position of the least significant bit of a signal in the `u64` loaded by
+    /// [`ByteOrder::load`].
+    fn signal_shift(&self, start_bit: u8, len: u8, order: ByteOrder) -> u32 {
+        assert!((1..=64).contains(&len), "invalid signal length");
+        let (start_bit, len) = (u32::from(start_bit), u32::from(len));
+        let payload_bits = u32::from(self.len) * 8;
+
+        // Position of the signal's least (Intel) or most (Motorola) significant bit, counted from
+        // the respective end of the payload.
+        let position = match order {
+            ByteOrder::Intel => start_bit,
+            ByteOrder::Motorola => start_bit / 8 * 8 + 7 - start_bit % 8,
+        };
+        assert!(position + len <= payload_bits, "signal exceeds the payload");
+
+        match order {
+            ByteOrder::Intel => position,
+            ByteOrder::Motorola => 64 - position - len,
+        }
+    }
+}
+
+/// The byte order of a signal packed into a [`Data`] payload.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub enum ByteOrder {
+    /// Little-endian: The least significant byte of the signal comes first.
+    Intel,
+    /// Big-endian: The most significant byte of the signal comes first.
+    Motorola,
+}
+
+impl ByteOrder {
+    fn load(self, bytes: [u8; 8]) -> u64 {
+        match self {
+            ByteOrder::Intel => u64::from_le_bytes(bytes),
+            ByteOrder::Motorola => u64::from_be_bytes(bytes),
+        }
+    }
+
+    fn store(self, raw: u64) -> [u8; 8] {
+        match self {
+            ByteOrder::Intel => raw.to_le_bytes(),
+            ByteOrder::Motorola => raw.to_be_bytes(),
+        }
+    }
+}
+
+fn signal_mask(len: u8) -> u64 {
+    u64::MAX >> (64 - u32::from(len))
 }
 
 impl Deref for Data {
@@ -217,6 +551,35 @@ impl AsMut<[u8]> for Data {
     }
 }
 
+impl IntoIterator for Data {
+    type Item = u8;
+    type IntoIter = core::iter::Take<core::array::IntoIter<u8, 8>>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIterator::into_iter(self.bytes).take(usize::from(self.len))
+    }
+}
+
+impl<'a> IntoIterator for &'a Data {
+    type Item = &'a u8;
+    type IntoIter = core::slice::Iter<'a, u8>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<const N: usize> TryFrom<&Data> for [u8; N] {
+    type Error = TryFromSliceError;
+
+    #[inline]
+    fn try_from(data: &Data) -> Result<Self, Self::Error> {
+        data.as_ref().try_into()
+    }
+}
+
 impl PartialEq for Data {
     fn eq(&self, other: &Self) -> bool {
         self.as_ref() == other.as_ref()
@@ -225,6 +588,12 @@ impl PartialEq for Data {
 
 impl Eq for Data {}
 
+impl Hash for Data {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_ref().hash(state);
+    }
+}
+
 #[cfg(feature = "unstable-defmt")]
 impl defmt::Format for Data {
     fn format(&self, fmt: defmt::Formatter<'_>) {
@@ -1,4 +1,8 @@
-use crate::{ExtendedId, Frame, StandardId};
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::hash::{Hash, Hasher};
+
+use crate::{ByteOrder, Data, ExtendedId, Frame, FramePriority, Id, StandardId};
 
 #[test]
 fn data_greater_remote() {
@@ -82,3 +86,207 @@ fn max_len() {
     Frame::new_data(StandardId::MAX, [0; 8]);
     Frame::new_remote(StandardId::MAX, 8);
 }
+
+#[test]
+fn socketcan_bytes() {
+    let frame = Frame::new_data(StandardId::new(0x123).unwrap(), [1, 2, 3]);
+    let bytes = frame.to_socketcan_bytes();
+    assert_eq!(
+        bytes,
+        [0x23, 0x01, 0, 0, 3, 0, 0, 0, 1, 2, 3, 0, 0, 0, 0, 0]
+    );
+    assert_eq!(Frame::from_socketcan_bytes(&bytes), Some(frame));
+
+    let frame = Frame::new_remote(ExtendedId::MAX, 8);
+    let bytes = frame.to_socketcan_bytes();
+    assert_eq!(&bytes[..8], [0xFF, 0xFF, 0xFF, 0xDF, 8, 0, 0, 0]);
+    assert_eq!(bytes[8..], [0; 8]);
+    assert_eq!(Frame::from_socketcan_bytes(&bytes), Some(frame));
+
+    // Error frames and invalid lengths are rejected.
+    let mut bytes = [0; 16];
+    bytes[3] = 0x20;
+    assert_eq!(Frame::from_socketcan_bytes(&bytes), None);
+    let mut bytes = [0; 16];
+    bytes[4] = 9;
+    assert_eq!(Frame::from_socketcan_bytes(&bytes), None);
+}
+
+#[test]
+fn mutate() {
+    let mut frame = Frame::new_data(StandardId::MAX, [1, 2, 3]);
+    frame.set_id(ExtendedId::MAX);
+    assert_eq!(frame, Frame::new_data(ExtendedId::MAX, [1, 2, 3]));
+
+    frame.set_rtr(true);
+    assert_eq!(frame, Frame::new_remote(ExtendedId::MAX, 3));
+    frame.set_id(StandardId::ZERO);
+    assert_eq!(frame, Frame::new_remote(StandardId::ZERO, 3));
+
+    frame.set_rtr(false);
+    assert_eq!(frame, Frame::new_data(StandardId::ZERO, [0, 0, 0]));
+
+    frame.set_rtr(true);
+    frame.set_data([4]);
+    assert_eq!(frame, Frame::new_data(StandardId::ZERO, [4]));
+}
+
+#[test]
+fn registers() {
+    let frame = Frame::new_data(StandardId::new(0x123).unwrap(), [1, 2, 3, 4, 5]);
+    let registers = frame.registers();
+    assert_eq!(registers.tir(), 0x123 << 21);
+    assert_eq!(registers.tdtr(), 5);
+    assert_eq!(registers.tdlr(), u32::from_ne_bytes([1, 2, 3, 4]));
+    assert_eq!(registers.tdhr(), u32::from_ne_bytes([5, 0, 0, 0]));
+
+    let frame = Frame::new_remote(ExtendedId::MAX, 8);
+    let registers = frame.registers();
+    assert_eq!(registers.tir(), 0x1FFF_FFFF << 3 | 0b110);
+    assert_eq!(registers.tdtr(), 8);
+}
+
+#[test]
+fn raw_dlc() {
+    let mut frame = Frame::new_data(StandardId::ZERO, [0xAA; 8]);
+    assert_eq!(frame.raw_dlc(), 8);
+
+    frame.set_raw_dlc(12);
+    assert_eq!(frame.dlc(), 8);
+    assert_eq!(frame.raw_dlc(), 12);
+    assert_eq!(frame.data().unwrap().len(), 8);
+    assert_eq!(frame.registers().tdtr(), 12);
+
+    frame.set_data([1, 2]);
+    assert_eq!(frame.raw_dlc(), 2);
+
+    let frame = Frame::new_remote(StandardId::ZERO, 3);
+    assert_eq!(frame.raw_dlc(), 3);
+}
+
+#[test]
+fn data_conversions() {
+    let mut data = Data::from([1, 2, 3]);
+    assert_eq!(data.extend_from_slice(&[4, 5]), 2);
+    assert_eq!(data.extend_from_slice(&[6, 7, 8, 9, 10]), 3);
+    assert_eq!(data.extend_from_slice(&[11]), 0);
+    assert_eq!(&data[..], [1, 2, 3, 4, 5, 6, 7, 8]);
+
+    assert_eq!(
+        <[u8; 8]>::try_from(&data).unwrap(),
+        [1, 2, 3, 4, 5, 6, 7, 8]
+    );
+    assert!(<[u8; 4]>::try_from(&data).is_err());
+
+    let data = Data::from([1, 2]);
+    assert!(data.into_iter().eq([1, 2].iter().copied()));
+    assert_eq!((&data).into_iter().sum::<u8>(), 3);
+}
+
+#[test]
+fn signals() {
+    let mut data = Data::from([0x12, 0xAB, 0xCD, 0x00]);
+    assert_eq!(data.get_bits(0, 16, ByteOrder::Intel), 0xAB12);
+    assert_eq!(data.get_bits(12, 4, ByteOrder::Intel), 0xA);
+    assert_eq!(data.get_bits(7, 16, ByteOrder::Motorola), 0x12AB);
+    // Starts at the lower nibble of byte 1 and continues with byte 2.
+    assert_eq!(data.get_bits(11, 12, ByteOrder::Motorola), 0xBCD);
+    assert_eq!(data.get_bits(0, 32, ByteOrder::Intel), 0x00CD_AB12);
+
+    data.set_bits(11, 12, ByteOrder::Motorola, 0x123);
+    assert_eq!(&data[..], [0x12, 0xA1, 0x23, 0x00]);
+    data.set_bits(24, 8, ByteOrder::Intel, 0x1FF);
+    assert_eq!(&data[..], [0x12, 0xA1, 0x23, 0xFF]);
+
+    let mut data = Data::from([0; 8]);
+    data.set_bits(0, 64, ByteOrder::Intel, u64::MAX);
+    assert_eq!(data.get_bits(7, 64, ByteOrder::Motorola), u64::MAX);
+}
+
+#[test]
+#[should_panic]
+fn signal_exceeds_payload() {
+    Data::from([0; 2]).get_bits(7, 12, ByteOrder::Intel);
+}
+
+/// FNV-1a, since `std`'s hashers are not available.
+#[derive(Default)]
+struct FnvHasher(u64);
+
+impl Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x100_0000_01B3);
+        }
+    }
+}
+
+fn hash(frame: &Frame) -> u64 {
+    let mut hasher = FnvHasher(0xCBF2_9CE4_8422_2325);
+    frame.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn eq_exact_and_hash() {
+    let id = StandardId::new(0x123).unwrap();
+
+    let remote1 = Frame::new_remote(id, 2);
+    let remote2 = Frame::new_remote(id, 4);
+    assert_eq!(remote1, remote2);
+    assert!(!remote1.eq_exact(&remote2));
+    assert_eq!(hash(&remote1), hash(&remote2));
+
+    let mut data1 = Frame::new_data(id, [1, 2, 3, 4, 5, 6, 7, 8]);
+    let data2 = data1.clone();
+    assert!(data1.eq_exact(&data2));
+    data1.set_raw_dlc(15);
+    assert_eq!(data1, data2);
+    assert!(!data1.eq_exact(&data2));
+    assert_eq!(hash(&data1), hash(&data2));
+
+    assert_ne!(hash(&data2), hash(&Frame::new_data(id, [1, 2, 3])));
+    assert_ne!(
+        hash(&Frame::new_data(id, [])),
+        hash(&Frame::new_remote(id, 0))
+    );
+}
+
+#[test]
+fn cmp_priority_and_id_ordering() {
+    let high = Frame::new_data(StandardId::new(0x100).unwrap(), []);
+    let low = Frame::new_data(StandardId::new(0x200).unwrap(), []);
+    assert_eq!(high.cmp_priority(&low), Ordering::Greater);
+    assert_eq!(low.cmp_priority(&high), Ordering::Less);
+    assert_eq!(high.cmp_priority(&high.clone()), Ordering::Equal);
+
+    assert!(high.id() < low.id());
+    let extended: Id = ExtendedId::new(0).unwrap().into();
+    assert!(low.id() < extended);
+}
+
+#[test]
+fn priority_raw() {
+    let frames = [
+        Frame::new_data(StandardId::new(0x100).unwrap(), []),
+        Frame::new_remote(StandardId::new(0x100).unwrap(), 0),
+        Frame::new_data(ExtendedId::new(0x100 << 18).unwrap(), []),
+        Frame::new_remote(ExtendedId::new(0x100 << 18 | 1).unwrap(), 0),
+        Frame::new_data(StandardId::new(0x101).unwrap(), []),
+    ];
+    for a in &frames {
+        let raw = a.priority().as_raw();
+        assert_eq!(FramePriority::from_raw(raw), Some(a.priority()));
+        for b in &frames {
+            assert_eq!(a.cmp_priority(b), b.priority().as_raw().cmp(&raw));
+        }
+    }
+
+    assert_eq!(FramePriority::from_raw(1), None);
+    assert_eq!(FramePriority::from_raw(0x8), None);
+    assert!(FramePriority::from_raw(0x8 | 0x4).is_some());
+}
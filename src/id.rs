@@ -1,7 +1,11 @@
 //! CAN Identifiers.
 
 /// Standard 11-bit CAN Identifier (`0..=0x7FF`).
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+///
+/// Identifiers are ordered by their raw value, so lower identifiers (with higher priority) compare
+/// less than higher ones.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
 pub struct StandardId(u16);
 
 impl StandardId {
@@ -42,7 +46,10 @@ impl StandardId {
 }
 
 /// Extended 29-bit CAN Identifier (`0..=1FFF_FFFF`).
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+///
+/// Like [`StandardId`], identifiers are ordered by their raw value.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
 pub struct ExtendedId(u32);
 
 impl ExtendedId {
@@ -89,7 +96,16 @@ impl ExtendedId {
 }
 
 /// A CAN Identifier (standard or extended).
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+///
+/// All standard identifiers compare less than all extended identifiers, and identifiers of the
+/// same kind are ordered by their raw value. This is not the order in which frames are arbitrated
+/// on the bus, use [`Frame::priority`] or [`Frame::cmp_priority`] for that.
+///
+/// [`Frame::priority`]: crate::Frame::priority
+/// [`Frame::cmp_priority`]: crate::Frame::cmp_priority
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Id {
     /// Standard 11-bit Identifier (`0..=0x7FF`).
     Standard(StandardId),
@@ -111,3 +127,60 @@ impl From<ExtendedId> for Id {
         Id::Extended(id)
     }
 }
+
+/// Creates a [`StandardId`] from a constant, checking its range at compile time.
+///
+/// This is useful for the fixed identifiers of a protocol, as it avoids the `Option` returned by
+/// [`StandardId::new`]:
+///
+/// ```
+/// use bxcan::{standard_id, Frame, StandardId};
+///
+/// const HEARTBEAT: StandardId = standard_id!(0x701);
+///
+/// let frame = Frame::new_data(standard_id!(0x123), [1, 2, 3]);
+/// ```
+///
+/// Out-of-range identifiers fail to compile:
+///
+/// ```compile_fail
+/// let id = bxcan::standard_id!(0x800);
+/// ```
+#[macro_export]
+macro_rules! standard_id {
+    ($raw:expr) => {{
+        const ID: $crate::StandardId = match $crate::StandardId::new($raw) {
+            ::core::option::Option::Some(id) => id,
+            ::core::option::Option::None => {
+                ::core::panic!("standard CAN ID out of range (> 0x7FF)")
+            }
+        };
+        ID
+    }};
+}
+
+/// Creates an [`ExtendedId`] from a constant, checking its range at compile time.
+///
+/// ```
+/// use bxcan::{extended_id, ExtendedId};
+///
+/// const NODE_STATUS: ExtendedId = extended_id!(0x18FF_5001);
+/// ```
+///
+/// Out-of-range identifiers fail to compile:
+///
+/// ```compile_fail
+/// let id = bxcan::extended_id!(0x2000_0000);
+/// ```
+#[macro_export]
+macro_rules! extended_id {
+    ($raw:expr) => {{
+        const ID: $crate::ExtendedId = match $crate::ExtendedId::new($raw) {
+            ::core::option::Option::Some(id) => id,
+            ::core::option::Option::None => {
+                ::core::panic!("extended CAN ID out of range (> 0x1FFF_FFFF)")
+            }
+        };
+        ID
+    }};
+}
@@ -3,7 +3,7 @@
 use core::ops;
 
 #[allow(unused_imports)] // for intra-doc links only
-use crate::{Can, Rx};
+use crate::{Can, Rx0};
 
 /// bxCAN interrupt sources.
 ///
@@ -33,20 +33,20 @@ pub enum Interrupt {
     /// Fires the **RX FIFO 0** interrupt when FIFO 0 holds a message.
     ///
     /// The interrupt handler must clear the interrupt condition by receiving all messages from the
-    /// FIFO by calling [`Can::receive`] or [`Rx::receive`].
+    /// FIFO by calling [`Can::receive`] or [`Rx0::receive`].
     Fifo0MessagePending = 1 << 1,
 
     /// Fires the **RX FIFO 0** interrupt when FIFO 0 holds 3 incoming messages.
     ///
     /// The interrupt handler must clear the interrupt condition by receiving at least one message
     /// from the FIFO (making it no longer "full"). This can be done by calling [`Can::receive`] or
-    /// [`Rx::receive`].
+    /// [`Rx0::receive`].
     Fifo0Full = 1 << 2,
 
     /// Fires the **RX FIFO 0** interrupt when FIFO 0 drops an incoming message.
     ///
     /// The interrupt handler must clear the interrupt condition by calling [`Can::receive`] or
-    /// [`Rx::receive`] (which will return an error).
+    /// [`Rx0::receive`] (which will return an error).
     Fifo0Overrun = 1 << 3,
 
     /// Fires the **RX FIFO 1** interrupt when FIFO 1 holds a message.
@@ -64,6 +64,35 @@ pub enum Interrupt {
     /// Behavior is otherwise identical to [`Self::Fifo0Overrun`].
     Fifo1Overrun = 1 << 6,
 
+    /// Fires the **SCE** interrupt when the error warning limit is reached (an error counter
+    /// is 96 or higher).
+    ///
+    /// This interrupt only fires if [`Self::Error`] is enabled as well.
+    ErrorWarning = 1 << 8,
+
+    /// Fires the **SCE** interrupt when the peripheral enters the
+    /// [`ErrorPassive`][crate::ErrorState::ErrorPassive] state.
+    ///
+    /// This interrupt only fires if [`Self::Error`] is enabled as well.
+    ErrorPassive = 1 << 9,
+
+    /// Fires the **SCE** interrupt when the peripheral enters the
+    /// [`BusOff`][crate::ErrorState::BusOff] state.
+    ///
+    /// This interrupt only fires if [`Self::Error`] is enabled as well.
+    BusOff = 1 << 10,
+
+    /// Fires the **SCE** interrupt when the last error code stored in the peripheral is updated
+    /// with a bus error.
+    ///
+    /// This interrupt only fires if [`Self::Error`] is enabled as well.
+    LastErrorCode = 1 << 11,
+
+    /// Fires the **SCE** interrupt when one of the enabled error conditions (eg.
+    /// [`Self::ErrorPassive`] or [`Self::BusOff`]) occurs.
+    ///
+    /// The interrupt handler must clear the interrupt condition by calling
+    /// [`Can::clear_error_interrupt`] or [`Can::on_error_state_change`].
     Error = 1 << 15,
 
     /// Fires the **SCE** interrupt when an incoming CAN frame is detected while the peripheral is
@@ -90,12 +119,59 @@ bitflags::bitflags! {
         const FIFO1_MESSAGE_PENDING = 1 << 4;
         const FIFO1_FULL = 1 << 5;
         const FIFO1_OVERRUN = 1 << 6;
+        const ERROR_WARNING = 1 << 8;
+        const ERROR_PASSIVE = 1 << 9;
+        const BUS_OFF = 1 << 10;
+        const LAST_ERROR_CODE = 1 << 11;
         const ERROR = 1 << 15;
         const WAKEUP = 1 << 16;
         const SLEEP = 1 << 17;
     }
 }
 
+impl Interrupts {
+    /// Decodes the raw status registers into the set of interrupt conditions that are active.
+    pub(crate) fn from_status(tsr: u32, rf0r: u32, rf1r: u32, msr: u32, esr: u32) -> Self {
+        let mut pending = Self::empty();
+        let mut set = |flag, condition| {
+            if condition {
+                pending |= flag;
+            }
+        };
+
+        // TSR: RQCP0/1/2
+        set(Self::TRANSMIT_MAILBOX_EMPTY, tsr & 0x0001_0101 != 0);
+
+        // RFxR: FMP, FULL, FOVR
+        set(Self::FIFO0_MESSAGE_PENDING, rf0r & 0b11 != 0);
+        set(Self::FIFO0_FULL, rf0r & (1 << 3) != 0);
+        set(Self::FIFO0_OVERRUN, rf0r & (1 << 4) != 0);
+        set(Self::FIFO1_MESSAGE_PENDING, rf1r & 0b11 != 0);
+        set(Self::FIFO1_FULL, rf1r & (1 << 3) != 0);
+        set(Self::FIFO1_OVERRUN, rf1r & (1 << 4) != 0);
+
+        // ESR: EWGF, EPVF, BOFF, LEC
+        set(Self::ERROR_WARNING, esr & (1 << 0) != 0);
+        set(Self::ERROR_PASSIVE, esr & (1 << 1) != 0);
+        set(Self::BUS_OFF, esr & (1 << 2) != 0);
+        set(Self::LAST_ERROR_CODE, esr & (0b111 << 4) != 0);
+
+        // MSR: ERRI, WKUI, SLAKI
+        set(Self::ERROR, msr & (1 << 2) != 0);
+        set(Self::WAKEUP, msr & (1 << 3) != 0);
+        set(Self::SLEEP, msr & (1 << 4) != 0);
+
+        pending
+    }
+}
+
+#[cfg(feature = "unstable-defmt")]
+impl defmt::Format for Interrupts {
+    fn format(&self, fmt: defmt::Formatter<'_>) {
+        defmt::write!(fmt, "Interrupts({=u32:#x})", self.bits())
+    }
+}
+
 impl From<Interrupt> for Interrupts {
     #[inline]
     fn from(i: Interrupt) -> Self {
@@ -122,9 +198,45 @@ mod tests {
             Interrupts::from(Interrupt::TransmitMailboxEmpty),
             Interrupts::TRANSMIT_MAILBOX_EMPTY
         );
+        assert_eq!(Interrupts::from(Interrupt::BusOff), Interrupts::BUS_OFF);
+
+        assert_eq!(
+            Interrupts::from(Interrupt::LastErrorCode),
+            Interrupts::LAST_ERROR_CODE
+        );
 
         let mut ints = Interrupts::FIFO0_FULL;
         ints |= Interrupt::Fifo1Full;
         assert_eq!(ints, Interrupts::FIFO0_FULL | Interrupts::FIFO1_FULL);
     }
+
+    #[test]
+    fn decode_status() {
+        assert_eq!(Interrupts::from_status(0, 0, 0, 0, 0), Interrupts::empty());
+        assert_eq!(
+            Interrupts::from_status(1 << 16, 0, 0, 0, 0),
+            Interrupts::TRANSMIT_MAILBOX_EMPTY
+        );
+        assert_eq!(
+            Interrupts::from_status(0, 0b11011, 0b00001, 0, 0),
+            Interrupts::FIFO0_MESSAGE_PENDING
+                | Interrupts::FIFO0_FULL
+                | Interrupts::FIFO0_OVERRUN
+                | Interrupts::FIFO1_MESSAGE_PENDING
+        );
+        assert_eq!(
+            Interrupts::from_status(0, 0, 0, 0b11100, 0b0110_0110),
+            Interrupts::ERROR
+                | Interrupts::WAKEUP
+                | Interrupts::SLEEP
+                | Interrupts::ERROR_PASSIVE
+                | Interrupts::BUS_OFF
+                | Interrupts::LAST_ERROR_CODE
+        );
+        // TEC/REC and the status bits of MSR do not map to interrupts.
+        assert_eq!(
+            Interrupts::from_status(0, 0, 0, 0b0000_0011, 0xFFFF_0000),
+            Interrupts::empty()
+        );
+    }
 }
@@ -0,0 +1,276 @@
+//! SAE J1939 identifiers.
+//!
+//! J1939 packs a priority, a Parameter Group Number ([`Pgn`]) and a [`SourceAddress`] into the
+//! 29-bit extended CAN identifier:
+//!
+//! | Bits  | 28..=26  | 25..=8 | 7..=0          |
+//! |-------|----------|--------|----------------|
+//! | Field | Priority | PGN    | Source Address |
+//!
+//! For PGNs in PDU1 format (PDU Format field below 240), the lowest 8 bits of the PGN are replaced
+//! by the destination address of the frame.
+//!
+//! ```
+//! use bxcan::j1939::{J1939Id, Pgn, SourceAddress};
+//! use bxcan::Frame;
+//!
+//! // Electronic Engine Controller 1, sent by the engine (address 0).
+//! let eec1 = Pgn::new(0xF004).unwrap();
+//! let id = J1939Id::new(3, eec1, SourceAddress::new(0x00)).unwrap();
+//! assert_eq!(id.as_extended_id().as_raw(), 0x0CF0_0400);
+//!
+//! let frame = Frame::new_j1939(id, [0xFF; 8]);
+//! assert_eq!(frame.j1939_id(), Some(id));
+//! ```
+
+use crate::{Data, ExtendedId, Frame, Id};
+
+/// The global destination address, which addresses all nodes on the bus.
+pub const GLOBAL_ADDRESS: u8 = 0xFF;
+
+/// An 18-bit J1939 Parameter Group Number (`0..=0x3FFFF`).
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub struct Pgn(u32);
+
+impl Pgn {
+    /// Tries to create a `Pgn` from a raw 32-bit integer.
+    ///
+    /// This will return `None` if `raw` is out of range of an 18-bit integer (`> 0x3FFFF`), or if
+    /// it is a PDU1 PGN (see [`Pgn::is_pdu1`]) with a non-zero PDU Specific field.
+    #[inline]
+    pub const fn new(raw: u32) -> Option<Self> {
+        let pgn = Self(raw);
+        if raw <= 0x3FFFF && !(pgn.is_pdu1() && pgn.pdu_specific() != 0) {
+            Some(pgn)
+        } else {
+            None
+        }
+    }
+
+    /// Returns this PGN as a raw 32-bit integer.
+    #[inline]
+    pub fn as_raw(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns the PDU Format (PF) field.
+    #[inline]
+    pub const fn pdu_format(&self) -> u8 {
+        (self.0 >> 8) as u8
+    }
+
+    /// Returns the PDU Specific (PS) field.
+    ///
+    /// For PDU2 PGNs, this is the group extension. For PDU1 PGNs, it is always 0.
+    #[inline]
+    pub const fn pdu_specific(&self) -> u8 {
+        self.0 as u8
+    }
+
+    /// Returns `true` if this PGN uses the PDU1 format, which carries a destination address.
+    ///
+    /// PDU1 PGNs have a PDU Format field below 240. PGNs with a PDU Format of 240 or greater use
+    /// the PDU2 format and are always broadcast.
+    #[inline]
+    pub const fn is_pdu1(&self) -> bool {
+        self.pdu_format() < 240
+    }
+}
+
+/// The address of the node that sent a J1939 frame.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub struct SourceAddress(u8);
+
+impl SourceAddress {
+    /// The null address, used by nodes that have not claimed an address yet.
+    pub const NULL: Self = Self(0xFE);
+
+    /// Creates a `SourceAddress` from a raw 8-bit integer.
+    #[inline]
+    pub const fn new(raw: u8) -> Self {
+        Self(raw)
+    }
+
+    /// Returns this address as a raw 8-bit integer.
+    #[inline]
+    pub fn as_raw(&self) -> u8 {
+        self.0
+    }
+}
+
+/// A J1939 identifier, consisting of priority, PGN, destination and source address.
+///
+/// Every [`ExtendedId`] can be interpreted as a `J1939Id` and vice versa.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub struct J1939Id(ExtendedId);
+
+impl J1939Id {
+    /// Creates a broadcast identifier.
+    ///
+    /// `priority` ranges from 0 (highest) to 7 (lowest), and `None` is returned if it is out of
+    /// range. Frames with a PDU1 `pgn` are sent to the [`GLOBAL_ADDRESS`].
+    pub fn new(priority: u8, pgn: Pgn, source: SourceAddress) -> Option<Self> {
+        let destination = if pgn.is_pdu1() { GLOBAL_ADDRESS } else { 0 };
+        Self::pack(priority, pgn, destination, source)
+    }
+
+    /// Creates an identifier for a frame sent to the node with address `destination`.
+    ///
+    /// Returns `None` if `priority` is greater than 7, or if `pgn` uses the PDU2 format, which
+    /// cannot carry a destination address.
+    pub fn new_destination_specific(
+        priority: u8,
+        pgn: Pgn,
+        destination: u8,
+        source: SourceAddress,
+    ) -> Option<Self> {
+        if !pgn.is_pdu1() {
+            return None;
+        }
+        Self::pack(priority, pgn, destination, source)
+    }
+
+    fn pack(priority: u8, pgn: Pgn, destination: u8, source: SourceAddress) -> Option<Self> {
+        if priority > 7 {
+            return None;
+        }
+
+        let raw = u32::from(priority) << 26
+            | (pgn.as_raw() | u32::from(destination)) << 8
+            | u32::from(source.as_raw());
+        // Safety: Priority is 3 bits, the PGN 18 bits and the source address 8 bits.
+        Some(Self(unsafe { ExtendedId::new_unchecked(raw) }))
+    }
+
+    /// Returns the priority, ranging from 0 (highest) to 7 (lowest).
+    #[inline]
+    pub fn priority(&self) -> u8 {
+        (self.0.as_raw() >> 26) as u8
+    }
+
+    /// Returns the Parameter Group Number.
+    ///
+    /// For PDU1 PGNs, the destination address is not part of the returned PGN.
+    pub fn pgn(&self) -> Pgn {
+        let pgn = Pgn((self.0.as_raw() >> 8) & 0x3FFFF);
+        if pgn.is_pdu1() {
+            Pgn(pgn.0 & !0xFF)
+        } else {
+            pgn
+        }
+    }
+
+    /// Returns the destination address, or `None` if the PGN uses the PDU2 (broadcast) format.
+    pub fn destination(&self) -> Option<u8> {
+        if self.pgn().is_pdu1() {
+            Some((self.0.as_raw() >> 8) as u8)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the address of the sender.
+    #[inline]
+    pub fn source(&self) -> SourceAddress {
+        SourceAddress(self.0.as_raw() as u8)
+    }
+
+    /// Returns the extended CAN identifier this identifier is encoded as.
+    #[inline]
+    pub fn as_extended_id(&self) -> ExtendedId {
+        self.0
+    }
+}
+
+impl From<ExtendedId> for J1939Id {
+    #[inline]
+    fn from(id: ExtendedId) -> Self {
+        Self(id)
+    }
+}
+
+impl From<J1939Id> for ExtendedId {
+    #[inline]
+    fn from(id: J1939Id) -> Self {
+        id.0
+    }
+}
+
+impl From<J1939Id> for Id {
+    #[inline]
+    fn from(id: J1939Id) -> Self {
+        Id::Extended(id.0)
+    }
+}
+
+impl Frame {
+    /// Creates a new data frame with a J1939 identifier.
+    pub fn new_j1939(id: J1939Id, data: impl Into<Data>) -> Self {
+        Self::new_data(id, data)
+    }
+
+    /// Returns the J1939 identifier of this frame, or `None` if it is a standard frame.
+    pub fn j1939_id(&self) -> Option<J1939Id> {
+        match self.id() {
+            Id::Standard(_) => None,
+            Id::Extended(id) => Some(J1939Id(id)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StandardId;
+
+    #[test]
+    fn pgn() {
+        assert!(Pgn::new(0x3FFFF).is_some());
+        assert!(Pgn::new(0x40000).is_none());
+        assert!(Pgn::new(0xEA00).unwrap().is_pdu1());
+        assert!(Pgn::new(0xEA01).is_none());
+        assert!(!Pgn::new(0xFEF1).unwrap().is_pdu1());
+    }
+
+    #[test]
+    fn pack_unpack() {
+        let request = Pgn::new(0xEA00).unwrap();
+        let id = J1939Id::new_destination_specific(6, request, 0x21, SourceAddress::new(0xF9));
+        let id = id.unwrap();
+        assert_eq!(id.as_extended_id().as_raw(), 0x18EA_21F9);
+        assert_eq!(id.priority(), 6);
+        assert_eq!(id.pgn(), request);
+        assert_eq!(id.destination(), Some(0x21));
+        assert_eq!(id.source(), SourceAddress::new(0xF9));
+
+        let id = J1939Id::new(6, request, SourceAddress::NULL).unwrap();
+        assert_eq!(id.destination(), Some(GLOBAL_ADDRESS));
+
+        let ccvs = Pgn::new(0xFEF1).unwrap();
+        let id = J1939Id::new(6, ccvs, SourceAddress::new(0x00)).unwrap();
+        assert_eq!(id.as_extended_id().as_raw(), 0x18FE_F100);
+        assert_eq!(id.pgn(), ccvs);
+        assert_eq!(id.destination(), None);
+        assert_eq!(
+            J1939Id::new_destination_specific(6, ccvs, 0x21, SourceAddress::new(0x00)),
+            None
+        );
+
+        assert_eq!(J1939Id::new(8, ccvs, SourceAddress::new(0x00)), None);
+    }
+
+    #[test]
+    fn frame() {
+        let id = J1939Id::from(ExtendedId::new(0x1CEC_FF00).unwrap());
+        let frame = Frame::new_j1939(id, [1, 2, 3]);
+        assert!(frame.is_extended());
+        assert_eq!(frame.j1939_id(), Some(id));
+        assert_eq!(frame.j1939_id().unwrap().pgn(), Pgn::new(0xEC00).unwrap());
+
+        let frame = Frame::new_data(StandardId::ZERO, []);
+        assert_eq!(frame.j1939_id(), None);
+    }
+}
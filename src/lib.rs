@@ -12,12 +12,21 @@
 //! - Support for interrupts emitted by the bxCAN peripheral.
 //! - Transmission respects CAN IDs and protects against priority inversion (a lower-priority frame
 //!   may be dequeued when enqueueing a higher-priority one).
+//! - A software [`TxQueue`] can buffer frames beyond the 3 hardware mailboxes while preserving
+//!   priority order.
+//! - An [`RxRingBuffer`] passes received frames from interrupt handlers to the application without
+//!   a critical section, and a [`TxCompletionQueue`] does the same for completed transmissions.
+//! - Remote frames can be answered automatically from an [`AutoReply`] table.
+//! - A [`Scheduler`] transmits frames periodically, eg. cyclic PDOs or heartbeats.
+//! - An [`RxWatchdog`] detects when periodic frames of other nodes stop arriving.
+//! - Frames can be forwarded between two instances using a [`Bridge`].
+//! - A [`Monitor`] observes the bus in listen-only mode, for building analyzers and loggers.
 //! - Optionally implements the [`embedded-can`] traits for interoperability.
+//! - Optional `async` API for use with async executors.
 //!
 //! # Limitations
 //!
-//! - Currently, only RX FIFO 0 is supported, and FIFO 1 will not be used.
-//! - Support for querying error states and handling error interrupts is incomplete.
+//! - Support for handling error interrupts is incomplete.
 //!
 //! # Cargo Features
 //!
@@ -25,11 +34,26 @@
 //! |---------|-------------|
 //! | `unstable-defmt` | Implements [`defmt`]'s `Format` trait for the types in this crate.[^1] |
 //! | `embedded-can-03` | Implements the [`embedded-can`] 0.3 traits. |
+//! | `embedded-can-04` | Implements the [`embedded-can`] 0.4 traits, and conversions from and to other `embedded-can` frame and ID types. |
+//! | `async` | Enables the [`asynch`] module, providing an `async` transmit and receive API. |
+//! | `serde` | Implements [`serde`]'s `Serialize` and `Deserialize` traits for frames and IDs. |
+//! | `ufmt` | Implements [`ufmt`]'s `uDebug` and `uDisplay` traits for frames, IDs and error types. |
+//! | `j1939` | Enables the [`j1939`] module, providing SAE J1939 identifier types. |
+//! | `canopen` | Enables the [`canopen`] module, providing CANopen COB-ID and frame helpers. |
+//! | `slcan` | Enables the [`slcan`] module, providing the SLCAN (Lawicel) ASCII encoding of frames. |
+//! | `candump` | Enables the [`candump`] module, providing the `candump` log file format of the Linux can-utils. |
+//! | `stats` | Enables [`Can::stats`], which counts received and transmitted frames and bus errors. |
+//! | `messages` | Enables the [`messages`] module, providing table-driven message and signal definitions. |
+//! | `fd-frames` | Enables the [`fd`] module, providing CAN FD frame types for gateways. |
+//! | `shared` | Enables [`SharedCan`], which shares a transmit half between interrupt handlers using [`critical-section`]. |
 //!
 //! [^1]: The specific version of defmt is unspecified and may be updated in a patch release.
 //!
 //! [`embedded-can`]: https://docs.rs/embedded-can
 //! [`defmt`]: https://docs.rs/defmt
+//! [`serde`]: https://docs.rs/serde
+//! [`ufmt`]: https://docs.rs/ufmt
+//! [`critical-section`]: https://docs.rs/critical-section
 
 #![doc(html_root_url = "https://docs.rs/bxcan/0.6.0")]
 // Deny a few warnings in doctests, since rustdoc `allow`s many warnings by default
@@ -37,26 +61,93 @@
 #![no_std]
 #![allow(clippy::unnecessary_operation)] // lint is bugged
 
+#[cfg(feature = "async")]
+pub mod asynch;
+mod auto_reply;
+pub mod bit_timing;
+mod bridge;
+mod broadcast;
+pub mod busload;
+#[cfg(feature = "candump")]
+pub mod candump;
+#[cfg(feature = "canopen")]
+pub mod canopen;
+mod control;
+mod counting_queue;
+mod dispatcher;
 #[cfg(feature = "embedded-can-03")]
 mod embedded_can;
+#[cfg(feature = "embedded-can-04")]
+mod embedded_can_04;
+mod error;
+mod event;
+#[cfg(feature = "fd-frames")]
+pub mod fd;
 pub mod filter;
 mod frame;
 mod id;
 mod interrupt;
+#[cfg(feature = "j1939")]
+pub mod j1939;
+#[cfg(feature = "messages")]
+pub mod messages;
+mod monitor;
 mod readme;
+mod registers;
+mod rx_buffer;
+mod rx_watchdog;
+mod scheduler;
+#[cfg(feature = "serde")]
+mod serde_impls;
+#[cfg(feature = "shared")]
+mod shared;
+mod shutdown;
+#[cfg(feature = "slcan")]
+pub mod slcan;
+#[cfg(feature = "stats")]
+mod stats;
+mod storm;
+mod tx_events;
+mod tx_queue;
+#[cfg(feature = "ufmt")]
+mod ufmt_impls;
 
 #[allow(clippy::all)] // generated code
 mod pac;
 
 pub use id::{ExtendedId, Id, StandardId};
 
-pub use crate::frame::{Data, Frame, FramePriority};
+pub use crate::auto_reply::AutoReply;
+pub use crate::bridge::Bridge;
+pub use crate::broadcast::{Broadcast, BroadcastProducer, Subscriber};
+pub use crate::control::Control;
+pub use crate::dispatcher::Dispatcher;
+pub use crate::error::{ErrorState, Lec};
+pub use crate::event::{ErrorEvents, Event, Events, RxEvent, SceEvent, TxEvent};
+pub use crate::frame::{ByteOrder, Data, Frame, FramePriority, MailboxRegisters};
 pub use crate::interrupt::{Interrupt, Interrupts};
+pub use crate::monitor::{Monitor, MonitorEvent};
 pub use crate::pac::can::RegisterBlock;
-
-use crate::filter::MasterFilters;
+pub use crate::registers::{FilterRegister, Register, Registers};
+pub use crate::rx_buffer::{RxConsumer, RxProducer, RxRingBuffer};
+pub use crate::rx_watchdog::RxWatchdog;
+pub use crate::scheduler::Scheduler;
+#[cfg(feature = "shared")]
+pub use crate::shared::SharedCan;
+pub use crate::shutdown::{ShutdownMode, ShutdownOnDrop};
+#[cfg(feature = "stats")]
+pub use crate::stats::Stats;
+pub use crate::storm::StormProtection;
+pub use crate::tx_events::{
+    TxCompletion, TxCompletionConsumer, TxCompletionProducer, TxCompletionQueue,
+};
+pub use crate::tx_queue::{BacklogOrdering, TxQueue, Watermark};
+
+use crate::filter::{BankConfig, FilterHandle, FiltersFull, MasterFilters, StaleFilterHandle};
+use crate::storm::StormGuard;
 use core::cmp::{Ord, Ordering};
-use core::convert::{Infallible, TryInto};
+use core::convert::Infallible;
+use core::fmt;
 use core::marker::PhantomData;
 use core::mem;
 use core::ptr::NonNull;
@@ -74,11 +165,70 @@ use self::pac::generic::*; // To make the PAC extraction build
 ///
 /// * The implementing type has ownership of the peripheral, preventing any other accesses to the
 ///   register block.
-/// * `REGISTERS` is a pointer to that peripheral's register block and can be safely accessed for as
-///   long as ownership or a borrow of the implementing type is present.
+/// * [`Instance::register_block`] (by default, `REGISTERS`) returns a pointer to that peripheral's
+///   register block, which can be safely accessed for as long as ownership or a borrow of the
+///   implementing type is present. It must return the same pointer every time it is called.
+///
+/// # Runtime addresses
+///
+/// If the address of the register block is only known at runtime, for example because it is
+/// provided by a HAL or the peripheral is a bxCAN-compatible IP at a non-standard address,
+/// override [`Instance::register_block`] instead of setting `REGISTERS`:
+///
+/// ```
+/// use core::sync::atomic::{AtomicPtr, Ordering};
+///
+/// use bxcan::{Instance, RegisterBlock};
+///
+/// /// Set once at startup, before creating the `Can` instance.
+/// static CAN_BASE: AtomicPtr<RegisterBlock> = AtomicPtr::new(core::ptr::null_mut());
+///
+/// struct RuntimeCan;
+///
+/// unsafe impl Instance for RuntimeCan {
+///     fn register_block() -> *mut RegisterBlock {
+///         CAN_BASE.load(Ordering::Relaxed)
+///     }
+/// }
+/// ```
 pub unsafe trait Instance {
     /// Pointer to the instance's register block.
-    const REGISTERS: *mut RegisterBlock;
+    ///
+    /// Defaults to a null pointer, in which case [`Instance::register_block`] must be overridden.
+    /// Using an instance that does neither fails to compile:
+    ///
+    /// ```compile_fail
+    /// use bxcan::Instance;
+    ///
+    /// struct NoRegisters;
+    ///
+    /// unsafe impl Instance for NoRegisters {}
+    ///
+    /// let _ = NoRegisters::register_block();
+    /// ```
+    const REGISTERS: *mut RegisterBlock = core::ptr::null_mut();
+
+    /// Returns a pointer to the instance's register block.
+    ///
+    /// This is called for every register access, so it should be cheap. The default
+    /// implementation returns `REGISTERS`.
+    #[inline(always)]
+    fn register_block() -> *mut RegisterBlock {
+        let () = RegistersSet::<Self>::VALID;
+
+        Self::REGISTERS
+    }
+}
+
+/// Checks at compile time that [`Instance::REGISTERS`] of `I` is set when the default
+/// [`Instance::register_block`] is used.
+struct RegistersSet<I: ?Sized>(PhantomData<I>);
+
+impl<I: Instance + ?Sized> RegistersSet<I> {
+    const VALID: () = assert!(
+        !I::REGISTERS.is_null(),
+        "`Instance::REGISTERS` must be set unless `Instance::register_block` is overridden"
+    );
 }
 
 /// A bxCAN instance that owns filter banks.
@@ -100,6 +250,9 @@ pub unsafe trait FilterOwner: Instance {
     ///
     /// This is usually either 14 or 28, and should be specified in the chip's reference manual or
     /// datasheet.
+    ///
+    /// Values outside of `1..=28` cause a compile-time error when the filters are accessed through
+    /// [`Can::modify_filters`].
     const NUM_FILTER_BANKS: u8;
 }
 
@@ -135,7 +288,7 @@ pub enum Error {
 /// Lower identifier values have a higher priority. Additionally standard frames
 /// have a higher priority than extended frames and data frames have a higher
 /// priority than remote frames.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
 struct IdReg(u32);
 
@@ -241,6 +394,9 @@ impl PartialOrd for IdReg {
     }
 }
 
+/// Mask of the bit timing fields of the `CAN_BTR` register (everything except the mode bits).
+const BTR_TIMING_MASK: u32 = 0x037F_03FF;
+
 /// Configuration proxy returned by [`Can::modify_config`].
 #[must_use = "`CanConfig` leaves the peripheral in uninitialized state, call `CanConfig::enable` or explicitly drop the value"]
 pub struct CanConfig<'a, I: Instance> {
@@ -260,27 +416,51 @@ impl<I: Instance> CanConfig<'_, I> {
     ///
     /// Then copy the `CAN_BUS_TIME` register value from the table and pass it as the `btr`
     /// parameter to this method.
+    ///
+    /// Alternatively, [`bit_timing::from_bitrate`] can compute the value at runtime.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if `btr` has any bits set that are not part of the bit timing
+    /// configuration.
     pub fn set_bit_timing(self, btr: u32) -> Self {
-        let can = self.can.registers();
-        can.btr.modify(|r, w| unsafe {
-            let mode_bits = r.bits() & 0xC000_0000;
-            w.bits(mode_bits | btr)
-        });
+        self.can.set_btr(btr);
         self
     }
 
-    /// Enables or disables loopback mode: Internally connects the TX and RX
-    /// signals together.
-    pub fn set_loopback(self, enabled: bool) -> Self {
-        let can = self.can.registers();
-        can.btr.modify(|_, w| w.lbkm().bit(enabled));
-        self
+    /// Configures the bit timings for `bitrate`, using the peripheral clock frequency provided by
+    /// `clock`.
+    ///
+    /// `clock` is typically the clock configuration type of a HAL that implements
+    /// [`PeripheralClock`][bit_timing::PeripheralClock], or the frequency in Hz as a `u32`. The
+    /// bit timing is computed by [`bit_timing::from_clock`], which places the sample point at
+    /// 87.5%. Use [`bit_timing::from_bitrate`] and [`CanConfig::set_bit_timing`] for other sample
+    /// points.
+    ///
+    /// If no bit timing achieves exactly `bitrate` with the given clock, the bit timing is left
+    /// unchanged, and `self` is returned along with the [`BitTimingError`][bit_timing::BitTimingError].
+    pub fn set_bitrate_from_clock(
+        self,
+        clock: &impl bit_timing::PeripheralClock,
+        bitrate: u32,
+    ) -> Result<Self, (Self, bit_timing::BitTimingError)> {
+        match bit_timing::from_clock(clock, bitrate) {
+            Ok(timing) => Ok(self.set_bit_timing(timing.btr())),
+            Err(e) => Err((self, e)),
+        }
     }
 
-    /// Enables or disables silent mode: Disconnects the TX signal from the pin.
-    pub fn set_silent(self, enabled: bool) -> Self {
+    /// Selects how the peripheral is connected to the bus.
+    ///
+    /// See [`Mode`] for details. [`Mode::Normal`] is used by default.
+    pub fn set_mode(self, mode: Mode) -> Self {
         let can = self.can.registers();
-        can.btr.modify(|_, w| w.silm().bit(enabled));
+        can.btr.modify(|_, w| {
+            w.lbkm()
+                .bit(mode.is_loopback())
+                .silm()
+                .bit(mode.is_silent())
+        });
         self
     }
 
@@ -296,13 +476,73 @@ impl<I: Instance> CanConfig<'_, I> {
         self
     }
 
+    /// Selects the order in which pending frames are transmitted.
+    ///
+    /// See [`TxOrdering`] for details. [`TxOrdering::Priority`] is used by default.
+    pub fn set_tx_ordering(self, ordering: TxOrdering) -> Self {
+        let can = self.can.registers();
+        can.mcr
+            .modify(|_, w| w.txfp().bit(ordering == TxOrdering::Fifo));
+        self
+    }
+
+    /// Selects which frame is lost when a frame is received while a receive FIFO is full.
+    ///
+    /// See [`OverrunMode`] for details. [`OverrunMode::Overwrite`] is used by default.
+    pub fn set_overrun_mode(self, mode: OverrunMode) -> Self {
+        let can = self.can.registers();
+        can.mcr
+            .modify(|_, w| w.rflm().bit(mode == OverrunMode::Discard));
+        self
+    }
+
+    /// Enables or disables time triggered communication mode.
+    ///
+    /// In this mode, the internal 16-bit timer of the peripheral is running, and its value at the
+    /// start of frame is stored for each received frame. The timestamp can be read with
+    /// [`Rx0::receive_timestamped`]. When the mode is disabled, the timestamp is not updated.
+    ///
+    /// Time triggered communication mode is disabled by default.
+    pub fn set_time_triggered_communication_mode(self, enabled: bool) -> Self {
+        let can = self.can.registers();
+        can.mcr.modify(|_, w| w.ttcm().bit(enabled));
+        self
+    }
+
+    /// Enables or disables the automatic wake-up feature.
+    ///
+    /// See [`Can::set_automatic_wakeup`] for details.
+    ///
+    /// Automatic wake-up is disabled by default.
+    pub fn set_automatic_wakeup(self, enabled: bool) -> Self {
+        self.can.set_automatic_wakeup(enabled);
+        self
+    }
+
+    /// Enables or disables automatic bus-off management.
+    ///
+    /// If this is enabled, the peripheral will automatically leave the bus-off state once it has
+    /// monitored 128 occurrences of 11 consecutive recessive bits on the bus. Otherwise, bus-off
+    /// recovery has to be initiated by software by calling [`Can::recover_from_bus_off`].
+    ///
+    /// No interrupt is raised when the peripheral leaves the bus-off state. Use
+    /// [`Can::on_error_state_change`] to find out when it has become error active again.
+    ///
+    /// Automatic bus-off management is enabled by default.
+    pub fn set_automatic_bus_off_management(self, enabled: bool) -> Self {
+        let can = self.can.registers();
+        can.mcr.modify(|_, w| w.abom().bit(enabled));
+        self
+    }
+
     /// Leaves initialization mode and enables the peripheral.
     ///
     /// To sync with the CAN bus, this will block until 11 consecutive recessive bits are detected
     /// on the bus.
     ///
     /// If you want to finish configuration without enabling the peripheral, you can call
-    /// [`CanConfig::leave_disabled`] or [`drop`] the [`CanConfig`] instead.
+    /// [`CanConfig::leave_disabled`] or [`drop`] the [`CanConfig`] instead. Use
+    /// [`CanConfig::try_enable`] to check the configuration first.
     pub fn enable(mut self) {
         self.leave_init_mode();
 
@@ -337,6 +577,24 @@ impl<I: Instance> CanConfig<'_, I> {
     }
 }
 
+impl<I: FilterOwner> CanConfig<'_, I> {
+    /// Checks the configuration, and enables the peripheral like [`CanConfig::enable`] if it is
+    /// valid.
+    ///
+    /// If the configuration is invalid, the peripheral stays in initialization mode, and the
+    /// [`CanConfig`] is returned along with the error, so that the configuration can be fixed.
+    /// See [`ConfigError`] for the checks that are performed.
+    pub fn try_enable(self) -> Result<(), (Self, ConfigError)> {
+        match self.can.validate_config() {
+            Ok(()) => {
+                self.enable();
+                Ok(())
+            }
+            Err(e) => Err((self, e)),
+        }
+    }
+}
+
 impl<I: Instance> Drop for CanConfig<'_, I> {
     #[inline]
     fn drop(&mut self) {
@@ -363,27 +621,51 @@ impl<I: Instance> CanBuilder<I> {
     ///
     /// Then copy the `CAN_BUS_TIME` register value from the table and pass it as the `btr`
     /// parameter to this method.
-    pub fn set_bit_timing(self, btr: u32) -> Self {
-        let can = self.can.registers();
-        can.btr.modify(|r, w| unsafe {
-            let mode_bits = r.bits() & 0xC000_0000;
-            w.bits(mode_bits | btr)
-        });
+    ///
+    /// Alternatively, [`bit_timing::from_bitrate`] can compute the value at runtime.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if `btr` has any bits set that are not part of the bit timing
+    /// configuration.
+    pub fn set_bit_timing(mut self, btr: u32) -> Self {
+        self.can.set_btr(btr);
         self
     }
 
-    /// Enables or disables loopback mode: Internally connects the TX and RX
-    /// signals together.
-    pub fn set_loopback(self, enabled: bool) -> Self {
-        let can = self.can.registers();
-        can.btr.modify(|_, w| w.lbkm().bit(enabled));
-        self
+    /// Configures the bit timings for `bitrate`, using the peripheral clock frequency provided by
+    /// `clock`.
+    ///
+    /// `clock` is typically the clock configuration type of a HAL that implements
+    /// [`PeripheralClock`][bit_timing::PeripheralClock], or the frequency in Hz as a `u32`. The
+    /// bit timing is computed by [`bit_timing::from_clock`], which places the sample point at
+    /// 87.5%. Use [`bit_timing::from_bitrate`] and [`CanBuilder::set_bit_timing`] for other sample
+    /// points.
+    ///
+    /// If no bit timing achieves exactly `bitrate` with the given clock, the bit timing is left
+    /// unchanged, and `self` is returned along with the [`BitTimingError`][bit_timing::BitTimingError].
+    pub fn set_bitrate_from_clock(
+        self,
+        clock: &impl bit_timing::PeripheralClock,
+        bitrate: u32,
+    ) -> Result<Self, (Self, bit_timing::BitTimingError)> {
+        match bit_timing::from_clock(clock, bitrate) {
+            Ok(timing) => Ok(self.set_bit_timing(timing.btr())),
+            Err(e) => Err((self, e)),
+        }
     }
 
-    /// Enables or disables silent mode: Disconnects the TX signal from the pin.
-    pub fn set_silent(self, enabled: bool) -> Self {
+    /// Selects how the peripheral is connected to the bus.
+    ///
+    /// See [`Mode`] for details. [`Mode::Normal`] is used by default.
+    pub fn set_mode(self, mode: Mode) -> Self {
         let can = self.can.registers();
-        can.btr.modify(|_, w| w.silm().bit(enabled));
+        can.btr.modify(|_, w| {
+            w.lbkm()
+                .bit(mode.is_loopback())
+                .silm()
+                .bit(mode.is_silent())
+        });
         self
     }
 
@@ -399,13 +681,86 @@ impl<I: Instance> CanBuilder<I> {
         self
     }
 
+    /// Selects the order in which pending frames are transmitted.
+    ///
+    /// See [`TxOrdering`] for details. [`TxOrdering::Priority`] is used by default.
+    pub fn set_tx_ordering(self, ordering: TxOrdering) -> Self {
+        let can = self.can.registers();
+        can.mcr
+            .modify(|_, w| w.txfp().bit(ordering == TxOrdering::Fifo));
+        self
+    }
+
+    /// Selects which frame is lost when a frame is received while a receive FIFO is full.
+    ///
+    /// See [`OverrunMode`] for details. [`OverrunMode::Overwrite`] is used by default.
+    pub fn set_overrun_mode(self, mode: OverrunMode) -> Self {
+        let can = self.can.registers();
+        can.mcr
+            .modify(|_, w| w.rflm().bit(mode == OverrunMode::Discard));
+        self
+    }
+
+    /// Enables or disables time triggered communication mode.
+    ///
+    /// In this mode, the internal 16-bit timer of the peripheral is running, and its value at the
+    /// start of frame is stored for each received frame. The timestamp can be read with
+    /// [`Rx0::receive_timestamped`]. When the mode is disabled, the timestamp is not updated.
+    ///
+    /// Time triggered communication mode is disabled by default.
+    pub fn set_time_triggered_communication_mode(self, enabled: bool) -> Self {
+        let can = self.can.registers();
+        can.mcr.modify(|_, w| w.ttcm().bit(enabled));
+        self
+    }
+
+    /// Enables or disables the automatic wake-up feature.
+    ///
+    /// See [`Can::set_automatic_wakeup`] for details.
+    ///
+    /// Automatic wake-up is disabled by default.
+    pub fn set_automatic_wakeup(mut self, enabled: bool) -> Self {
+        self.can.set_automatic_wakeup(enabled);
+        self
+    }
+
+    /// Enables or disables automatic bus-off management.
+    ///
+    /// If this is enabled, the peripheral will automatically leave the bus-off state once it has
+    /// monitored 128 occurrences of 11 consecutive recessive bits on the bus. Otherwise, bus-off
+    /// recovery has to be initiated by software by calling [`Can::recover_from_bus_off`].
+    ///
+    /// No interrupt is raised when the peripheral leaves the bus-off state. Use
+    /// [`Can::on_error_state_change`] to find out when it has become error active again.
+    ///
+    /// Automatic bus-off management is enabled by default.
+    pub fn set_automatic_bus_off_management(self, enabled: bool) -> Self {
+        let can = self.can.registers();
+        can.mcr.modify(|_, w| w.abom().bit(enabled));
+        self
+    }
+
+    /// Restores a configuration captured by [`Can::release`].
+    ///
+    /// This overwrites the bit timing, all settings of the other configuration methods of this
+    /// builder, and the set of enabled interrupts. Filters have to be configured separately.
+    pub fn restore(self, config: &SavedConfig) -> Self {
+        let can = self.can.registers();
+        can.btr.write(|w| unsafe { w.bits(config.btr) });
+        can.mcr
+            .modify(|r, w| unsafe { w.bits(r.bits() & !SavedConfig::MCR_MASK | config.mcr) });
+        can.ier.write(|w| unsafe { w.bits(config.ier) });
+        self
+    }
+
     /// Leaves initialization mode and enables the peripheral.
     ///
     /// To sync with the CAN bus, this will block until 11 consecutive recessive bits are detected
     /// on the bus.
     ///
     /// If you want to finish configuration without enabling the peripheral, you can call
-    /// [`CanBuilder::leave_disabled`] instead.
+    /// [`CanBuilder::leave_disabled`] instead. Use [`CanBuilder::try_enable`] to check the
+    /// configuration first.
     pub fn enable(mut self) -> Can<I> {
         self.leave_init_mode();
 
@@ -441,9 +796,76 @@ impl<I: Instance> CanBuilder<I> {
     }
 }
 
+impl<I: FilterOwner> CanBuilder<I> {
+    /// Accesses the filter banks owned by this CAN peripheral.
+    ///
+    /// This allows configuring the filters before the peripheral is enabled, so that no frames
+    /// are lost or received unintentionally. See [`Can::modify_filters`].
+    pub fn modify_filters(&mut self) -> MasterFilters<'_, I> {
+        self.can.modify_filters()
+    }
+
+    /// Checks the configuration, and enables the peripheral like [`CanBuilder::enable`] if it is
+    /// valid.
+    ///
+    /// If the configuration is invalid, the peripheral stays in initialization mode, and the
+    /// builder is returned along with the error, so that the configuration can be fixed:
+    ///
+    /// ```no_run
+    /// # use bxcan::{FilterOwner, Instance, RegisterBlock};
+    /// # struct CAN1;
+    /// # unsafe impl Instance for CAN1 {
+    /// #     const REGISTERS: *mut RegisterBlock = 0x4000_6400 as *mut _;
+    /// # }
+    /// # unsafe impl FilterOwner for CAN1 {
+    /// #     const NUM_FILTER_BANKS: u8 = 14;
+    /// # }
+    /// use bxcan::filter::Mask32;
+    /// use bxcan::{Can, ConfigError, Fifo};
+    ///
+    /// fn setup(instance: CAN1) -> Can<CAN1> {
+    ///     match Can::builder(instance).set_bit_timing(0x0005_0008).try_enable() {
+    ///         Ok(can) => can,
+    ///         Err((mut builder, ConfigError::NoFilters)) => {
+    ///             builder
+    ///                 .modify_filters()
+    ///                 .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+    ///             builder.enable()
+    ///         }
+    ///         Err((_, e)) => panic!("{}", e),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// See [`ConfigError`] for the checks that are performed.
+    pub fn try_enable(self) -> Result<Can<I>, (Self, ConfigError)> {
+        match self.can.validate_config() {
+            Ok(()) => Ok(self.enable()),
+            Err(e) => Err((self, e)),
+        }
+    }
+}
+
 /// Interface to a bxCAN peripheral.
 pub struct Can<I: Instance> {
     instance: I,
+    /// Error state as of the last call to [`Can::on_error_state_change`].
+    error_state: ErrorState,
+    /// Error warning flag as of the last call to [`Can::on_sce_interrupt`].
+    error_warning: bool,
+    /// Events buffered by [`Can::on_sce_interrupt_buffered`].
+    error_events: ErrorEvents,
+    /// Whether [`Interrupt::LastErrorCode`] was disabled by [`Can::on_sce_interrupt_buffered`].
+    lec_masked: bool,
+    /// Cookies of the frames in the transmit mailboxes, see [`Can::transmit_with_cookie`].
+    cookies: [u32; 3],
+    /// Number of overruns of each FIFO, see [`OverrunError::count`].
+    overruns: [u32; 2],
+    /// Incremented by [`Can::modify_filters`], to detect stale [`FilterHandle`]s.
+    filter_generation: u32,
+    storm: StormGuard,
+    #[cfg(feature = "stats")]
+    stats: Stats,
 }
 
 impl<I> Can<I>
@@ -451,14 +873,61 @@ where
     I: Instance,
 {
     /// Creates a [`CanBuilder`] for constructing a CAN interface.
+    ///
+    /// This puts the peripheral in initialization mode, in which the configuration methods of the
+    /// [`CanBuilder`] take effect.
     pub fn builder(instance: I) -> CanBuilder<I> {
-        CanBuilder {
-            can: Can { instance },
-        }
+        let mut can = Can {
+            instance,
+            error_state: ErrorState::ErrorActive,
+            error_warning: false,
+            error_events: ErrorEvents::default(),
+            lec_masked: false,
+            cookies: [0; 3],
+            overruns: [0; 2],
+            filter_generation: 0,
+            storm: StormGuard::default(),
+            #[cfg(feature = "stats")]
+            stats: Stats::default(),
+        };
+        can.enter_init_mode();
+
+        // Enable automatic bus-off management by default.
+        can.registers().mcr.modify(|_, w| w.abom().set_bit());
+
+        CanBuilder { can }
     }
 
     fn registers(&self) -> &RegisterBlock {
-        unsafe { &*I::REGISTERS }
+        unsafe { &*I::register_block() }
+    }
+
+    /// Enters initialization mode, leaving sleep mode if necessary.
+    fn enter_init_mode(&mut self) {
+        let can = self.registers();
+        can.mcr
+            .modify(|_, w| w.sleep().clear_bit().inrq().set_bit());
+        loop {
+            let msr = can.msr.read();
+            if msr.slak().bit_is_clear() && msr.inak().bit_is_set() {
+                break;
+            }
+        }
+    }
+
+    /// Writes the bit timing configuration, preserving the mode bits.
+    ///
+    /// The peripheral must be in initialization mode.
+    fn set_btr(&mut self, btr: u32) {
+        assert!(
+            btr & !BTR_TIMING_MASK == 0,
+            "invalid bit timing configuration"
+        );
+
+        self.registers().btr.modify(|r, w| unsafe {
+            let mode_bits = r.bits() & !BTR_TIMING_MASK;
+            w.bits(mode_bits | btr)
+        });
     }
 
     /// Returns a reference to the peripheral instance.
@@ -477,23 +946,179 @@ where
         self.instance
     }
 
-    /// Configure bit timings and silent/loop-back mode.
+    /// Disables the CAN interface like [`Can::free`], and additionally returns a snapshot of its
+    /// configuration.
+    ///
+    /// The snapshot can be passed to [`CanBuilder::restore`] to restore the configuration, eg.
+    /// after the peripheral was powered down in a low-power sequence.
+    pub fn release(self) -> (I, SavedConfig) {
+        let can = self.registers();
+        let config = SavedConfig {
+            btr: can.btr.read().bits(),
+            mcr: can.mcr.read().bits() & SavedConfig::MCR_MASK,
+            ier: can.ier.read().bits(),
+        };
+        (self.free(), config)
+    }
+
+    /// Configure bit timings and the mode of the peripheral.
     ///
     /// Calling this method will enter initialization mode.
     pub fn modify_config(&mut self) -> CanConfig<'_, I> {
-        let can = self.registers();
+        self.enter_init_mode();
 
-        // Enter init mode.
-        can.mcr
-            .modify(|_, w| w.sleep().clear_bit().inrq().set_bit());
-        loop {
-            let msr = can.msr.read();
-            if msr.slak().bit_is_clear() && msr.inak().bit_is_set() {
+        CanConfig { can: self }
+    }
+
+    /// Changes the mode at runtime.
+    ///
+    /// This is a shorthand for changing the mode via [`Can::modify_config`]: The peripheral enters
+    /// initialization mode, which waits for an ongoing transmission or reception to finish, and is
+    /// enabled again afterwards. Frames pending in the transmit mailboxes or receive FIFOs are
+    /// kept.
+    ///
+    /// See [`Mode`] for details about the modes.
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.modify_config().set_mode(mode).enable();
+    }
+
+    /// Returns the currently configured mode.
+    pub fn mode(&self) -> Mode {
+        let btr = self.registers().btr.read();
+        Mode::from_bits(btr.lbkm().bit_is_set(), btr.silm().bit_is_set())
+    }
+
+    /// Changes the bit timing at runtime.
+    ///
+    /// Like [`Can::set_mode`], this temporarily enters initialization mode. Filters, enabled
+    /// interrupts and the other configuration are preserved.
+    ///
+    /// See [`CanConfig::set_bit_timing`] for the format of `btr`.
+    pub fn set_bit_timing(&mut self, btr: u32) {
+        self.modify_config().set_bit_timing(btr).enable();
+    }
+
+    /// Changes the bit timing at runtime, like [`Can::set_bit_timing`].
+    ///
+    /// This takes a [`BitTiming`][bit_timing::BitTiming], eg. as computed by
+    /// [`bit_timing::from_bitrate`], instead of a raw register value.
+    pub fn set_bitrate(&mut self, timing: bit_timing::BitTiming) {
+        self.set_bit_timing(timing.btr());
+    }
+
+    /// Returns the currently configured bit timing.
+    ///
+    /// This is the value of the `CAN_BTR` register with the silent and loopback mode bits masked
+    /// out, in the format accepted by [`CanConfig::set_bit_timing`]. It can be decoded with
+    /// [`BitTiming::from_btr`][bit_timing::BitTiming::from_btr].
+    pub fn bit_timing(&self) -> u32 {
+        self.registers().btr.read().bits() & BTR_TIMING_MASK
+    }
+
+    /// Detects the bitrate of the bus by trying each of the `candidates` bit timings in turn.
+    ///
+    /// Each candidate is configured in silent mode, so that the peripheral does not disturb the
+    /// bus, and is selected as soon as a frame is observed on the bus without any error. Frames do
+    /// not have to pass the filters for this. If no error-free frame is seen within `timeout`, the
+    /// next candidate is tried.
+    ///
+    /// `now` is called repeatedly to read a free-running tick counter (eg. a hardware timer)
+    /// which wraps around at `u32::MAX`. `timeout` is given in its ticks.
+    ///
+    /// Returns the detected bit timing, which stays configured. Otherwise, returns `None` and
+    /// restores the previous bit timing. In both cases, the previous [`Mode`] is restored
+    /// afterwards.
+    ///
+    /// See [`CanConfig::set_bit_timing`] for the format of the candidates. The [`bit_timing`]
+    /// module can be used to compute them from a list of bitrates.
+    pub fn detect_bit_timing(
+        &mut self,
+        candidates: &[u32],
+        timeout: u32,
+        mut now: impl FnMut() -> u32,
+    ) -> Option<u32> {
+        let previous_mode = self.mode();
+        let previous_btr = self.bit_timing();
+
+        let mut detected = None;
+        for &btr in candidates {
+            self.modify_config()
+                .set_mode(Mode::Silent)
+                .set_bit_timing(btr)
+                .enable();
+
+            // The peripheral resets the code to "no error" after an error-free frame.
+            self.registers().esr.write(|w| w.lec().custom());
+            let start = now();
+            while now().wrapping_sub(start) < timeout {
+                if self.registers().esr.read().lec().is_no_error() {
+                    detected = Some(btr);
+                    break;
+                }
+            }
+            if detected.is_some() {
                 break;
             }
         }
 
-        CanConfig { can: self }
+        self.modify_config()
+            .set_mode(previous_mode)
+            .set_bit_timing(detected.unwrap_or(previous_btr))
+            .enable();
+        // Errors caused by wrong candidates are not bus errors, so they are not counted.
+        self.registers().esr.write(|w| w.lec().no_error());
+
+        detected
+    }
+
+    /// Runs a self-test of the peripheral by looping back `frame` internally.
+    ///
+    /// This temporarily switches to [`Mode::SilentLoopback`], which disconnects the
+    /// peripheral from the bus. It then transmits `frame`, checks that the same frame is received,
+    /// and restores the previous mode.
+    ///
+    /// The received frame has to pass the configured filters, so `frame` should use an ID that is
+    /// accepted by them. The transmit mailboxes and receive FIFOs have to be empty when calling
+    /// this method, and no interrupt handler may concurrently receive frames.
+    pub fn self_test(&mut self, frame: &Frame) -> Result<(), SelfTestError> {
+        if !self.is_transmitter_idle() {
+            return Err(SelfTestError::Busy);
+        }
+        if self.fifo_status(Fifo::Fifo0).pending() != 0
+            || self.fifo_status(Fifo::Fifo1).pending() != 0
+        {
+            return Err(SelfTestError::Busy);
+        }
+
+        let mode = self.mode();
+        self.set_mode(Mode::SilentLoopback);
+
+        let result = self.loop_back_frame(frame);
+
+        self.set_mode(mode);
+
+        result
+    }
+
+    fn loop_back_frame(&mut self, frame: &Frame) -> Result<(), SelfTestError> {
+        let mailbox = match self.transmit(frame) {
+            Ok(status) => status.mailbox(),
+            Err(nb::Error::WouldBlock) => return Err(SelfTestError::Busy),
+            Err(nb::Error::Other(infallible)) => match infallible {},
+        };
+        while !self.is_transmitter_idle() {}
+
+        let status = self.mailbox_status(mailbox);
+        if !status.is_transmitted() {
+            return Err(SelfTestError::TransmitFailed(status));
+        }
+
+        // Reception finishes together with the transmission.
+        match self.receive() {
+            Ok(received) if received == *frame => Ok(()),
+            Ok(received) => Err(SelfTestError::Mismatch(received)),
+            Err(_) => Err(SelfTestError::NotReceived),
+        }
     }
 
     /// Configures the automatic wake-up feature.
@@ -520,8 +1145,7 @@ where
         let can = self.registers();
         let msr = can.msr.read();
         if msr.slak().bit_is_set() {
-            can.mcr
-                .modify(|_, w| w.abom().set_bit().sleep().clear_bit());
+            can.mcr.modify(|_, w| w.sleep().clear_bit());
             Err(nb::Error::WouldBlock)
         } else {
             Ok(())
@@ -531,6 +1155,9 @@ where
     /// Puts the peripheral in a sleep mode to save power.
     ///
     /// While in sleep mode, an incoming CAN frame will trigger [`Interrupt::Wakeup`] if enabled.
+    /// If automatic wake-up is enabled (see [`Can::set_automatic_wakeup`]), the frame also causes
+    /// the peripheral to leave sleep mode on its own, which can be checked with
+    /// [`Can::is_sleeping`].
     pub fn sleep(&mut self) {
         let can = self.registers();
         can.mcr
@@ -559,6 +1186,11 @@ where
         }
     }
 
+    /// Returns `true` if the peripheral is in sleep mode.
+    pub fn is_sleeping(&self) -> bool {
+        self.registers().msr.read().slak().bit_is_set()
+    }
+
     /// Starts listening for a CAN interrupt.
     pub fn enable_interrupt(&mut self, interrupt: Interrupt) {
         self.enable_interrupts(Interrupts::from_bits_truncate(interrupt as u32))
@@ -583,111 +1215,1066 @@ where
             .modify(|r, w| unsafe { w.bits(r.bits() & !interrupts.bits()) })
     }
 
+    /// Returns the set of interrupt conditions that are currently active.
+    ///
+    /// This decodes the status registers of the peripheral, regardless of which interrupts are
+    /// enabled. An interrupt fires when it is both enabled and contained in the returned set (the
+    /// flags of [`Interrupt::ErrorWarning`], [`Interrupt::ErrorPassive`], [`Interrupt::BusOff`]
+    /// and [`Interrupt::LastErrorCode`] additionally require [`Interrupt::Error`]).
+    pub fn pending_interrupts(&self) -> Interrupts {
+        let can = self.registers();
+        Interrupts::from_status(
+            can.tsr.read().bits(),
+            can.rfr[0].read().bits(),
+            can.rfr[1].read().bits(),
+            can.msr.read().bits(),
+            can.esr.read().bits(),
+        )
+    }
+
     /// Clears the pending flag of [`Interrupt::Sleep`].
     pub fn clear_sleep_interrupt(&self) {
-        let can = self.registers();
-        // Read-only register with write-1-to-clear, so `&self` is sufficient.
-        can.msr.write(|w| w.slaki().set_bit());
+        // Safety: The method only accesses write-1-to-clear bits.
+        unsafe { Sce::<I>::conjure() }.clear_sleep_interrupt()
     }
 
     /// Clears the pending flag of [`Interrupt::Wakeup`].
     pub fn clear_wakeup_interrupt(&self) {
-        let can = self.registers();
-        // Read-only register with write-1-to-clear, so `&self` is sufficient.
-        can.msr.write(|w| w.wkui().set_bit());
+        // Safety: The method only accesses write-1-to-clear bits.
+        unsafe { Sce::<I>::conjure() }.clear_wakeup_interrupt()
     }
 
-    /// Clears the "Request Completed" (RQCP) flag of a transmit mailbox.
+    /// Clears the pending flag of [`Interrupt::Error`].
+    pub fn clear_error_interrupt(&self) {
+        // Safety: The method only accesses write-1-to-clear bits.
+        unsafe { Sce::<I>::conjure() }.clear_error_interrupt()
+    }
+
+    /// Returns the current fault confinement state of the peripheral.
+    pub fn error_state(&self) -> ErrorState {
+        // Safety: The method only reads registers.
+        unsafe { Sce::<I>::conjure() }.error_state()
+    }
+
+    /// Returns the value of the transmit error counter (TEC).
     ///
-    /// Returns the [`Mailbox`] whose flag was cleared. If no mailbox has the flag set, returns
-    /// `None`.
+    /// The counter is incremented for errors detected while transmitting, and decremented for
+    /// successful transmissions. The peripheral becomes [`ErrorState::ErrorPassive`] when it
+    /// exceeds 127, and [`ErrorState::BusOff`] when it exceeds 255.
     ///
-    /// Once this function returns `None`, a pending [`Interrupt::TransmitMailboxEmpty`] is
-    /// considered acknowledged.
-    pub fn clear_request_completed_flag(&mut self) -> Option<Mailbox> {
-        let can = self.registers();
-        let tsr = can.tsr.read();
-        if tsr.rqcp0().bit_is_set() {
-            can.tsr.modify(|_, w| w.rqcp0().set_bit());
-            Some(Mailbox::Mailbox0)
-        } else if tsr.rqcp1().bit_is_set() {
-            can.tsr.modify(|_, w| w.rqcp1().set_bit());
-            Some(Mailbox::Mailbox1)
-        } else if tsr.rqcp2().bit_is_set() {
-            can.tsr.modify(|_, w| w.rqcp2().set_bit());
-            Some(Mailbox::Mailbox2)
-        } else {
-            None
-        }
+    /// While in bus-off state, the peripheral reuses this counter for tracking recovery, so the
+    /// value is not meaningful then.
+    pub fn transmit_error_counter(&self) -> u8 {
+        // Safety: The method only reads registers.
+        unsafe { Sce::<I>::conjure() }.transmit_error_counter()
     }
 
-    /// Clears a pending TX interrupt ([`Interrupt::TransmitMailboxEmpty`]).
+    /// Returns the value of the receive error counter (REC).
     ///
-    /// This does not return the mailboxes that have finished tranmission. If you need that
-    /// information, call [`Can::clear_request_completed_flag`] instead.
-    pub fn clear_tx_interrupt(&mut self) {
-        while self.clear_request_completed_flag().is_some() {}
+    /// The counter is incremented for errors detected while receiving, and decremented for
+    /// successful receptions. The peripheral becomes [`ErrorState::ErrorPassive`] when it exceeds
+    /// 127.
+    pub fn receive_error_counter(&self) -> u8 {
+        // Safety: The method only reads registers.
+        unsafe { Sce::<I>::conjure() }.receive_error_counter()
     }
 
-    /// Puts a CAN frame in a free transmit mailbox for transmission on the bus.
+    /// Returns the code of the last bus error detected by the peripheral.
     ///
-    /// Frames are transmitted to the bus based on their priority (identifier).
-    /// Transmit order is preserved for frames with identical identifiers.
-    /// If all transmit mailboxes are full, a higher priority frame replaces the
-    /// lowest priority frame, which is returned as `Ok(Some(frame))`.
-    pub fn transmit(&mut self, frame: &Frame) -> nb::Result<TransmitStatus, Infallible> {
-        // Safety: We have a `&mut self` and have unique access to the peripheral.
-        unsafe { Tx::<I>::conjure().transmit(frame) }
+    /// Returns `None` if no error has occurred since the code was last cleared with
+    /// [`Can::clear_last_error_code`]. The code is also reset by the peripheral when a frame was
+    /// transmitted or received without error.
+    pub fn last_error_code(&self) -> Option<Lec> {
+        // Safety: The method only reads registers.
+        unsafe { Sce::<I>::conjure() }.last_error_code()
     }
 
-    /// Returns `true` if no frame is pending for transmission.
-    pub fn is_transmitter_idle(&self) -> bool {
-        // Safety: Read-only operation.
-        unsafe { Tx::<I>::conjure().is_idle() }
+    /// Resets the last error code to "no error".
+    ///
+    /// With the `stats` feature enabled, the cleared error is counted in [`Stats::errors`].
+    pub fn clear_last_error_code(&mut self) {
+        #[cfg(feature = "stats")]
+        if let Some(lec) = self.last_error_code() {
+            self.stats.count_error(lec);
+        }
+
+        // All other fields of the register are read-only.
+        self.registers().esr.write(|w| w.lec().no_error());
     }
 
-    /// Attempts to abort the sending of a frame that is pending in a mailbox.
+    /// Overwrites the last error code, for testing error handling code.
     ///
-    /// If there is no frame in the provided mailbox, or its transmission succeeds before it can be
-    /// aborted, this function has no effect and returns `false`.
+    /// See [`Sce::inject_last_error_code`] for details. Injected errors are counted like real ones
+    /// when cleared.
+    pub fn inject_last_error_code(&mut self, lec: Lec) {
+        // Safety: The last error code is only written through `&mut self`.
+        unsafe { Sce::<I>::conjure() }.inject_last_error_code(lec)
+    }
+
+    /// Handles an error interrupt and returns the new [`ErrorState`] if it has changed.
+    ///
+    /// This should be called from the **SCE** interrupt handler when [`Interrupt::Error`] and
+    /// [`Interrupt::ErrorPassive`] and/or [`Interrupt::BusOff`] are enabled. It clears the pending
+    /// flag of [`Interrupt::Error`], and returns `Some` if the error state is different from the
+    /// one observed by the previous call.
+    ///
+    /// The peripheral only raises interrupts when the error state gets worse. Recovery (for
+    /// example from [`ErrorState::BusOff`] back to [`ErrorState::ErrorActive`] after automatic
+    /// bus-off management has kicked in) is not signaled, so this method should also be called
+    /// periodically, eg. from a timer interrupt, while the peripheral is not error active.
+    pub fn on_error_state_change(&mut self) -> Option<ErrorState> {
+        self.clear_error_interrupt();
+        self.update_error_state()
+    }
+
+    fn update_error_state(&mut self) -> Option<ErrorState> {
+        let state = self.error_state();
+        if state == self.error_state {
+            None
+        } else {
+            #[cfg(feature = "stats")]
+            if state == ErrorState::BusOff {
+                self.stats.count_bus_off();
+            }
+
+            self.error_state = state;
+            Some(state)
+        }
+    }
+
+    /// Handles a status change and error (**SCE**) interrupt and returns the next pending event.
+    ///
+    /// This clears the pending flags of [`Interrupt::Wakeup`], [`Interrupt::Sleep`] and
+    /// [`Interrupt::Error`], and returns `None` once there are no more events. It should be called
+    /// in a loop from the interrupt handler:
+    ///
+    /// ```no_run
+    /// # use bxcan::{Instance, RegisterBlock};
+    /// # struct CAN1;
+    /// # unsafe impl Instance for CAN1 {
+    /// #     const REGISTERS: *mut RegisterBlock = 0x4000_6400 as *mut _;
+    /// # }
+    /// use bxcan::{Can, ErrorState, SceEvent};
+    ///
+    /// fn can1_sce(can: &mut Can<CAN1>) {
+    ///     while let Some(event) = can.on_sce_interrupt() {
+    ///         match event {
+    ///             SceEvent::ErrorStateChanged(ErrorState::BusOff) => {
+    ///                 // Stop transmitting.
+    ///             }
+    ///             SceEvent::ErrorStateChanged(ErrorState::ErrorActive) => {
+    ///                 // Recovered, start transmitting again.
+    ///             }
+    ///             SceEvent::BusError(lec) => {
+    ///                 // Log `lec`.
+    ///             }
+    ///             _ => {}
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// Error state changes are detected like in [`Can::on_error_state_change`]: the error state is
+    /// compared to the one observed by the previous call, so recovery from the bus-off or error
+    /// passive state is reported as [`SceEvent::ErrorStateChanged`] with
+    /// [`ErrorState::ErrorActive`]. Since the peripheral raises no interrupt on recovery, the
+    /// **SCE** interrupt should be pended periodically (eg. with `cortex_m::peripheral::NVIC::pend`
+    /// from a timer interrupt) while the peripheral is not error active. Bus errors are only reported
+    /// if [`Interrupt::LastErrorCode`] is enabled, and their code is cleared in the process.
+    pub fn on_sce_interrupt(&mut self) -> Option<SceEvent> {
+        let msr = self.registers().msr.read();
+        if msr.wkui().bit_is_set() {
+            self.clear_wakeup_interrupt();
+            return Some(SceEvent::Wakeup);
+        }
+        if msr.slaki().bit_is_set() {
+            self.clear_sleep_interrupt();
+            return Some(SceEvent::SleepAcknowledged);
+        }
+        if msr.erri().bit_is_set() {
+            // The error conditions below are checked on every call, so further events caused by the
+            // same interrupt are not lost.
+            self.clear_error_interrupt();
+        }
+
+        if let Some(state) = self.update_error_state() {
+            return Some(SceEvent::ErrorStateChanged(state));
+        }
+
+        let warning = self.registers().esr.read().ewgf().bit_is_set();
+        if warning != self.error_warning {
+            self.error_warning = warning;
+            if warning {
+                return Some(SceEvent::ErrorWarning);
+            }
+        }
+
+        if self.registers().ier.read().lecie().bit_is_set() {
+            match self.last_error_code() {
+                None | Some(Lec::SoftwareSet) => {}
+                Some(lec) => {
+                    self.clear_last_error_code();
+                    self.count_storm_event(Interrupt::LastErrorCode);
+                    return Some(SceEvent::BusError(lec));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Handles an **SCE** interrupt like [`Can::on_sce_interrupt`], but buffers error events
+    /// instead of returning them.
+    ///
+    /// Error state changes, error warnings and bus errors are coalesced into [`ErrorEvents`],
+    /// which the application fetches with [`Can::take_error_events`]. Only [`SceEvent::Wakeup`]
+    /// and [`SceEvent::SleepAcknowledged`] are returned, so this should be called in a loop until
+    /// it returns `None`:
+    ///
+    /// ```no_run
+    /// # use bxcan::{Instance, RegisterBlock};
+    /// # struct CAN1;
+    /// # unsafe impl Instance for CAN1 {
+    /// #     const REGISTERS: *mut RegisterBlock = 0x4000_6400 as *mut _;
+    /// # }
+    /// use bxcan::Can;
+    ///
+    /// // Called from the SCE interrupt handler.
+    /// fn can1_sce(can: &mut Can<CAN1>) {
+    ///     while let Some(event) = can.on_sce_interrupt_buffered() {
+    ///         // Handle wakeup or sleep.
+    ///     }
+    /// }
+    ///
+    /// // Called periodically from the main loop.
+    /// fn poll(can: &mut Can<CAN1>) {
+    ///     let events = can.take_error_events();
+    ///     if let Some(lec) = events.bus_error() {
+    ///         // Log `lec`.
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// On a faulty bus, a bus error can be detected for every frame, which would keep the CPU busy
+    /// handling interrupts. So after a bus error has been buffered, [`Interrupt::LastErrorCode`]
+    /// is disabled until the next call to `take_error_events`.
+    pub fn on_sce_interrupt_buffered(&mut self) -> Option<SceEvent> {
+        loop {
+            let event = self.on_sce_interrupt()?;
+            match event {
+                SceEvent::Wakeup | SceEvent::SleepAcknowledged => return Some(event),
+                SceEvent::BusError(_) => {
+                    self.disable_interrupt(Interrupt::LastErrorCode);
+                    self.lec_masked = true;
+                }
+                SceEvent::ErrorStateChanged(_) | SceEvent::ErrorWarning => {}
+            }
+            self.error_events.record(event);
+        }
+    }
+
+    /// Returns and clears the error events buffered by [`Can::on_sce_interrupt_buffered`].
+    ///
+    /// This enables [`Interrupt::LastErrorCode`] again if it was disabled after a bus error.
+    pub fn take_error_events(&mut self) -> ErrorEvents {
+        let suspended = self.storm.suspended().contains(Interrupts::LAST_ERROR_CODE);
+        if mem::take(&mut self.lec_masked) && !suspended {
+            self.enable_interrupt(Interrupt::LastErrorCode);
+        }
+        mem::take(&mut self.error_events)
+    }
+
+    /// Configures the protection against interrupt storms, or disables it with `None`.
+    ///
+    /// On a flooded or faulty bus, [`Interrupt::Fifo0Overrun`], [`Interrupt::Fifo1Overrun`] and
+    /// [`Interrupt::LastErrorCode`] can fire so often that the CPU is kept busy handling them.
+    /// With the protection enabled, [`Can::on_rx_interrupt`] and [`Can::on_sce_interrupt`] count
+    /// the overruns and bus errors they report, and disable the offending interrupt source when it
+    /// fires more often than the configured limit between two calls to [`Can::tick`]. It is
+    /// enabled again by `tick` when its backoff period has elapsed.
+    ///
+    /// ```no_run
+    /// # use bxcan::{Instance, RegisterBlock};
+    /// # struct CAN1;
+    /// # unsafe impl Instance for CAN1 {
+    /// #     const REGISTERS: *mut RegisterBlock = 0x4000_6400 as *mut _;
+    /// # }
+    /// use bxcan::{Can, StormProtection};
+    ///
+    /// fn setup(can: &mut Can<CAN1>) {
+    ///     // Tolerate 50 events per tick, and back off for 10 ticks.
+    ///     can.set_storm_protection(Some(StormProtection::new(50, 10)));
+    /// }
+    ///
+    /// // Called from a timer interrupt handler every 10 ms.
+    /// fn timer(can: &mut Can<CAN1>) {
+    ///     can.tick();
+    /// }
+    /// ```
+    ///
+    /// Only sources that are enabled are counted. Sources that are currently suspended are enabled
+    /// again when the configuration is replaced.
+    pub fn set_storm_protection(&mut self, protection: Option<StormProtection>) {
+        let suspended = self.storm.set_protection(protection);
+        self.resume_interrupts(suspended);
+    }
+
+    /// Advances the time base of the interrupt storm protection by one tick.
+    ///
+    /// This resets the event counts, and enables the interrupt sources whose backoff period has
+    /// elapsed again. See [`Can::set_storm_protection`] for details.
+    pub fn tick(&mut self) {
+        let expired = self.storm.tick();
+        self.resume_interrupts(expired);
+    }
+
+    /// Returns the interrupt sources that are currently disabled by the interrupt storm
+    /// protection.
+    #[inline]
+    pub fn suspended_interrupts(&self) -> Interrupts {
+        self.storm.suspended()
+    }
+
+    /// Counts an event of `source` for the interrupt storm protection, and disables `source` if it
+    /// fires too often.
+    fn count_storm_event(&mut self, source: Interrupt) {
+        let ier = Interrupts::from_bits_truncate(self.registers().ier.read().bits());
+        if ier.contains(source.into()) && self.storm.record(source) {
+            self.disable_interrupt(source);
+        }
+    }
+
+    /// Enables interrupt sources suspended by the interrupt storm protection again.
+    fn resume_interrupts(&mut self, mut interrupts: Interrupts) {
+        if self.lec_masked {
+            // Enabled again by `take_error_events`.
+            interrupts.remove(Interrupts::LAST_ERROR_CODE);
+        }
+        self.enable_interrupts(interrupts);
+    }
+
+    /// Initiates recovery from the bus-off state.
+    ///
+    /// If automatic bus-off management is disabled (see
+    /// [`CanConfig::set_automatic_bus_off_management`]), the peripheral stays in bus-off state
+    /// until this method is called. This requests initialization mode and leaves it again, which
+    /// starts the recovery sequence. The peripheral becomes [`ErrorState::ErrorActive`] once it
+    /// has observed 128 occurrences of 11 consecutive recessive bits on the bus, which happens in
+    /// the background after this method returns.
+    ///
+    /// Does nothing if the peripheral is not in bus-off state.
+    pub fn recover_from_bus_off(&mut self) {
+        if self.error_state() != ErrorState::BusOff {
+            return;
+        }
+
+        self.enter_init_mode();
+
+        let can = self.registers();
+        can.mcr.modify(|_, w| w.inrq().clear_bit());
+        while can.msr.read().inak().bit_is_set() {}
+    }
+
+    /// Clears the "Request Completed" (RQCP) flag of a transmit mailbox.
+    ///
+    /// Returns the [`Mailbox`] whose flag was cleared. If no mailbox has the flag set, returns
+    /// `None`.
+    ///
+    /// Once this function returns `None`, a pending [`Interrupt::TransmitMailboxEmpty`] is
+    /// considered acknowledged.
+    pub fn clear_request_completed_flag(&mut self) -> Option<Mailbox> {
+        self.complete_request().map(|(mailbox, _, _)| mailbox)
+    }
+
+    /// Clears the RQCP flag of the next mailbox that has it set, and returns its previous status
+    /// and the cookie of its frame.
+    fn complete_request(&mut self) -> Option<(Mailbox, MailboxStatus, u32)> {
+        let tsr = self.registers().tsr.read();
+        let mailbox = if tsr.rqcp0().bit_is_set() {
+            Mailbox::Mailbox0
+        } else if tsr.rqcp1().bit_is_set() {
+            Mailbox::Mailbox1
+        } else if tsr.rqcp2().bit_is_set() {
+            Mailbox::Mailbox2
+        } else {
+            return None;
+        };
+
+        let status = self.mailbox_status(mailbox);
+        #[cfg(feature = "stats")]
+        {
+            if status.is_transmitted() {
+                self.stats.count_transmitted();
+            } else if status.is_arbitration_lost() {
+                self.stats.count_arbitration_loss();
+            }
+        }
+
+        let can = self.registers();
+        match mailbox {
+            Mailbox::Mailbox0 => can.tsr.modify(|_, w| w.rqcp0().set_bit()),
+            Mailbox::Mailbox1 => can.tsr.modify(|_, w| w.rqcp1().set_bit()),
+            Mailbox::Mailbox2 => can.tsr.modify(|_, w| w.rqcp2().set_bit()),
+        }
+        let cookie = mem::take(&mut self.cookies[mailbox as usize]);
+        Some((mailbox, status, cookie))
+    }
+
+    /// Clears a pending TX interrupt ([`Interrupt::TransmitMailboxEmpty`]).
+    ///
+    /// This does not return the mailboxes that have finished tranmission. If you need that
+    /// information, call [`Can::clear_request_completed_flag`] instead.
+    pub fn clear_tx_interrupt(&mut self) {
+        while self.clear_request_completed_flag().is_some() {}
+    }
+
+    /// Handles a **TX** interrupt and returns the next completed transmission request.
+    ///
+    /// This clears the "Request Completed" flag of one mailbox, like
+    /// [`Can::clear_request_completed_flag`], and returns how the request was completed. Once this
+    /// returns `None`, the pending [`Interrupt::TransmitMailboxEmpty`] is acknowledged, so it
+    /// should be called in a loop from the interrupt handler.
+    pub fn on_tx_interrupt(&mut self) -> Option<TxEvent> {
+        self.next_tx_event().map(|(event, _)| event)
+    }
+
+    /// Completes the next transmission request, and returns how it was completed and its cookie.
+    fn next_tx_event(&mut self) -> Option<(TxEvent, u32)> {
+        let (mailbox, status, cookie) = self.complete_request()?;
+        let event = if status.is_transmitted() {
+            TxEvent::Transmitted(mailbox)
+        } else if status.is_arbitration_lost() {
+            TxEvent::ArbitrationLost(mailbox)
+        } else if status.is_transmit_error() {
+            TxEvent::Error(mailbox)
+        } else {
+            TxEvent::Aborted(mailbox)
+        };
+        Some((event, cookie))
+    }
+
+    /// Handles a **TX** interrupt and returns the next completed transmission request, along with
+    /// the timestamp and cookie of the frame.
+    ///
+    /// This behaves like [`Can::on_tx_interrupt`]. The returned [`TxCompletion`] can be passed to
+    /// the application through a [`TxCompletionQueue`].
+    pub fn on_tx_interrupt_timestamped(&mut self) -> Option<TxCompletion> {
+        let (event, cookie) = self.next_tx_event()?;
+        let timestamp = self.registers().tx[event.mailbox() as usize]
+            .tdtr
+            .read()
+            .time()
+            .bits();
+        Some(TxCompletion::new(event, timestamp, cookie))
+    }
+
+    /// Handles an **RX FIFO** interrupt and returns the next pending event of `fifo`.
+    ///
+    /// This clears the overrun and full flags of the FIFO, and returns `None` once there are no
+    /// more events. [`RxEvent::FramePending`] is returned as long as there are frames in the FIFO,
+    /// so they have to be received while handling it, before calling this method again:
+    ///
+    /// ```no_run
+    /// # use bxcan::{Instance, RegisterBlock};
+    /// # struct CAN1;
+    /// # unsafe impl Instance for CAN1 {
+    /// #     const REGISTERS: *mut RegisterBlock = 0x4000_6400 as *mut _;
+    /// # }
+    /// use bxcan::{Can, Fifo, RxEvent};
+    ///
+    /// fn can1_rx0(can: &mut Can<CAN1>) {
+    ///     while let Some(event) = can.on_rx_interrupt(Fifo::Fifo0) {
+    ///         match event {
+    ///             RxEvent::FramePending => {
+    ///                 let frame = can.receive();
+    ///                 // Process `frame`.
+    ///             }
+    ///             RxEvent::Overrun(_) | RxEvent::Full => {}
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// With the `stats` feature enabled, overruns are counted in [`Stats::rx_overruns`].
+    pub fn on_rx_interrupt(&mut self, fifo: Fifo) -> Option<RxEvent> {
+        let can = self.registers();
+        let rfr = &can.rfr[fifo as usize];
+        let rfr_read = rfr.read();
+        if rfr_read.fovr().bit_is_set() {
+            rfr.write(|w| w.fovr().set_bit());
+            let mode = overrun_mode(can);
+            self.count_storm_event(match fifo {
+                Fifo::Fifo0 => Interrupt::Fifo0Overrun,
+                Fifo::Fifo1 => Interrupt::Fifo1Overrun,
+            });
+            let error = self.count_overrun(OverrunError::new(fifo, mode));
+            return Some(RxEvent::Overrun(error));
+        }
+        if rfr_read.full().bit_is_set() {
+            rfr.write(|w| w.full().set_bit());
+            return Some(RxEvent::Full);
+        }
+        if rfr_read.fmp().bits() != 0 {
+            return Some(RxEvent::FramePending);
+        }
+        None
+    }
+
+    /// Decodes the events of all interrupt sources in one pass.
+    ///
+    /// On some parts, eg. the STM32F0 series, the **TX**, **RX FIFO 0**, **RX FIFO 1** and **SCE**
+    /// interrupts share a single interrupt vector. This calls [`Can::on_tx_interrupt`],
+    /// [`Can::on_rx_interrupt`] for both FIFOs and [`Can::on_sce_interrupt`] until each returns
+    /// `None`, so a single interrupt handler can be written portably:
+    ///
+    /// ```no_run
+    /// # use bxcan::{Instance, RegisterBlock};
+    /// # struct CAN1;
+    /// # unsafe impl Instance for CAN1 {
+    /// #     const REGISTERS: *mut RegisterBlock = 0x4000_6400 as *mut _;
+    /// # }
+    /// use bxcan::{Can, Event, RxEvent};
+    ///
+    /// fn cec_can(can: &mut Can<CAN1>) {
+    ///     for event in can.poll_all_events() {
+    ///         match event {
+    ///             Event::Rx(_, RxEvent::FramePending) => {
+    ///                 while let Ok(frame) = can.receive() {
+    ///                     // Process `frame`.
+    ///                 }
+    ///             }
+    ///             Event::Tx(event) => { /* Transmission in `event.mailbox()` completed. */ }
+    ///             Event::Rx(..) | Event::Sce(_) => {}
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// All flags are cleared in the process, except for pending frames: like with
+    /// `on_rx_interrupt`, the **RX FIFO** interrupt stays pending until the frames reported by
+    /// [`RxEvent::FramePending`] have been received.
+    pub fn poll_all_events(&mut self) -> Events {
+        let mut events = Events::new();
+        while let Some(event) = self.on_tx_interrupt() {
+            events.push(Event::Tx(event));
+        }
+        for fifo in [Fifo::Fifo0, Fifo::Fifo1] {
+            while let Some(event) = self.on_rx_interrupt(fifo) {
+                events.push(Event::Rx(fifo, event));
+                if event == RxEvent::FramePending {
+                    // Reported until the frames are received.
+                    break;
+                }
+            }
+        }
+        while !events.is_full() {
+            match self.on_sce_interrupt() {
+                Some(event) => events.push(Event::Sce(event)),
+                None => break,
+            }
+        }
+        events
+    }
+
+    /// Puts a CAN frame in a free transmit mailbox for transmission on the bus.
+    ///
+    /// Frames are transmitted to the bus based on their priority (identifier). Transmit order is
+    /// preserved for frames with identical identifiers.
+    ///
+    /// If all transmit mailboxes are full, a higher priority frame can replace a lower-priority
+    /// frame, which is returned in the [`TransmitStatus`].
+    ///
+    /// With [`TxOrdering::Fifo`], frames are transmitted in the order they were enqueued instead,
+    /// and `WouldBlock` is returned as long as all mailboxes are full. No frames are dequeued.
+    pub fn transmit(&mut self, frame: &Frame) -> nb::Result<TransmitStatus, Infallible> {
+        self.transmit_with_cookie(frame, 0)
+    }
+
+    /// Puts a CAN frame in a free transmit mailbox, and associates `cookie` with it.
+    ///
+    /// The cookie is handed back in the [`TxCompletion`] returned by
+    /// [`Can::on_tx_interrupt_timestamped`] once the transmission request has completed, so higher
+    /// layer protocols can correlate completions with their own state. If the frame is dequeued
+    /// again to make space for a higher priority frame, its cookie is returned by
+    /// [`TransmitStatus::dequeued_cookie`] instead.
+    ///
+    /// Frames put in a mailbox by other methods have the cookie 0. Otherwise, this behaves like
+    /// [`Can::transmit`].
+    pub fn transmit_with_cookie(
+        &mut self,
+        frame: &Frame,
+        cookie: u32,
+    ) -> nb::Result<TransmitStatus, Infallible> {
+        self.transmit_with_cookie_and_policy(frame, cookie, ReplacementPolicy::DequeueLower)
+    }
+
+    fn transmit_with_cookie_and_policy(
+        &mut self,
+        frame: &Frame,
+        cookie: u32,
+        policy: ReplacementPolicy,
+    ) -> nb::Result<TransmitStatus, Infallible> {
+        // Safety: We have a `&mut self` and have unique access to the peripheral.
+        let status = unsafe { Tx::<I>::conjure().transmit_with_policy(frame, policy) }?;
+        Ok(self.track_cookie(status, cookie))
+    }
+
+    /// Stores `cookie` for the mailbox the frame was put in, and hands back the cookie of the
+    /// frame it dequeued.
+    ///
+    /// This has to be called by every method that puts a frame in a mailbox, so that the cookie
+    /// of its previous frame is not reported for the new one.
+    fn track_cookie(&mut self, mut status: TransmitStatus, cookie: u32) -> TransmitStatus {
+        let slot = &mut self.cookies[status.mailbox as usize];
+        if status.dequeued_frame.is_some() {
+            status.dequeued_cookie = *slot;
+        }
+        *slot = cookie;
+        status
+    }
+
+    /// Puts as many frames from `frames` as possible in the free transmit mailboxes, and returns
+    /// the number of frames that were accepted.
+    ///
+    /// See [`Tx::transmit_all`] for details.
+    pub fn transmit_all<'a>(&mut self, frames: impl Iterator<Item = &'a Frame>) -> usize {
+        // Safety: We have a `&mut self` and have unique access to the peripheral.
+        let mut tx = unsafe { Tx::<I>::conjure() };
+        let mut count = 0;
+        for frame in frames {
+            match tx.transmit_to_free_mailbox(frame) {
+                Some(mailbox) => self.cookies[mailbox as usize] = 0,
+                None => break,
+            }
+            count += 1;
+        }
+        count
+    }
+
+    /// Puts a CAN frame in a transmit mailbox, using `policy` when all mailboxes are occupied.
+    ///
+    /// See [`Tx::transmit_with_policy`] for details.
+    pub fn transmit_with_policy(
+        &mut self,
+        frame: &Frame,
+        policy: ReplacementPolicy,
+    ) -> nb::Result<TransmitStatus, Infallible> {
+        self.transmit_with_cookie_and_policy(frame, 0, policy)
+    }
+
+    /// Puts a CAN frame in a free transmit mailbox, without using `nb`.
+    ///
+    /// See [`Tx::try_transmit`] for details.
+    pub fn try_transmit(&mut self, frame: &Frame) -> Result<TransmitStatus, TryError> {
+        self.transmit(frame).map_err(|e| match e {
+            nb::Error::WouldBlock => TryError::Busy,
+            nb::Error::Other(infallible) => match infallible {},
+        })
+    }
+
+    /// Puts a frame, given as its precomputed mailbox register values, in a transmit mailbox.
+    ///
+    /// See [`Tx::transmit_raw`] for details.
+    pub fn transmit_raw(
+        &mut self,
+        registers: &MailboxRegisters,
+    ) -> nb::Result<TransmitStatus, Infallible> {
+        // Safety: We have a `&mut self` and have unique access to the peripheral.
+        let status = unsafe { Tx::<I>::conjure().transmit_raw(registers) }?;
+        Ok(self.track_cookie(status, 0))
+    }
+
+    /// Puts a CAN frame in a transmit mailbox, waiting until one becomes available or `timed_out`
+    /// returns `true`.
+    ///
+    /// See [`Tx::transmit_blocking`] for details.
+    pub fn transmit_blocking(
+        &mut self,
+        frame: &Frame,
+        timed_out: impl FnMut() -> bool,
+    ) -> Option<TransmitStatus> {
+        match block_with_timeout(|| self.transmit(frame), timed_out) {
+            Ok(status) => status,
+            Err(infallible) => match infallible {},
+        }
+    }
+
+    /// Transmits a frame with a single attempt, regardless of the automatic retransmission
+    /// setting.
+    ///
+    /// See [`Tx::transmit_single_shot`] for details.
+    pub fn transmit_single_shot(
+        &mut self,
+        frame: &Frame,
+        timed_out: impl FnMut() -> bool,
+    ) -> nb::Result<Option<MailboxStatus>, Infallible> {
+        // Safety: We have a `&mut self` and have unique access to the peripheral.
+        let mut tx = unsafe { Tx::<I>::conjure() };
+        tx.transmit_single_shot_with(frame, timed_out, |mailbox| {
+            self.cookies[mailbox as usize] = 0;
+        })
+    }
+
+    /// Returns `true` if no frame is pending for transmission.
+    pub fn is_transmitter_idle(&self) -> bool {
+        // Safety: Read-only operation.
+        unsafe { Tx::<I>::conjure().is_idle() }
+    }
+
+    /// Waits until all pending frames have been transmitted.
+    ///
+    /// See [`Tx::flush`] for details.
+    pub fn flush(&mut self) {
+        // Safety: We have a `&mut self` and have unique access to the peripheral.
+        unsafe { Tx::<I>::conjure().flush() }
+    }
+
+    /// Waits until all pending frames have been transmitted, or aborts them when `timed_out`
+    /// returns `true`.
+    ///
+    /// See [`Tx::flush_with_timeout`] for details.
+    pub fn flush_with_timeout(&mut self, timed_out: impl FnMut() -> bool) -> bool {
+        // Safety: We have a `&mut self` and have unique access to the peripheral.
+        unsafe { Tx::<I>::conjure().flush_with_timeout(timed_out) }
+    }
+
+    /// Attempts to abort the sending of a frame that is pending in a mailbox.
+    ///
+    /// If there is no frame in the provided mailbox, or its transmission succeeds before it can be
+    /// aborted, this function has no effect and returns `false`.
     ///
     /// If there is a frame in the provided mailbox, and it is canceled successfully, this function
     /// returns `true`.
     pub fn abort(&mut self, mailbox: Mailbox) -> bool {
         // Safety: We have a `&mut self` and have unique access to the peripheral.
-        unsafe { Tx::<I>::conjure().abort(mailbox) }
+        let aborted = unsafe { Tx::<I>::conjure().abort(mailbox) };
+
+        #[cfg(feature = "stats")]
+        if aborted {
+            self.stats.count_tx_abort();
+        }
+
+        aborted
+    }
+
+    /// Returns the status of a transmit mailbox.
+    ///
+    /// See [`Tx::mailbox_status`] for details.
+    pub fn mailbox_status(&self, mailbox: Mailbox) -> MailboxStatus {
+        // Safety: Only reads a status register.
+        unsafe { Tx::<I>::conjure().mailbox_status(mailbox) }
+    }
+
+    /// Clears the status flags of a transmit mailbox.
+    ///
+    /// The completion of the mailbox's last transmission request is not reported afterwards, so
+    /// the cookie of its frame is discarded.
+    pub fn clear_mailbox_status(&mut self, mailbox: Mailbox) {
+        // Safety: We have a `&mut self` and have unique access to the peripheral.
+        unsafe { Tx::<I>::conjure().clear_mailbox_status(mailbox) }
+        self.cookies[mailbox as usize] = 0;
     }
 
     /// Returns a received frame if available.
     ///
+    /// FIFO 0 is checked before FIFO 1.
+    ///
     /// Returns `Err` when a frame was lost due to buffer overrun.
-    pub fn receive(&mut self) -> nb::Result<Frame, ()> {
-        // Safety: We have a `&mut self` and have unique access to the peripheral.
-        unsafe { Rx::<I>::conjure().receive() }
+    pub fn receive(&mut self) -> nb::Result<Frame, OverrunError> {
+        self.receive_timestamped().map(|(frame, _)| frame)
+    }
+
+    /// Returns a received frame if available, without using `nb`.
+    ///
+    /// Returns [`TryError::Empty`] if no frame is pending in either FIFO. Otherwise, this behaves
+    /// like [`Can::receive`].
+    pub fn try_receive(&mut self) -> Result<Frame, TryError> {
+        self.receive().map_err(TryError::from)
+    }
+
+    /// Returns a received frame like [`Can::receive`], answering the remote frames registered in
+    /// `replies`.
+    ///
+    /// Remote frames with an entry in `replies` are answered right away and not returned, so
+    /// calling this instead of [`Can::receive`] in the **RX FIFO** interrupt handlers answers
+    /// remote frames with minimal latency. The responses are enqueued with
+    /// [`ReplacementPolicy::RejectWhenFull`], so no pending frame is aborted in favor of a
+    /// response. If all mailboxes are occupied, the remote frame is returned like any other frame
+    /// and can be answered later with [`AutoReply::handle`].
+    ///
+    /// The [`AutoReply`] table is not stored in the [`Can`] instance, so that it can be shared with
+    /// the code updating its entries.
+    pub fn receive_with_auto_reply<const N: usize>(
+        &mut self,
+        replies: &AutoReply<N>,
+    ) -> nb::Result<Frame, OverrunError> {
+        loop {
+            let frame = self.receive()?;
+            if frame.is_remote_frame() {
+                if let Some(response) = replies.get(frame.id()) {
+                    let policy = ReplacementPolicy::RejectWhenFull;
+                    if self.transmit_with_policy(response, policy).is_ok() {
+                        continue;
+                    }
+                }
+            }
+            return Ok(frame);
+        }
+    }
+
+    /// Waits for a frame to be received in either FIFO, or until `timed_out` returns `true`.
+    ///
+    /// See [`Rx0::receive_blocking`] for details.
+    pub fn receive_blocking(
+        &mut self,
+        timed_out: impl FnMut() -> bool,
+    ) -> Result<Option<Frame>, OverrunError> {
+        block_with_timeout(|| self.receive(), timed_out)
+    }
+
+    /// Passes all frames pending in both FIFOs to `f`, and returns how many frames were received.
+    ///
+    /// FIFO 0 is drained before FIFO 1. If a FIFO has overrun, the other one is still drained, and
+    /// the error of the first FIFO that has overrun is returned. See [`Rx0::drain`] for details.
+    pub fn drain(&mut self, mut f: impl FnMut(Frame)) -> Result<usize, OverrunError> {
+        let can = self.registers();
+        let fifo0 = drain_fifo(can, Fifo::Fifo0, &mut f);
+        let fifo1 = drain_fifo(can, Fifo::Fifo1, &mut f);
+        let fifo0 = fifo0.map_err(|e| self.count_overrun(e));
+        let fifo1 = fifo1.map_err(|e| self.count_overrun(e));
+        fifo0.and_then(|count| Ok(count + fifo1?))
+    }
+
+    /// Returns a received frame and its timestamp if available.
+    ///
+    /// FIFO 0 is checked before FIFO 1. See [`Rx0::receive_timestamped`] for details about the
+    /// timestamp.
+    pub fn receive_timestamped(&mut self) -> nb::Result<(Frame, u16), OverrunError> {
+        self.receive_with_meta()
+            .map(|(frame, meta)| (frame, meta.timestamp()))
+    }
+
+    /// Returns a received frame and its [`RxMeta`] data if available.
+    ///
+    /// FIFO 0 is checked before FIFO 1.
+    pub fn receive_with_meta(&mut self) -> nb::Result<(Frame, RxMeta), OverrunError> {
+        let result = match receive_fifo(self.registers(), Fifo::Fifo0) {
+            Err(nb::Error::WouldBlock) => receive_fifo(self.registers(), Fifo::Fifo1),
+            result => result,
+        };
+
+        self.count_receive_result(result)
+    }
+
+    /// Receives a frame, copying its data directly into `data` instead of returning a [`Frame`].
+    ///
+    /// FIFO 0 is checked before FIFO 1. See [`Rx0::receive_into`] for details.
+    pub fn receive_into(&mut self, data: &mut [u8; 8]) -> nb::Result<RxHeader, OverrunError> {
+        let result = match receive_fifo_into(self.registers(), Fifo::Fifo0, data) {
+            Err(nb::Error::WouldBlock) => receive_fifo_into(self.registers(), Fifo::Fifo1, data),
+            result => result,
+        };
+
+        self.count_receive_result(result)
+    }
+
+    fn count_receive_result<T>(
+        &mut self,
+        result: nb::Result<T, OverrunError>,
+    ) -> nb::Result<T, OverrunError> {
+        #[cfg(feature = "stats")]
+        if result.is_ok() {
+            self.stats.count_received();
+        }
+        result.map_err(|e| e.map(|e| self.count_overrun(e)))
+    }
+
+    /// Counts an overrun detected through this instance, and stores the count in the error.
+    fn count_overrun(&mut self, mut error: OverrunError) -> OverrunError {
+        #[cfg(feature = "stats")]
+        self.stats.count_rx_overrun();
+        let count = &mut self.overruns[error.fifo as usize];
+        *count = count.wrapping_add(1);
+        error.count = Some(*count);
+        error
+    }
+
+    /// Returns how many overruns of `fifo` have been detected through this instance.
+    ///
+    /// Overruns are detected by [`Can::receive`] and related methods, [`Can::drain`] and
+    /// [`Can::on_rx_interrupt`], and are not counted when detected by the halves returned by
+    /// [`Can::split`]. The counter wraps around at `u32::MAX`.
+    ///
+    /// Since the peripheral only flags *that* a FIFO overran, this is a lower bound of the number
+    /// of lost frames.
+    pub fn overrun_count(&self, fifo: Fifo) -> u32 {
+        self.overruns[fifo as usize]
+    }
+
+    /// Returns the statistics counters of this instance.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// Resets all statistics counters to 0.
+    #[cfg(feature = "stats")]
+    pub fn reset_stats(&mut self) {
+        self.stats = Stats::default();
+    }
+
+    /// Returns the current status of a receive FIFO.
+    pub fn fifo_status(&self, fifo: Fifo) -> FifoStatus {
+        fifo_status(self.registers(), fifo)
+    }
+
+    /// Returns the number of frames pending in a receive FIFO (0 to 3).
+    ///
+    /// See [`Rx0::pending_count`] for details.
+    pub fn pending_count(&self, fifo: Fifo) -> u8 {
+        pending_count(self.registers(), fifo)
+    }
+
+    /// Returns a copy of the oldest frame in a receive FIFO, without removing it.
+    ///
+    /// See [`Rx0::peek`] for details.
+    pub fn peek(&self, fifo: Fifo) -> Option<(Frame, RxMeta)> {
+        peek_fifo(self.registers(), fifo)
     }
 
     /// Splits this `Can` instance into transmitting and receiving halves, by reference.
-    pub fn split_by_ref(&mut self) -> (&mut Tx<I>, &mut Rx<I>) {
+    pub fn split_by_ref(&mut self) -> (&mut Tx<I>, &mut Rx0<I>, &mut Rx1<I>) {
         // Safety: We take `&mut self` and the return value lifetimes are tied to `self`'s lifetime.
         let tx = unsafe { Tx::conjure_by_ref() };
-        let rx = unsafe { Rx::conjure_by_ref() };
-        (tx, rx)
+        let rx0 = unsafe { Rx0::conjure_by_ref() };
+        let rx1 = unsafe { Rx1::conjure_by_ref() };
+        (tx, rx0, rx1)
     }
 
     /// Consumes this `Can` instance and splits it into transmitting and receiving halves.
-    pub fn split(self) -> (Tx<I>, Rx<I>) {
-        unsafe { (Tx::conjure(), Rx::conjure()) }
+    ///
+    /// The receiving half is split further into [`Rx0`] and [`Rx1`], one for each receive FIFO, so
+    /// that they can be used from different interrupt handlers.
+    pub fn split(self) -> (Tx<I>, Rx0<I>, Rx1<I>) {
+        unsafe { (Tx::conjure(), Rx0::conjure(), Rx1::conjure()) }
+    }
+
+    /// Consumes this `Can` instance and splits it into one part per interrupt handler.
+    ///
+    /// In addition to the halves returned by [`Can::split`], this returns the [`Sce`] part, which
+    /// handles the **SCE** (status change and error) interrupt. All parts are `Send`, so each of
+    /// them can be moved into the task handling the corresponding interrupt (eg. in an RTIC
+    /// application), without sharing the `Can` instance behind a lock.
+    pub fn split_by_interrupt(self) -> (Tx<I>, Rx0<I>, Rx1<I>, Sce<I>) {
+        unsafe {
+            (
+                Tx::conjure(),
+                Rx0::conjure(),
+                Rx1::conjure(),
+                Sce::conjure(),
+            )
+        }
     }
 }
 
 impl<I: FilterOwner> Can<I> {
+    /// Checks the configuration for the combinations rejected by [`CanBuilder::try_enable`].
+    fn validate_config(&self) -> Result<(), ConfigError> {
+        let timing = bit_timing::BitTiming::from_btr(self.registers().btr.read().bits());
+        if timing.sjw() > timing.seg1() || timing.sjw() > timing.seg2() {
+            return Err(ConfigError::InvalidBitTiming);
+        }
+        if !filter::has_enabled_bank::<I>(self.registers()) {
+            return Err(ConfigError::NoFilters);
+        }
+        Ok(())
+    }
+
     /// Accesses the filter banks owned by this CAN peripheral.
     ///
     /// To modify filters of a slave peripheral, `modify_filters` has to be called on the master
     /// peripheral instead.
+    ///
+    /// All [`FilterHandle`]s returned by [`Can::add_filter`] become stale.
     pub fn modify_filters(&mut self) -> MasterFilters<'_, I> {
+        self.filter_generation = self.filter_generation.wrapping_add(1);
         unsafe { MasterFilters::new() }
     }
+
+    /// Enables a filter bank with `config`, without disturbing the reception of frames through the
+    /// other banks.
+    ///
+    /// A disabled bank owned by this instance is selected and configured, and a handle to it is
+    /// returned. Unlike [`Can::modify_filters`], this does not enter filter initialization mode,
+    /// which suspends reception, if a free bank already has the mode, scale and FIFO assignment
+    /// required by `config` and `fifo` (these can only be changed in initialization mode).
+    ///
+    /// Returns [`FiltersFull`] if all banks are enabled.
+    pub fn add_filter(
+        &mut self,
+        fifo: Fifo,
+        config: impl Into<BankConfig>,
+    ) -> Result<FilterHandle, FiltersFull> {
+        // Safety: We have a `&mut self`, so no `MasterFilters` exists.
+        unsafe { filter::add_bank::<I>(fifo, config.into(), self.filter_generation) }
+    }
+
+    /// Disables a filter bank enabled by [`Can::add_filter`].
+    ///
+    /// The bank is disabled without entering filter initialization mode, so the reception of
+    /// frames through other banks is not disturbed.
+    ///
+    /// Returns [`StaleFilterHandle`] and leaves the filters unchanged if they were modified
+    /// through [`Can::modify_filters`] since `handle` was created.
+    pub fn remove_filter(&mut self, handle: FilterHandle) -> Result<(), StaleFilterHandle> {
+        // Safety: We have a `&mut self`, so no `MasterFilters` exists.
+        unsafe { filter::remove_bank::<I>(handle, self.filter_generation) }
+    }
+
+    /// Puts the peripheral in sleep mode, to be woken up by frames accepted by `config`.
+    ///
+    /// The bxCAN peripheral does not apply its filters while sleeping: any activity on the bus
+    /// wakes it up. The frame that causes the wakeup is usually lost as well, since the peripheral
+    /// has to resynchronize to the bus before it can receive. So a wakeup filter can only be
+    /// approximated in software, which is what this method and [`Can::confirm_wakeup`] do:
+    ///
+    /// 1. All filter banks of this instance are replaced by a single bank with `config`, which
+    ///    stores accepted frames in FIFO 0. The filters of a slave instance are not modified.
+    /// 2. Automatic wakeup is enabled (see [`Can::set_automatic_wakeup`]), and the peripheral is
+    ///    put in sleep mode.
+    /// 3. Once the peripheral has woken up, which can be signalled by [`Interrupt::Wakeup`], the
+    ///    application calls [`Can::confirm_wakeup`]. It waits for a frame accepted by `config`,
+    ///    and puts the peripheral back to sleep if none arrives in time.
+    ///
+    /// This only works if the frame that should wake up the node is repeated, as most network
+    /// management protocols do anyway. The previous filter configuration is not restored when
+    /// waking up.
+    pub fn sleep_with_wakeup_filter(&mut self, config: impl Into<BankConfig>) {
+        self.modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, config);
+        self.set_automatic_wakeup(true);
+        self.sleep();
+    }
+
+    /// Waits for a frame that confirms a wakeup from [`Can::sleep_with_wakeup_filter`].
+    ///
+    /// Returns the first frame accepted by the wakeup filter. If `timed_out` returns `true` before
+    /// such a frame is received, the peripheral is put back to sleep and `None` is returned. See
+    /// [`Tx::transmit_blocking`] for how to implement a timeout.
+    pub fn confirm_wakeup(&mut self, mut timed_out: impl FnMut() -> bool) -> Option<Frame> {
+        loop {
+            match block_with_timeout(|| self.receive(), &mut timed_out) {
+                Ok(Some(frame)) => return Some(frame),
+                Ok(None) => {
+                    self.sleep();
+                    return None;
+                }
+                // Frames were lost, but matching frames have arrived, so receive the next one.
+                Err(_) => {}
+            }
+        }
+    }
 }
 
 /// Interface to the CAN transmitter part.
@@ -695,16 +2282,36 @@ pub struct Tx<I> {
     _can: PhantomData<I>,
 }
 
+#[inline]
+const fn request_completed_mask(idx: usize) -> u32 {
+    0x01 << (8 * idx)
+}
+
 #[inline]
 const fn ok_mask(idx: usize) -> u32 {
     0x02 << (8 * idx)
 }
 
+#[inline]
+const fn arbitration_lost_mask(idx: usize) -> u32 {
+    0x04 << (8 * idx)
+}
+
+#[inline]
+const fn error_mask(idx: usize) -> u32 {
+    0x08 << (8 * idx)
+}
+
 #[inline]
 const fn abort_mask(idx: usize) -> u32 {
     0x80 << (8 * idx)
 }
 
+#[inline]
+const fn empty_mask(idx: usize) -> u32 {
+    1 << (26 + idx)
+}
+
 impl<I> Tx<I>
 where
     I: Instance,
@@ -725,7 +2332,7 @@ where
     }
 
     fn registers(&self) -> &RegisterBlock {
-        unsafe { &*I::REGISTERS }
+        unsafe { &*I::register_block() }
     }
 
     /// Puts a CAN frame in a transmit mailbox for transmission on the bus.
@@ -735,8 +2342,56 @@ where
     ///
     /// If all transmit mailboxes are full, a higher priority frame can replace a lower-priority
     /// frame, which is returned in the [`TransmitStatus`].
+    ///
+    /// With [`TxOrdering::Fifo`], frames are transmitted in the order they were enqueued instead,
+    /// and `WouldBlock` is returned as long as all mailboxes are full. No frames are dequeued.
     pub fn transmit(&mut self, frame: &Frame) -> nb::Result<TransmitStatus, Infallible> {
+        self.transmit_raw(&frame.registers())
+    }
+
+    /// Puts a CAN frame in a free transmit mailbox, without using `nb`.
+    ///
+    /// Returns [`TryError::Busy`] if all mailboxes are occupied by frames of higher priority.
+    /// Otherwise, this behaves like [`Tx::transmit`].
+    pub fn try_transmit(&mut self, frame: &Frame) -> Result<TransmitStatus, TryError> {
+        self.transmit(frame).map_err(|e| match e {
+            nb::Error::WouldBlock => TryError::Busy,
+            nb::Error::Other(infallible) => match infallible {},
+        })
+    }
+
+    /// Puts a frame, given as its precomputed mailbox register values, in a transmit mailbox.
+    ///
+    /// This behaves like [`Tx::transmit`], but skips encoding the frame, which can help in hot
+    /// paths that send the same frame repeatedly.
+    pub fn transmit_raw(
+        &mut self,
+        registers: &MailboxRegisters,
+    ) -> nb::Result<TransmitStatus, Infallible> {
+        self.transmit_raw_with_policy(registers, ReplacementPolicy::DequeueLower)
+    }
+
+    /// Puts a CAN frame in a transmit mailbox, using `policy` when all mailboxes are occupied.
+    ///
+    /// With [`ReplacementPolicy::RejectWhenFull`], `WouldBlock` is returned as long as all
+    /// mailboxes are occupied, so a frame that was accepted once is never aborted to make space
+    /// for another one. This is required by applications that must not lose an already enqueued
+    /// frame, eg. a safety heartbeat. Otherwise, this behaves like [`Tx::transmit`].
+    pub fn transmit_with_policy(
+        &mut self,
+        frame: &Frame,
+        policy: ReplacementPolicy,
+    ) -> nb::Result<TransmitStatus, Infallible> {
+        self.transmit_raw_with_policy(&frame.registers(), policy)
+    }
+
+    fn transmit_raw_with_policy(
+        &mut self,
+        registers: &MailboxRegisters,
+        policy: ReplacementPolicy,
+    ) -> nb::Result<TransmitStatus, Infallible> {
         let can = self.registers();
+        let id = IdReg(registers.tir());
 
         // Get the index of the next free mailbox or the one with the lowest priority.
         let tsr = can.tsr.read();
@@ -744,19 +2399,28 @@ where
 
         let frame_is_pending =
             tsr.tme0().bit_is_clear() || tsr.tme1().bit_is_clear() || tsr.tme2().bit_is_clear();
-        let pending_frame = if frame_is_pending {
+        let pending_frame = if can.mcr.read().txfp().bit_is_set() {
+            // In FIFO mode, the peripheral transmits frames in request order, so there is no
+            // priority to check. Wait for a free mailbox instead of dequeuing a frame.
+            if tsr.tme0().bit_is_clear() && tsr.tme1().bit_is_clear() && tsr.tme2().bit_is_clear() {
+                return Err(nb::Error::WouldBlock);
+            }
+            None
+        } else if frame_is_pending {
             // High priority frames are transmitted first by the mailbox system.
             // Frames with identical identifier shall be transmitted in FIFO order.
             // The controller schedules pending frames of same priority based on the
             // mailbox index instead. As a workaround check all pending mailboxes
             // and only accept higher priority frames.
-            self.check_priority(0, frame.id)?;
-            self.check_priority(1, frame.id)?;
-            self.check_priority(2, frame.id)?;
+            self.check_priority(0, id)?;
+            self.check_priority(1, id)?;
+            self.check_priority(2, id)?;
 
             let all_frames_are_pending =
                 tsr.tme0().bit_is_clear() && tsr.tme1().bit_is_clear() && tsr.tme2().bit_is_clear();
-            if all_frames_are_pending {
+            if all_frames_are_pending && policy == ReplacementPolicy::RejectWhenFull {
+                return Err(nb::Error::WouldBlock);
+            } else if all_frames_are_pending {
                 // No free mailbox is available. This can only happen when three frames with
                 // ascending priority (descending IDs) were requested for transmission and all
                 // of them are blocked by bus traffic with even higher priority.
@@ -771,7 +2435,7 @@ where
             None
         };
 
-        self.write_mailbox(idx, frame);
+        self.write_mailbox(idx, registers);
 
         let mailbox = match idx {
             0 => Mailbox::Mailbox0,
@@ -781,10 +2445,124 @@ where
         };
         Ok(TransmitStatus {
             dequeued_frame: pending_frame,
+            dequeued_cookie: 0,
             mailbox,
         })
     }
 
+    /// Puts as many frames from `frames` as possible in the free transmit mailboxes, and returns
+    /// the number of frames that were accepted.
+    ///
+    /// Frames are enqueued in iteration order until all mailboxes are occupied, or until a frame
+    /// is not accepted because a pending frame has a higher or equal priority (see
+    /// [`Tx::transmit`]). Unlike `transmit`, this never dequeues a pending frame. The iterator is
+    /// not advanced beyond the first frame that was not accepted, so when iterating over a slice
+    /// `frames`, the frames in `frames[count..]` still need to be sent.
+    ///
+    /// This is more efficient than calling [`Tx::transmit`] for each frame, which is useful for
+    /// gateways forwarding bursts of frames.
+    pub fn transmit_all<'a>(&mut self, frames: impl Iterator<Item = &'a Frame>) -> usize {
+        let mut count = 0;
+        for frame in frames {
+            if self.transmit_to_free_mailbox(frame).is_none() {
+                break;
+            }
+            count += 1;
+        }
+        count
+    }
+
+    /// Puts `frame` in a free mailbox, without dequeuing a pending frame.
+    ///
+    /// Returns `None` if no mailbox is free or the frame is not accepted due to its priority.
+    fn transmit_to_free_mailbox(&mut self, frame: &Frame) -> Option<Mailbox> {
+        self.transmit_with_policy(frame, ReplacementPolicy::RejectWhenFull)
+            .ok()
+            .map(|status| status.mailbox())
+    }
+
+    /// Puts a CAN frame in a transmit mailbox, waiting until one becomes available or `timed_out`
+    /// returns `true`.
+    ///
+    /// `timed_out` is called repeatedly while all mailboxes are occupied by frames of higher
+    /// priority. It can be used to implement a timeout, for example by checking a timer: With an
+    /// `embedded-hal` `CountDown` timer, `|| timer.wait().is_ok()` can be passed. To wait
+    /// indefinitely, pass `|| false`.
+    ///
+    /// This only waits for a free mailbox, not for the transmission of the frame. Otherwise, this
+    /// behaves like [`Tx::transmit`]. Returns `None` if the timeout expired before the frame could
+    /// be enqueued.
+    pub fn transmit_blocking(
+        &mut self,
+        frame: &Frame,
+        timed_out: impl FnMut() -> bool,
+    ) -> Option<TransmitStatus> {
+        match block_with_timeout(|| self.transmit(frame), timed_out) {
+            Ok(status) => status,
+            Err(infallible) => match infallible {},
+        }
+    }
+
+    /// Transmits a frame with a single attempt, regardless of the automatic retransmission
+    /// setting.
+    ///
+    /// The peripheral can only disable retransmission for all pending frames at once, so the frame
+    /// is only enqueued if all mailboxes are empty. Otherwise, `WouldBlock` is returned.
+    ///
+    /// This then waits until the transmission attempt has finished, which takes at most one frame
+    /// time while the peripheral takes part in bus communication, and returns the status of the
+    /// mailbox. It tells whether the frame was transmitted, or lost arbitration or failed with an
+    /// error.
+    ///
+    /// The attempt never finishes while the peripheral is in bus-off state or sleep mode, so
+    /// `timed_out` is called repeatedly while waiting, like in [`Tx::transmit_blocking`]. When it
+    /// returns `true`, the frame is aborted and `Ok(None)` is returned, unless the attempt has
+    /// finished in the meantime. The automatic retransmission setting is restored in any case.
+    pub fn transmit_single_shot(
+        &mut self,
+        frame: &Frame,
+        timed_out: impl FnMut() -> bool,
+    ) -> nb::Result<Option<MailboxStatus>, Infallible> {
+        self.transmit_single_shot_with(frame, timed_out, |_| {})
+    }
+
+    /// Implements [`Tx::transmit_single_shot`], calling `enqueued` with the mailbox the frame was
+    /// put in.
+    fn transmit_single_shot_with(
+        &mut self,
+        frame: &Frame,
+        mut timed_out: impl FnMut() -> bool,
+        enqueued: impl FnOnce(Mailbox),
+    ) -> nb::Result<Option<MailboxStatus>, Infallible> {
+        if !self.is_idle() {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        let can = self.registers();
+        let nart = can.mcr.read().nart().bit_is_set();
+        can.mcr.modify(|_, w| w.nart().set_bit());
+
+        let result = self.transmit(frame).map(|status| {
+            let mailbox = status.mailbox();
+            enqueued(mailbox);
+            loop {
+                let status = self.mailbox_status(mailbox);
+                if status.is_request_completed() {
+                    break Some(status);
+                }
+                if timed_out() {
+                    if self.abort(mailbox) {
+                        break None;
+                    }
+                    break Some(self.mailbox_status(mailbox));
+                }
+            }
+        });
+
+        self.registers().mcr.modify(|_, w| w.nart().bit(nart));
+        result
+    }
+
     /// Returns `Ok` when the mailbox is free or if it contains pending frame with a
     /// lower priority (higher ID) than the identifier `id`.
     fn check_priority(&self, idx: usize, id: IdReg) -> nb::Result<(), Infallible> {
@@ -805,26 +2583,17 @@ where
         Ok(())
     }
 
-    fn write_mailbox(&mut self, idx: usize, frame: &Frame) {
+    fn write_mailbox(&mut self, idx: usize, registers: &MailboxRegisters) {
         let can = self.registers();
 
         debug_assert!(idx < 3);
         let mb = unsafe { &can.tx.get_unchecked(idx) };
 
-        mb.tdtr
-            .write(|w| unsafe { w.dlc().bits(frame.dlc() as u8) });
-        mb.tdlr.write(|w| unsafe {
-            w.bits(u32::from_ne_bytes(
-                frame.data.bytes[0..4].try_into().unwrap(),
-            ))
-        });
-        mb.tdhr.write(|w| unsafe {
-            w.bits(u32::from_ne_bytes(
-                frame.data.bytes[4..8].try_into().unwrap(),
-            ))
-        });
+        mb.tdtr.write(|w| unsafe { w.bits(registers.tdtr()) });
+        mb.tdlr.write(|w| unsafe { w.bits(registers.tdlr()) });
+        mb.tdhr.write(|w| unsafe { w.bits(registers.tdhr()) });
         mb.tir
-            .write(|w| unsafe { w.bits(frame.id.0).txrq().set_bit() });
+            .write(|w| unsafe { w.bits(registers.tir()).txrq().set_bit() });
     }
 
     fn read_pending_mailbox(&mut self, idx: usize) -> Option<Frame> {
@@ -834,13 +2603,11 @@ where
             let mb = unsafe { &can.tx.get_unchecked(idx) };
 
             // Read back the pending frame.
-            let mut pending_frame = Frame {
-                id: IdReg(mb.tir.read().bits()),
-                data: Data::empty(),
-            };
+            let mut pending_frame = Frame::new_data(StandardId::ZERO, []);
+            pending_frame.id = IdReg(mb.tir.read().bits());
             pending_frame.data.bytes[0..4].copy_from_slice(&mb.tdlr.read().bits().to_ne_bytes());
             pending_frame.data.bytes[4..8].copy_from_slice(&mb.tdhr.read().bits().to_ne_bytes());
-            pending_frame.data.len = mb.tdtr.read().dlc().bits();
+            pending_frame.set_raw_dlc(mb.tdtr.read().dlc().bits());
 
             Some(pending_frame)
         } else {
@@ -852,129 +2619,635 @@ where
         }
     }
 
-    /// Tries to abort a pending frame. Returns `true` when aborted.
-    fn abort_by_index(&mut self, idx: usize) -> bool {
-        let can = self.registers();
+    /// Tries to abort a pending frame. Returns `true` when aborted.
+    fn abort_by_index(&mut self, idx: usize) -> bool {
+        let can = self.registers();
+
+        can.tsr.write(|w| unsafe { w.bits(abort_mask(idx)) });
+
+        // Wait for the abort request to be finished.
+        loop {
+            let tsr = can.tsr.read().bits();
+            if tsr & abort_mask(idx) == 0 {
+                break tsr & ok_mask(idx) == 0;
+            }
+        }
+    }
+
+    /// Attempts to abort the sending of a frame that is pending in a mailbox.
+    ///
+    /// If there is no frame in the provided mailbox, or its transmission succeeds before it can be
+    /// aborted, this function has no effect and returns `false`.
+    ///
+    /// If there is a frame in the provided mailbox, and it is canceled successfully, this function
+    /// returns `true`.
+    pub fn abort(&mut self, mailbox: Mailbox) -> bool {
+        // If the mailbox is empty, the value of TXOKx depends on what happened with the previous
+        // frame in that mailbox. Only call abort_by_index() if the mailbox is not empty.
+        let tsr = self.registers().tsr.read();
+        let mailbox_empty = match mailbox {
+            Mailbox::Mailbox0 => tsr.tme0().bit_is_set(),
+            Mailbox::Mailbox1 => tsr.tme1().bit_is_set(),
+            Mailbox::Mailbox2 => tsr.tme2().bit_is_set(),
+        };
+        if mailbox_empty {
+            false
+        } else {
+            self.abort_by_index(mailbox as usize)
+        }
+    }
+
+    /// Returns the status of a transmit mailbox.
+    ///
+    /// The status flags reflect the outcome of the last transmission request in the mailbox, and
+    /// stay set until cleared with [`Tx::clear_mailbox_status`].
+    pub fn mailbox_status(&self, mailbox: Mailbox) -> MailboxStatus {
+        let tsr = self.registers().tsr.read().bits();
+        let idx = mailbox as usize;
+        MailboxStatus {
+            empty: tsr & empty_mask(idx) != 0,
+            request_completed: tsr & request_completed_mask(idx) != 0,
+            transmitted: tsr & ok_mask(idx) != 0,
+            arbitration_lost: tsr & arbitration_lost_mask(idx) != 0,
+            error: tsr & error_mask(idx) != 0,
+        }
+    }
+
+    /// Clears the status flags of a transmit mailbox.
+    ///
+    /// This clears the request completed, transmission OK, arbitration lost and transmission error
+    /// flags reported by [`Tx::mailbox_status`].
+    pub fn clear_mailbox_status(&mut self, mailbox: Mailbox) {
+        // Write-1-to-clear register, writing 0 to the other bits has no effect.
+        self.registers()
+            .tsr
+            .write(|w| unsafe { w.bits(request_completed_mask(mailbox as usize)) });
+    }
+
+    /// Returns `true` if no frame is pending for transmission.
+    pub fn is_idle(&self) -> bool {
+        let can = self.registers();
+        let tsr = can.tsr.read();
+        tsr.tme0().bit_is_set() && tsr.tme1().bit_is_set() && tsr.tme2().bit_is_set()
+    }
+
+    /// Waits until all pending frames have been transmitted.
+    ///
+    /// This should be used before changing the configuration, entering sleep mode or resetting the
+    /// peripheral, so that no frame is cut off. If automatic retransmission is enabled and no other
+    /// node acknowledges the frames, this waits forever, see [`Tx::flush_with_timeout`].
+    pub fn flush(&mut self) {
+        while !self.is_idle() {}
+    }
+
+    /// Waits until all pending frames have been transmitted, or aborts them when `timed_out`
+    /// returns `true`.
+    ///
+    /// `timed_out` is called repeatedly while frames are pending, see [`Tx::transmit_blocking`]
+    /// for how to implement a timeout with it. When it expires, the remaining frames are aborted.
+    /// A frame that is being transmitted at that moment is not cut off, but its transmission is
+    /// completed first.
+    ///
+    /// Returns `true` if all mailboxes became empty without aborting a frame, and `false` if any
+    /// frame was aborted.
+    pub fn flush_with_timeout(&mut self, mut timed_out: impl FnMut() -> bool) -> bool {
+        while !self.is_idle() {
+            if timed_out() {
+                let mut flushed = true;
+                for mailbox in [Mailbox::Mailbox0, Mailbox::Mailbox1, Mailbox::Mailbox2] {
+                    if self.abort(mailbox) {
+                        flushed = false;
+                    }
+                }
+                return flushed;
+            }
+        }
+        true
+    }
+
+    /// Clears the request complete flag for all mailboxes.
+    pub fn clear_interrupt_flags(&mut self) {
+        let can = self.registers();
+        can.tsr
+            .write(|w| w.rqcp2().set_bit().rqcp1().set_bit().rqcp0().set_bit());
+    }
+}
+
+/// Interface to the status change and error (SCE) part of the peripheral.
+///
+/// Returned by [`Can::split_by_interrupt`].
+pub struct Sce<I> {
+    _can: PhantomData<I>,
+}
+
+impl<I> Sce<I>
+where
+    I: Instance,
+{
+    unsafe fn conjure() -> Self {
+        Self { _can: PhantomData }
+    }
+
+    fn registers(&self) -> &RegisterBlock {
+        unsafe { &*I::register_block() }
+    }
+
+    /// Clears the pending flag of [`Interrupt::Sleep`].
+    pub fn clear_sleep_interrupt(&self) {
+        let can = self.registers();
+        // Read-only register with write-1-to-clear, so `&self` is sufficient.
+        can.msr.write(|w| w.slaki().set_bit());
+    }
+
+    /// Clears the pending flag of [`Interrupt::Wakeup`].
+    pub fn clear_wakeup_interrupt(&self) {
+        let can = self.registers();
+        // Read-only register with write-1-to-clear, so `&self` is sufficient.
+        can.msr.write(|w| w.wkui().set_bit());
+    }
+
+    /// Clears the pending flag of [`Interrupt::Error`].
+    pub fn clear_error_interrupt(&self) {
+        let can = self.registers();
+        // Read-only register with write-1-to-clear, so `&self` is sufficient.
+        can.msr.write(|w| w.erri().set_bit());
+    }
+
+    /// Returns the current fault confinement state of the peripheral.
+    pub fn error_state(&self) -> ErrorState {
+        let esr = self.registers().esr.read();
+        if esr.boff().bit_is_set() {
+            ErrorState::BusOff
+        } else if esr.epvf().bit_is_set() {
+            ErrorState::ErrorPassive
+        } else {
+            ErrorState::ErrorActive
+        }
+    }
+
+    /// Returns the value of the transmit error counter (TEC).
+    ///
+    /// See [`Can::transmit_error_counter`] for details.
+    pub fn transmit_error_counter(&self) -> u8 {
+        self.registers().esr.read().tec().bits()
+    }
+
+    /// Returns the value of the receive error counter (REC).
+    ///
+    /// See [`Can::receive_error_counter`] for details.
+    pub fn receive_error_counter(&self) -> u8 {
+        self.registers().esr.read().rec().bits()
+    }
+
+    /// Returns the code of the last bus error detected by the peripheral.
+    ///
+    /// See [`Can::last_error_code`] for details.
+    pub fn last_error_code(&self) -> Option<Lec> {
+        Lec::from_bits(self.registers().esr.read().lec().bits())
+    }
+
+    /// Resets the last error code to "no error".
+    ///
+    /// Unlike [`Can::clear_last_error_code`], this does not update any statistics counters.
+    pub fn clear_last_error_code(&mut self) {
+        // All other fields of the register are read-only.
+        self.registers().esr.write(|w| w.lec().no_error());
+    }
+
+    /// Overwrites the last error code, for testing error handling code.
+    ///
+    /// The `LEC` field is writable by software, so this makes [`Sce::last_error_code`] (and
+    /// [`Can::on_sce_interrupt`]) report `lec` as if the peripheral had detected that error on the
+    /// bus. The error counters and error state are not affected.
+    ///
+    /// Writing the field does not raise [`Interrupt::Error`]. To run the **SCE** interrupt handler,
+    /// pend its interrupt in the NVIC, eg. with `cortex_m::peripheral::NVIC::pend`.
+    pub fn inject_last_error_code(&mut self, lec: Lec) {
+        // All other fields of the register are read-only.
+        self.registers().esr.write(|w| w.lec().bits(lec.to_bits()));
+    }
+}
+
+macro_rules! rx_fifo {
+    ($rx:ident, $fifo:ident, $num:literal) => {
+        #[doc = concat!("Interface to receiver FIFO ", $num, ".")]
+        pub struct $rx<I> {
+            _can: PhantomData<I>,
+        }
+
+        impl<I> $rx<I>
+        where
+            I: Instance,
+        {
+            unsafe fn conjure() -> Self {
+                Self { _can: PhantomData }
+            }
+
+            /// Creates a `&mut Self` out of thin air.
+            ///
+            #[doc = concat!("This is only safe if it is the only way to access an `", stringify!($rx), "<I>`.")]
+            unsafe fn conjure_by_ref<'a>() -> &'a mut Self {
+                // Cause out of bounds access when `Self` is not zero-sized.
+                [()][core::mem::size_of::<Self>()];
+
+                // Any aligned pointer is valid for ZSTs.
+                &mut *NonNull::dangling().as_ptr()
+            }
+
+            /// Returns a received frame if available.
+            ///
+            /// Returns `Err` when a frame was lost due to buffer overrun. The overrun flag is
+            /// cleared in the process, and the frames still stored in the FIFO can be read by
+            /// calling this method again.
+            pub fn receive(&mut self) -> nb::Result<Frame, OverrunError> {
+                self.receive_timestamped().map(|(frame, _)| frame)
+            }
+
+            /// Returns a received frame if available, without using `nb`.
+            ///
+            /// Returns [`TryError::Empty`] if no frame is pending in the FIFO. Otherwise, this
+            #[doc = concat!("behaves like [`", stringify!($rx), "::receive`].")]
+            pub fn try_receive(&mut self) -> Result<Frame, TryError> {
+                self.receive().map_err(TryError::from)
+            }
+
+            /// Waits for a frame to be received, or until `timed_out` returns `true`.
+            ///
+            /// `timed_out` is called repeatedly while the FIFO is empty, see
+            /// [`Tx::transmit_blocking`] for how to implement a timeout with it.
+            ///
+            /// Returns `Ok(None)` if the timeout expired, and `Err` when a frame was lost due to
+            #[doc = concat!("buffer overrun (see [`", stringify!($rx), "::receive`]).")]
+            pub fn receive_blocking(
+                &mut self,
+                timed_out: impl FnMut() -> bool,
+            ) -> Result<Option<Frame>, OverrunError> {
+                block_with_timeout(|| self.receive(), timed_out)
+            }
+
+            /// Returns a received frame and its timestamp if available.
+            ///
+            /// The timestamp is the value of the peripheral's internal 16-bit timer at the start
+            /// of frame. The timer runs at the CAN bit rate and is only active in time triggered
+            /// communication mode (see [`CanConfig::set_time_triggered_communication_mode`]).
+            /// Outside of that mode, the returned timestamp is meaningless.
+            ///
+            #[doc = concat!("Otherwise, this behaves like [`", stringify!($rx), "::receive`].")]
+            pub fn receive_timestamped(&mut self) -> nb::Result<(Frame, u16), OverrunError> {
+                self.receive_with_meta()
+                    .map(|(frame, meta)| (frame, meta.timestamp()))
+            }
+
+            /// Returns a received frame and its [`RxMeta`] data if available.
+            ///
+            #[doc = concat!("Otherwise, this behaves like [`", stringify!($rx), "::receive`].")]
+            pub fn receive_with_meta(&mut self) -> nb::Result<(Frame, RxMeta), OverrunError> {
+                receive_fifo(self.registers(), Fifo::$fifo)
+            }
+
+            /// Receives a frame, copying its data directly into `data` instead of returning a
+            /// [`Frame`].
+            ///
+            /// The first [`RxHeader::dlc`] bytes of `data` contain the data of the frame. For
+            /// remote frames, `data` is left unchanged. Otherwise, this behaves like
+            #[doc = concat!("[`", stringify!($rx), "::receive`].")]
+            pub fn receive_into(
+                &mut self,
+                data: &mut [u8; 8],
+            ) -> nb::Result<RxHeader, OverrunError> {
+                receive_fifo_into(self.registers(), Fifo::$fifo, data)
+            }
+
+            /// Passes all frames pending in the FIFO to `f`, and returns how many frames were
+            /// received.
+            ///
+            /// Only the frames that are pending when the call starts are received (see
+            #[doc = concat!("[`", stringify!($rx), "::pending_count`]). Each mailbox is released right after its frame has")]
+            /// been read, so the FIFO can accept new frames while `f` runs, but frames arriving
+            /// during the call are left for the next one. This is faster than calling
+            #[doc = concat!("[`", stringify!($rx), "::receive`] in a loop, since the overrun flag is only checked once.")]
+            ///
+            /// If the FIFO has overrun, its overrun flag is cleared and `Err` is returned without
+            /// receiving any frames. The pending frames can be received by calling this method
+            /// again.
+            pub fn drain(&mut self, mut f: impl FnMut(Frame)) -> Result<usize, OverrunError> {
+                drain_fifo(self.registers(), Fifo::$fifo, &mut f)
+            }
+
+            /// Returns the current status of the FIFO.
+            ///
+            #[doc = concat!("Unlike [`", stringify!($rx), "::receive`], this does not clear the overrun flag.")]
+            pub fn status(&self) -> FifoStatus {
+                fifo_status(self.registers(), Fifo::$fifo)
+            }
+
+            /// Returns the number of frames pending in the FIFO (0 to 3).
+            ///
+            /// This is the same as [`FifoStatus::pending`], but only reads the pending count.
+            /// Each of these frames can be received without checking the FIFO again, so an
+            /// interrupt handler can drain exactly the frames that were pending when it was
+            /// entered.
+            pub fn pending_count(&self) -> u8 {
+                pending_count(self.registers(), Fifo::$fifo)
+            }
+
+            /// Returns a copy of the oldest frame in the FIFO and its [`RxMeta`] data, without
+            /// removing it.
+            ///
+            /// The frame stays in the FIFO and will be returned again by the next call to
+            #[doc = concat!("[`", stringify!($rx), "::receive`], so the application can decide whether to process it")]
+            /// now or later. The overrun flag is not checked or cleared.
+            ///
+            /// Returns `None` if the FIFO is empty.
+            pub fn peek(&self) -> Option<(Frame, RxMeta)> {
+                peek_fifo(self.registers(), Fifo::$fifo)
+            }
+
+            fn registers(&self) -> &RegisterBlock {
+                unsafe { &*I::register_block() }
+            }
+        }
+    };
+}
+
+rx_fifo!(Rx0, Fifo0, "0");
+rx_fifo!(Rx1, Fifo1, "1");
+
+/// Retries a non-blocking operation until it completes or `timed_out` returns `true`.
+fn block_with_timeout<T, E>(
+    mut op: impl FnMut() -> nb::Result<T, E>,
+    mut timed_out: impl FnMut() -> bool,
+) -> Result<Option<T>, E> {
+    loop {
+        match op() {
+            Ok(value) => return Ok(Some(value)),
+            Err(nb::Error::Other(e)) => return Err(e),
+            Err(nb::Error::WouldBlock) => {
+                if timed_out() {
+                    return Ok(None);
+                }
+            }
+        }
+    }
+}
+
+fn pending_count(can: &RegisterBlock, fifo: Fifo) -> u8 {
+    can.rfr[fifo as usize].read().fmp().bits()
+}
 
-        can.tsr.write(|w| unsafe { w.bits(abort_mask(idx)) });
+fn fifo_status(can: &RegisterBlock, fifo: Fifo) -> FifoStatus {
+    let rfr = can.rfr[fifo as usize].read();
+    FifoStatus {
+        pending: rfr.fmp().bits(),
+        full: rfr.full().bit_is_set(),
+        overrun: rfr.fovr().bit_is_set(),
+    }
+}
 
-        // Wait for the abort request to be finished.
-        loop {
-            let tsr = can.tsr.read().bits();
-            if tsr & abort_mask(idx) == 0 {
-                break tsr & ok_mask(idx) == 0;
-            }
+fn receive_fifo(can: &RegisterBlock, fifo: Fifo) -> nb::Result<(Frame, RxMeta), OverrunError> {
+    receive_fifo_with(can, fifo, |rx| read_fifo_mailbox(rx, fifo))
+}
+
+fn receive_fifo_into(
+    can: &RegisterBlock,
+    fifo: Fifo,
+    data: &mut [u8; 8],
+) -> nb::Result<RxHeader, OverrunError> {
+    receive_fifo_with(can, fifo, |rx| {
+        let id = IdReg(rx.rir.read().bits());
+        if !id.rtr() {
+            data[0..4].copy_from_slice(&rx.rdlr.read().bits().to_ne_bytes());
+            data[4..8].copy_from_slice(&rx.rdhr.read().bits().to_ne_bytes());
+        }
+        let rdtr = rx.rdtr.read();
+        RxHeader {
+            id,
+            dlc: rdtr.dlc().bits(),
+            meta: RxMeta {
+                fifo,
+                filter_match_index: rdtr.fmi().bits(),
+                timestamp: rdtr.time().bits(),
+            },
         }
+    })
+}
+
+/// Reads the output mailbox of a FIFO using `read`, then releases it.
+fn receive_fifo_with<T>(
+    can: &RegisterBlock,
+    fifo: Fifo,
+    read: impl FnOnce(&pac::can::RX) -> T,
+) -> nb::Result<T, OverrunError> {
+    let rfr = &can.rfr[fifo as usize];
+    let rx = &can.rx[fifo as usize];
+
+    // Check if a frame is available in the mailbox.
+    let rfr_read = rfr.read();
+    if rfr_read.fmp().bits() == 0 {
+        return Err(nb::Error::WouldBlock);
     }
 
-    /// Attempts to abort the sending of a frame that is pending in a mailbox.
-    ///
-    /// If there is no frame in the provided mailbox, or its transmission succeeds before it can be
-    /// aborted, this function has no effect and returns `false`.
-    ///
-    /// If there is a frame in the provided mailbox, and it is canceled successfully, this function
-    /// returns `true`.
-    pub fn abort(&mut self, mailbox: Mailbox) -> bool {
-        // If the mailbox is empty, the value of TXOKx depends on what happened with the previous
-        // frame in that mailbox. Only call abort_by_index() if the mailbox is not empty.
-        let tsr = self.registers().tsr.read();
-        let mailbox_empty = match mailbox {
-            Mailbox::Mailbox0 => tsr.tme0().bit_is_set(),
-            Mailbox::Mailbox1 => tsr.tme1().bit_is_set(),
-            Mailbox::Mailbox2 => tsr.tme2().bit_is_set(),
-        };
-        if mailbox_empty {
-            false
-        } else {
-            self.abort_by_index(mailbox as usize)
-        }
+    // Check for RX FIFO overrun.
+    if rfr_read.fovr().bit_is_set() {
+        rfr.write(|w| w.fovr().set_bit());
+        let mode = overrun_mode(can);
+        return Err(nb::Error::Other(OverrunError::new(fifo, mode)));
     }
 
-    /// Returns `true` if no frame is pending for transmission.
-    pub fn is_idle(&self) -> bool {
-        let can = self.registers();
-        let tsr = can.tsr.read();
-        tsr.tme0().bit_is_set() && tsr.tme1().bit_is_set() && tsr.tme2().bit_is_set()
+    let received = read(rx);
+
+    // Release the mailbox.
+    rfr.write(|w| w.rfom().set_bit());
+
+    Ok(received)
+}
+
+fn drain_fifo(
+    can: &RegisterBlock,
+    fifo: Fifo,
+    f: &mut impl FnMut(Frame),
+) -> Result<usize, OverrunError> {
+    let rfr = &can.rfr[fifo as usize];
+    let rx = &can.rx[fifo as usize];
+
+    if rfr.read().fovr().bit_is_set() {
+        rfr.write(|w| w.fovr().set_bit());
+        let mode = overrun_mode(can);
+        return Err(OverrunError::new(fifo, mode));
     }
 
-    /// Clears the request complete flag for all mailboxes.
-    pub fn clear_interrupt_flags(&mut self) {
-        let can = self.registers();
-        can.tsr
-            .write(|w| w.rqcp2().set_bit().rqcp1().set_bit().rqcp0().set_bit());
+    // Frames arriving while draining are left for the next call, so that a busy bus cannot keep
+    // the caller in here forever.
+    let pending = usize::from(rfr.read().fmp().bits());
+    for _ in 0..pending {
+        // Wait until the previous mailbox has been released.
+        while rfr.read().rfom().bit_is_set() {}
+
+        let (frame, _) = read_fifo_mailbox(rx, fifo);
+        rfr.write(|w| w.rfom().set_bit());
+        f(frame);
     }
+    Ok(pending)
 }
 
-/// Interface to the CAN receiver part.
-pub struct Rx<I> {
-    _can: PhantomData<I>,
+fn overrun_mode(can: &RegisterBlock) -> OverrunMode {
+    if can.mcr.read().rflm().bit_is_set() {
+        OverrunMode::Discard
+    } else {
+        OverrunMode::Overwrite
+    }
 }
 
-impl<I> Rx<I>
-where
-    I: Instance,
-{
-    unsafe fn conjure() -> Self {
-        Self { _can: PhantomData }
+fn peek_fifo(can: &RegisterBlock, fifo: Fifo) -> Option<(Frame, RxMeta)> {
+    if can.rfr[fifo as usize].read().fmp().bits() == 0 {
+        return None;
     }
 
-    /// Creates a `&mut Self` out of thin air.
+    Some(read_fifo_mailbox(&can.rx[fifo as usize], fifo))
+}
+
+/// Reads the frame in the output mailbox of a FIFO, without releasing the mailbox.
+fn read_fifo_mailbox(rx: &pac::can::RX, fifo: Fifo) -> (Frame, RxMeta) {
+    let mut frame = Frame::new_data(StandardId::ZERO, [0; 8]);
+    frame.id = IdReg(rx.rir.read().bits());
+    frame.data[0..4].copy_from_slice(&rx.rdlr.read().bits().to_ne_bytes());
+    frame.data[4..8].copy_from_slice(&rx.rdhr.read().bits().to_ne_bytes());
+    let rdtr = rx.rdtr.read();
+    frame.set_raw_dlc(rdtr.dlc().bits());
+    let meta = RxMeta {
+        fifo,
+        filter_match_index: rdtr.fmi().bits(),
+        timestamp: rdtr.time().bits(),
+    };
+    (frame, meta)
+}
+
+/// The order in which the peripheral transmits pending frames.
+///
+/// Configured with [`CanBuilder::set_tx_ordering`] or [`CanConfig::set_tx_ordering`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub enum TxOrdering {
+    /// Frames are transmitted by priority (identifier), as they would be arbitrated on the bus.
     ///
-    /// This is only safe if it is the only way to access an `Rx<I>`.
-    unsafe fn conjure_by_ref<'a>() -> &'a mut Self {
-        // Cause out of bounds access when `Self` is not zero-sized.
-        [()][core::mem::size_of::<Self>()];
+    /// Frames with the same identifier are transmitted in the order they were enqueued.
+    Priority,
+    /// Frames are transmitted in the order they were enqueued, regardless of their priority.
+    ///
+    /// A pending high-priority frame can be delayed by lower-priority frames enqueued before it.
+    Fifo,
+}
 
-        // Any aligned pointer is valid for ZSTs.
-        &mut *NonNull::dangling().as_ptr()
-    }
+/// Determines what happens when a frame is enqueued while all transmit mailboxes are occupied by
+/// frames of lower priority.
+///
+/// Passed to [`Tx::transmit_with_policy`]. [`Tx::transmit`] always uses
+/// [`ReplacementPolicy::DequeueLower`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub enum ReplacementPolicy {
+    /// The lowest priority frame is aborted and replaced, and returned in the [`TransmitStatus`].
+    ///
+    /// This prevents priority inversion, since the new frame does not have to wait for the
+    /// lower-priority frames to be sent.
+    DequeueLower,
+    /// The new frame is rejected with `WouldBlock`, and pending frames are never aborted.
+    RejectWhenFull,
+}
 
-    /// Returns a received frame if available.
+/// Determines which frame is lost when a frame is received while a receive FIFO is full.
+///
+/// Configured with [`CanBuilder::set_overrun_mode`] or [`CanConfig::set_overrun_mode`], and
+/// reported by [`OverrunError::mode`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub enum OverrunMode {
+    /// The newest frame in the FIFO is overwritten by the received frame.
     ///
-    /// Returns `Err` when a frame was lost due to buffer overrun.
-    pub fn receive(&mut self) -> nb::Result<Frame, ()> {
-        match self.receive_fifo(0) {
-            Err(nb::Error::WouldBlock) => self.receive_fifo(1),
-            result => result,
+    /// This makes sure that the most recent frame is always available.
+    Overwrite,
+    /// The FIFO is locked and the received frame is discarded.
+    ///
+    /// This keeps the oldest frames, for protocols that cannot tolerate gaps in the middle of a
+    /// sequence of frames.
+    Discard,
+}
+
+/// Selects how the peripheral is connected to the bus.
+///
+/// Configured with [`CanBuilder::set_mode`], [`CanConfig::set_mode`] or [`Can::set_mode`], and
+/// reported by [`Can::mode`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub enum Mode {
+    /// The peripheral transmits and receives on the bus.
+    Normal,
+    /// The peripheral receives from the bus, but only sends recessive bits.
+    ///
+    /// Frames are not acknowledged and errors are not signaled, so the bus is not disturbed. This
+    /// is useful for monitoring a bus. The peripheral cannot start a transmission in this mode.
+    /// The dominant bits it would have to send (acknowledgement, overload and active error flags)
+    /// are only routed internally, so that it monitors them while the bus stays recessive.
+    Silent,
+    /// Transmitted frames are looped back internally, and also sent on the bus.
+    ///
+    /// The RX pin is ignored, and the acknowledgement of transmitted frames is not checked.
+    Loopback,
+    /// Transmitted frames are looped back internally, and the peripheral is disconnected from the
+    /// bus.
+    ///
+    /// This is the "hot self-test" mode, used by [`Can::self_test`], which can be used without
+    /// affecting a running CAN system.
+    SilentLoopback,
+}
+
+impl Mode {
+    fn from_bits(loopback: bool, silent: bool) -> Self {
+        match (loopback, silent) {
+            (false, false) => Mode::Normal,
+            (false, true) => Mode::Silent,
+            (true, false) => Mode::Loopback,
+            (true, true) => Mode::SilentLoopback,
         }
     }
 
-    fn registers(&self) -> &RegisterBlock {
-        unsafe { &*I::REGISTERS }
+    fn is_loopback(self) -> bool {
+        matches!(self, Mode::Loopback | Mode::SilentLoopback)
     }
 
-    fn receive_fifo(&mut self, fifo_nr: usize) -> nb::Result<Frame, ()> {
-        let can = self.registers();
-
-        assert!(fifo_nr < 2);
-        let rfr = &can.rfr[fifo_nr];
-        let rx = &can.rx[fifo_nr];
-
-        // Check if a frame is available in the mailbox.
-        let rfr_read = rfr.read();
-        if rfr_read.fmp().bits() == 0 {
-            return Err(nb::Error::WouldBlock);
-        }
+    fn is_silent(self) -> bool {
+        matches!(self, Mode::Silent | Mode::SilentLoopback)
+    }
+}
 
-        // Check for RX FIFO overrun.
-        if rfr_read.fovr().bit_is_set() {
-            rfr.write(|w| w.fovr().set_bit());
-            return Err(nb::Error::Other(()));
-        }
+/// A snapshot of the configuration of a peripheral, returned by [`Can::release`].
+///
+/// This captures the bit timing, the mode bits in `CAN_BTR` and `CAN_MCR`, and the set of enabled
+/// interrupts. It can be passed to [`CanBuilder::restore`] to reconfigure the peripheral after it
+/// was reset or powered down. The filter configuration is not included.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub struct SavedConfig {
+    btr: u32,
+    mcr: u32,
+    ier: u32,
+}
 
-        // Read the frame.
-        let mut frame = Frame {
-            id: IdReg(rx.rir.read().bits()),
-            data: [0; 8].into(),
-        };
-        frame.data[0..4].copy_from_slice(&rx.rdlr.read().bits().to_ne_bytes());
-        frame.data[4..8].copy_from_slice(&rx.rdhr.read().bits().to_ne_bytes());
-        frame.data.len = rx.rdtr.read().dlc().bits();
+impl SavedConfig {
+    /// Mask of the configuration bits of `CAN_MCR` (`TTCM`, `ABOM`, `AWUM`, `NART`, `RFLM` and
+    /// `TXFP`).
+    const MCR_MASK: u32 = 0x0000_00FC;
 
-        // Release the mailbox.
-        rfr.write(|w| w.rfom().set_bit());
+    /// Returns the saved bit timing configuration, as passed to [`CanBuilder::set_bit_timing`].
+    #[inline]
+    pub fn bit_timing(&self) -> u32 {
+        self.btr & BTR_TIMING_MASK
+    }
 
-        Ok(frame)
+    /// Returns the set of interrupts that were enabled.
+    #[inline]
+    pub fn interrupts(&self) -> Interrupts {
+        Interrupts::from_bits_truncate(self.ier)
     }
 }
 
@@ -992,8 +3265,13 @@ pub enum Mailbox {
 
 /// Contains information about a frame enqueued for transmission via [`Can::transmit`] or
 /// [`Tx::transmit`].
+///
+/// The [`Mailbox`] can be passed to [`Tx::abort`] to cancel the transmission later.
+#[derive(Debug)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
 pub struct TransmitStatus {
     dequeued_frame: Option<Frame>,
+    dequeued_cookie: u32,
     mailbox: Mailbox,
 }
 
@@ -1004,9 +3282,343 @@ impl TransmitStatus {
         self.dequeued_frame.as_ref()
     }
 
+    /// Returns the cookie of the dequeued frame, as passed to [`Can::transmit_with_cookie`].
+    ///
+    /// Returns 0 if no frame was dequeued, if the dequeued frame had no cookie, or if the frame
+    /// was enqueued through the [`Tx`] half, which does not track cookies.
+    #[inline]
+    pub fn dequeued_cookie(&self) -> u32 {
+        self.dequeued_cookie
+    }
+
     /// Returns the [`Mailbox`] the frame was enqueued in.
     #[inline]
     pub fn mailbox(&self) -> Mailbox {
         self.mailbox
     }
+
+    /// Consumes the status and returns the dequeued lower-priority frame, if any.
+    ///
+    /// Unlike [`TransmitStatus::dequeued_frame`], this does not require cloning the frame.
+    #[inline]
+    pub fn into_dequeued_frame(self) -> Option<Frame> {
+        self.dequeued_frame
+    }
+}
+
+/// Error returned by [`Can::self_test`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum SelfTestError {
+    /// A transmit mailbox or receive FIFO was not empty, so the test was not run.
+    Busy,
+    /// The test frame could not be transmitted.
+    TransmitFailed(MailboxStatus),
+    /// The test frame was transmitted, but not received (eg. because it was rejected by the
+    /// filters).
+    NotReceived,
+    /// A different frame than the test frame was received.
+    Mismatch(Frame),
+}
+
+impl fmt::Display for SelfTestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SelfTestError::Busy => "peripheral busy, self-test not run",
+            SelfTestError::TransmitFailed(_) => "self-test frame could not be transmitted",
+            SelfTestError::NotReceived => "self-test frame was not received",
+            SelfTestError::Mismatch(_) => "received frame does not match the self-test frame",
+        })
+    }
+}
+
+/// Error returned by [`CanBuilder::try_enable`] and [`CanConfig::try_enable`] for an invalid
+/// configuration.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum ConfigError {
+    /// The resynchronization jump width of the bit timing is longer than bit segment 1 or 2.
+    ///
+    /// The CAN specification limits it to the length of the phase segments, otherwise the
+    /// peripheral cannot synchronize to the other nodes reliably.
+    InvalidBitTiming,
+    /// No filter bank of this instance is enabled, so no frames would be received.
+    ///
+    /// Filters have to be configured even for accepting all frames, eg. with
+    /// [`Mask32::accept_all`][filter::Mask32::accept_all]. Use [`CanBuilder::enable`] for
+    /// transmit-only nodes.
+    NoFilters,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ConfigError::InvalidBitTiming => "resynchronization jump width exceeds a bit segment",
+            ConfigError::NoFilters => "no filter bank enabled",
+        })
+    }
+}
+
+/// Status of a transmit mailbox, as returned by [`Tx::mailbox_status`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub struct MailboxStatus {
+    empty: bool,
+    request_completed: bool,
+    transmitted: bool,
+    arbitration_lost: bool,
+    error: bool,
+}
+
+impl MailboxStatus {
+    /// Returns `true` if the mailbox holds no pending frame.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.empty
+    }
+
+    /// Returns `true` if the last transmission request has completed (successfully or not), or
+    /// was aborted.
+    #[inline]
+    pub fn is_request_completed(&self) -> bool {
+        self.request_completed
+    }
+
+    /// Returns `true` if the last frame was transmitted successfully.
+    #[inline]
+    pub fn is_transmitted(&self) -> bool {
+        self.transmitted
+    }
+
+    /// Returns `true` if the last transmission attempt failed because another node won
+    /// arbitration.
+    #[inline]
+    pub fn is_arbitration_lost(&self) -> bool {
+        self.arbitration_lost
+    }
+
+    /// Returns `true` if the last transmission attempt failed because of a bus error.
+    #[inline]
+    pub fn is_transmit_error(&self) -> bool {
+        self.error
+    }
+}
+
+/// The two receive FIFOs.
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub enum Fifo {
+    /// Receive FIFO 0
+    Fifo0 = 0,
+    /// Receive FIFO 1
+    Fifo1 = 1,
+}
+
+/// Status of a receive FIFO, as returned by [`Rx0::status`], [`Rx1::status`] and
+/// [`Can::fifo_status`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub struct FifoStatus {
+    pending: u8,
+    full: bool,
+    overrun: bool,
+}
+
+impl FifoStatus {
+    /// Returns the number of frames stored in the FIFO (0 to 3).
+    #[inline]
+    pub fn pending(&self) -> u8 {
+        self.pending
+    }
+
+    /// Returns `true` if all 3 FIFO slots are occupied.
+    ///
+    /// Any further frame accepted by the filters will cause an overrun.
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.full
+    }
+
+    /// Returns `true` if a frame was lost because it was received while the FIFO was full.
+    #[inline]
+    pub fn is_overrun(&self) -> bool {
+        self.overrun
+    }
+}
+
+/// Additional information about a received frame, returned by [`Rx0::receive_with_meta`],
+/// [`Rx1::receive_with_meta`] and [`Can::receive_with_meta`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub struct RxMeta {
+    fifo: Fifo,
+    filter_match_index: u8,
+    timestamp: u16,
+}
+
+impl RxMeta {
+    /// Returns the receive FIFO the frame was stored in.
+    #[inline]
+    pub fn fifo(&self) -> Fifo {
+        self.fifo
+    }
+
+    /// Returns the index of the filter that accepted the frame.
+    ///
+    /// Filters are numbered per FIFO, counting all filter banks assigned to that FIFO in
+    /// ascending order, whether they are active or not. Each bank contains 1 ([`Mask32`]), 2
+    /// ([`Mask16`], [`ListEntry32`]) or 4 ([`ListEntry16`]) filters.
+    ///
+    /// [`Mask32`]: crate::filter::Mask32
+    /// [`Mask16`]: crate::filter::Mask16
+    /// [`ListEntry32`]: crate::filter::ListEntry32
+    /// [`ListEntry16`]: crate::filter::ListEntry16
+    #[inline]
+    pub fn filter_match_index(&self) -> u8 {
+        self.filter_match_index
+    }
+
+    /// Returns the timestamp of the frame.
+    ///
+    /// See [`Rx0::receive_timestamped`] for details.
+    #[inline]
+    pub fn timestamp(&self) -> u16 {
+        self.timestamp
+    }
+}
+
+/// The identifier, DLC and [`RxMeta`] data of a frame received via [`Rx0::receive_into`],
+/// [`Rx1::receive_into`] or [`Can::receive_into`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub struct RxHeader {
+    id: IdReg,
+    dlc: u8,
+    meta: RxMeta,
+}
+
+impl RxHeader {
+    /// Returns the frame identifier.
+    #[inline]
+    pub fn id(&self) -> Id {
+        self.id.to_id()
+    }
+
+    /// Returns true if the frame is a remote frame.
+    #[inline]
+    pub fn is_remote_frame(&self) -> bool {
+        self.id.rtr()
+    }
+
+    /// Returns the data length code (DLC) of the frame, which is in the range `0..=8`.
+    ///
+    /// Like [`Frame::dlc`], a DLC of 9 to 15 is reported as 8.
+    #[inline]
+    pub fn dlc(&self) -> u8 {
+        self.dlc.min(8)
+    }
+
+    /// Returns the DLC field as received from the bus, which is in the range `0..=15`.
+    ///
+    /// See [`Frame::raw_dlc`] for details.
+    #[inline]
+    pub fn raw_dlc(&self) -> u8 {
+        self.dlc
+    }
+
+    /// Returns the FIFO, filter match index and timestamp of the frame.
+    #[inline]
+    pub fn meta(&self) -> RxMeta {
+        self.meta
+    }
+}
+
+/// Error returned when a receive FIFO overran and at least one frame was lost.
+///
+/// The peripheral only tracks *whether* an overrun happened since the flag was last cleared, not
+/// how many frames were lost.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub struct OverrunError {
+    fifo: Fifo,
+    mode: OverrunMode,
+    count: Option<u32>,
+}
+
+impl OverrunError {
+    pub(crate) fn new(fifo: Fifo, mode: OverrunMode) -> Self {
+        Self {
+            fifo,
+            mode,
+            count: None,
+        }
+    }
+
+    /// Returns the FIFO that overran.
+    #[inline]
+    pub fn fifo(&self) -> Fifo {
+        self.fifo
+    }
+
+    /// Returns the [`OverrunMode`] that was configured when the overrun was detected.
+    ///
+    /// This tells whether the newest frame stored in the FIFO was overwritten, or the frames
+    /// received afterwards were discarded.
+    #[inline]
+    pub fn mode(&self) -> OverrunMode {
+        self.mode
+    }
+
+    /// Returns how many overruns of this FIFO have been detected through the [`Can`] instance,
+    /// including this one.
+    ///
+    /// This is `None` when the overrun was detected by [`Rx0`], [`Rx1`] or another type that
+    /// does not keep track of overruns. See [`Can::overrun_count`].
+    #[inline]
+    pub fn count(&self) -> Option<u32> {
+        self.count
+    }
+}
+
+impl fmt::Display for OverrunError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lost = match self.mode {
+            OverrunMode::Overwrite => "newest frame overwritten",
+            OverrunMode::Discard => "received frame discarded",
+        };
+        write!(f, "receive FIFO {} overran, {}", self.fifo as u8, lost)
+    }
+}
+
+/// Error returned by the `try_transmit` and `try_receive` methods, which do not use `nb`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub enum TryError {
+    /// All transmit mailboxes are occupied by frames of higher priority.
+    Busy,
+    /// No frame has been received.
+    Empty,
+    /// A receive FIFO overran, see [`OverrunError`].
+    Overrun(OverrunError),
+}
+
+impl From<nb::Error<OverrunError>> for TryError {
+    fn from(e: nb::Error<OverrunError>) -> Self {
+        match e {
+            nb::Error::WouldBlock => TryError::Empty,
+            nb::Error::Other(e) => TryError::Overrun(e),
+        }
+    }
+}
+
+impl fmt::Display for TryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryError::Busy => f.write_str("all transmit mailboxes are busy"),
+            TryError::Empty => f.write_str("no frame received"),
+            TryError::Overrun(e) => e.fmt(f),
+        }
+    }
 }
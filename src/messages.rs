@@ -0,0 +1,253 @@
+//! Table-driven message definitions.
+//!
+//! A [`Message`] describes the identifier, DLC and [`Signal`]s of a frame, similar to a message
+//! in a DBC file. Definitions are `const` data, and convert between frames and physical signal
+//! values:
+//!
+//! ```
+//! use bxcan::messages::{Message, Signal};
+//! use bxcan::ByteOrder;
+//!
+//! /// Engine speed in rpm and coolant temperature in °C.
+//! const ENGINE: Message<2> = Message::standard(
+//!     0x100,
+//!     4,
+//!     [
+//!         Signal::new(0, 16, ByteOrder::Intel).scaled(0.25, 0.0),
+//!         Signal::new(16, 8, ByteOrder::Intel).scaled(1.0, -40.0),
+//!     ],
+//! );
+//!
+//! let frame = ENGINE.encode(&[3000.0, 90.0]);
+//! assert_eq!(&frame.data().unwrap()[..], [0xE0, 0x2E, 130, 0]);
+//! assert_eq!(ENGINE.decode(&frame), Some([3000.0, 90.0]));
+//! ```
+
+use crate::{ByteOrder, Data, ExtendedId, Frame, Id, StandardId};
+
+/// A signal packed into the payload of a [`Message`].
+///
+/// The physical value of a signal is `raw * scale + offset`, where `raw` is the integer stored in
+/// the payload.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Signal {
+    start_bit: u8,
+    len: u8,
+    order: ByteOrder,
+    signed: bool,
+    scale: f32,
+    offset: f32,
+}
+
+impl Signal {
+    /// Creates an unsigned signal of `len` bits with a scale of 1 and an offset of 0.
+    ///
+    /// See [`Data::get_bits`] for the meaning of `start_bit`.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if `len` is not in the range `1..=64`. When used in a `const` definition,
+    /// this is a compile-time error.
+    pub const fn new(start_bit: u8, len: u8, order: ByteOrder) -> Self {
+        assert!(len >= 1 && len <= 64, "invalid signal length");
+        Self {
+            start_bit,
+            len,
+            order,
+            signed: false,
+            scale: 1.0,
+            offset: 0.0,
+        }
+    }
+
+    /// Makes this a signed (two's complement) signal.
+    pub const fn signed(mut self) -> Self {
+        self.signed = true;
+        self
+    }
+
+    /// Sets the scale and offset used to convert the raw value to the physical value.
+    pub const fn scaled(mut self, scale: f32, offset: f32) -> Self {
+        self.scale = scale;
+        self.offset = offset;
+        self
+    }
+
+    /// Returns the raw integer value of this signal in `data`, sign-extended if it is signed.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if the signal does not fit into `data`.
+    pub fn decode_raw(&self, data: &Data) -> i64 {
+        let raw = data.get_bits(self.start_bit, self.len, self.order);
+        let unused = 64 - u32::from(self.len);
+        if self.signed {
+            ((raw << unused) as i64) >> unused
+        } else {
+            raw as i64
+        }
+    }
+
+    /// Stores the raw integer value of this signal in `data`.
+    ///
+    /// Bits of `raw` that do not fit into the signal are ignored.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if the signal does not fit into `data`.
+    pub fn encode_raw(&self, data: &mut Data, raw: i64) {
+        data.set_bits(self.start_bit, self.len, self.order, raw as u64);
+    }
+
+    /// Returns the physical value of this signal in `data`.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if the signal does not fit into `data`.
+    pub fn decode(&self, data: &Data) -> f32 {
+        self.decode_raw(data) as f32 * self.scale + self.offset
+    }
+
+    /// Stores the physical `value` of this signal in `data`.
+    ///
+    /// The value is rounded to the nearest raw value. Values outside of the range of the signal
+    /// are truncated to its bits, like in [`Signal::encode_raw`].
+    ///
+    /// # Panics
+    ///
+    /// This will panic if the signal does not fit into `data`.
+    pub fn encode(&self, data: &mut Data, value: f32) {
+        let raw = (value - self.offset) / self.scale;
+        // `f32::round` is not available in `core`.
+        let raw = if raw < 0.0 { raw - 0.5 } else { raw + 0.5 } as i64;
+        self.encode_raw(data, raw);
+    }
+}
+
+/// The layout of a data frame made up of `N` signals.
+///
+/// See the [module documentation](self) for an example.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Message<const N: usize> {
+    id: Id,
+    dlc: u8,
+    signals: [Signal; N],
+}
+
+impl<const N: usize> Message<N> {
+    /// Defines a message with a standard identifier.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if `id` is not a valid standard identifier or `dlc` is greater than 8.
+    /// When used in a `const` definition, this is a compile-time error.
+    pub const fn standard(id: u16, dlc: u8, signals: [Signal; N]) -> Self {
+        let id = match StandardId::new(id) {
+            Some(id) => id,
+            None => panic!("invalid standard ID"),
+        };
+        Self::new(Id::Standard(id), dlc, signals)
+    }
+
+    /// Defines a message with an extended identifier.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if `id` is not a valid extended identifier or `dlc` is greater than 8.
+    /// When used in a `const` definition, this is a compile-time error.
+    pub const fn extended(id: u32, dlc: u8, signals: [Signal; N]) -> Self {
+        let id = match ExtendedId::new(id) {
+            Some(id) => id,
+            None => panic!("invalid extended ID"),
+        };
+        Self::new(Id::Extended(id), dlc, signals)
+    }
+
+    const fn new(id: Id, dlc: u8, signals: [Signal; N]) -> Self {
+        assert!(dlc <= 8, "invalid DLC");
+        Self { id, dlc, signals }
+    }
+
+    /// Returns the identifier of this message.
+    #[inline]
+    pub fn id(&self) -> Id {
+        self.id
+    }
+
+    /// Returns the DLC of this message.
+    #[inline]
+    pub fn dlc(&self) -> u8 {
+        self.dlc
+    }
+
+    /// Returns the signals of this message.
+    #[inline]
+    pub fn signals(&self) -> &[Signal; N] {
+        &self.signals
+    }
+
+    /// Returns `true` if `frame` is a data frame with the identifier and DLC of this message.
+    pub fn matches(&self, frame: &Frame) -> bool {
+        frame.is_data_frame() && frame.id() == self.id && frame.dlc() == self.dlc
+    }
+
+    /// Creates a frame containing the physical `values` of the signals.
+    ///
+    /// Bits of the payload that are not covered by a signal are 0.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if a signal does not fit into the DLC of this message.
+    pub fn encode(&self, values: &[f32; N]) -> Frame {
+        let mut data = Data::new(&[0; 8][..usize::from(self.dlc)]).unwrap();
+        for (signal, &value) in self.signals.iter().zip(values) {
+            signal.encode(&mut data, value);
+        }
+        Frame::new_data(self.id, data)
+    }
+
+    /// Returns the physical values of the signals in `frame`.
+    ///
+    /// Returns `None` if the frame does not [match][Message::matches] this message.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if a signal does not fit into the DLC of this message.
+    pub fn decode(&self, frame: &Frame) -> Option<[f32; N]> {
+        if !self.matches(frame) {
+            return None;
+        }
+        let data = frame.data()?;
+        let mut values = [0.0; N];
+        for (value, signal) in values.iter_mut().zip(&self.signals) {
+            *value = signal.decode(data);
+        }
+        Some(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signed_signals() {
+        const MESSAGE: Message<2> = Message::extended(
+            0x18FE_F100,
+            3,
+            [
+                Signal::new(7, 12, ByteOrder::Motorola).signed(),
+                Signal::new(16, 8, ByteOrder::Intel)
+                    .signed()
+                    .scaled(0.5, 0.0),
+            ],
+        );
+
+        let frame = MESSAGE.encode(&[-2.0, -1.5]);
+        assert_eq!(&frame.data().unwrap()[..], [0xFF, 0xE0, 0xFD]);
+        assert_eq!(MESSAGE.decode(&frame), Some([-2.0, -1.5]));
+
+        let other = Frame::new_data(StandardId::ZERO, [0; 3]);
+        assert_eq!(MESSAGE.decode(&other), None);
+    }
+}
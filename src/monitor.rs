@@ -0,0 +1,103 @@
+//! Listen-only bus monitoring.
+
+use crate::{Can, Frame, Instance, Interrupt, Mode, OverrunError, SceEvent};
+
+/// An event observed by a [`Monitor`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub enum MonitorEvent {
+    /// A frame was received, along with its timestamp.
+    ///
+    /// See [`Rx0::receive_timestamped`][crate::Rx0::receive_timestamped] for details about the
+    /// timestamp.
+    Frame(Frame, u16),
+    /// Frames were lost because a receive FIFO overran.
+    Overrun(OverrunError),
+    /// A status change or bus error was detected, see [`Can::on_sce_interrupt`].
+    Sce(SceEvent),
+}
+
+/// A listen-only view of the bus, for building bus analyzers and loggers.
+///
+/// The monitor puts the peripheral into [`Mode::Silent`], so it receives all frames accepted by
+/// its filters without acknowledging them or otherwise influencing the bus. [`Monitor::poll`]
+/// merges received frames and decoded error events into a single stream:
+///
+/// ```no_run
+/// # use bxcan::{Instance, RegisterBlock};
+/// # struct CAN1;
+/// # unsafe impl Instance for CAN1 {
+/// #     const REGISTERS: *mut RegisterBlock = 0x4000_6400 as *mut _;
+/// # }
+/// use bxcan::{Can, Monitor, MonitorEvent, SceEvent};
+///
+/// fn log(can: Can<CAN1>) -> ! {
+///     let mut monitor = Monitor::new(can);
+///     loop {
+///         match monitor.poll() {
+///             Some(MonitorEvent::Frame(frame, timestamp)) => { /* Log `frame`. */ }
+///             Some(MonitorEvent::Sce(SceEvent::BusError(lec))) => { /* Log `lec`. */ }
+///             Some(_) | None => {}
+///         }
+///     }
+/// }
+/// ```
+///
+/// Like with [`Can::on_sce_interrupt`], bus errors are only reported if
+/// [`Interrupt::LastErrorCode`] is enabled, which the monitor does while it exists. The
+/// interrupt line does not need to be enabled in the interrupt controller to use
+/// [`Monitor::poll`].
+pub struct Monitor<I: Instance> {
+    can: Can<I>,
+    /// Mode to restore in [`Monitor::free`].
+    mode: Mode,
+    /// Whether [`Interrupt::LastErrorCode`] was already enabled before.
+    lec_enabled: bool,
+}
+
+impl<I: Instance> Monitor<I> {
+    /// Puts `can` into silent mode and starts monitoring the bus.
+    ///
+    /// `can` should already be configured and enabled, including its filters. Frames that are
+    /// still pending in its transmit mailboxes are not sent while the monitor exists.
+    pub fn new(mut can: Can<I>) -> Self {
+        let mode = can.mode();
+        can.set_mode(Mode::Silent);
+        let lec_enabled = can.registers().ier.read().lecie().bit_is_set();
+        can.enable_interrupt(Interrupt::LastErrorCode);
+        Self {
+            can,
+            mode,
+            lec_enabled,
+        }
+    }
+
+    /// Returns the next event, or `None` if nothing happened since the last call.
+    ///
+    /// Status and error events are returned before received frames.
+    pub fn poll(&mut self) -> Option<MonitorEvent> {
+        if let Some(event) = self.can.on_sce_interrupt() {
+            return Some(MonitorEvent::Sce(event));
+        }
+        match self.can.receive_timestamped() {
+            Ok((frame, timestamp)) => Some(MonitorEvent::Frame(frame, timestamp)),
+            Err(nb::Error::Other(e)) => Some(MonitorEvent::Overrun(e)),
+            Err(nb::Error::WouldBlock) => None,
+        }
+    }
+
+    /// Returns a reference to the monitored instance.
+    #[inline]
+    pub fn can(&self) -> &Can<I> {
+        &self.can
+    }
+
+    /// Stops monitoring, and returns the instance in the mode it had before.
+    pub fn free(mut self) -> Can<I> {
+        if !self.lec_enabled {
+            self.can.disable_interrupt(Interrupt::LastErrorCode);
+        }
+        self.can.set_mode(self.mode);
+        self.can
+    }
+}
@@ -0,0 +1,227 @@
+//! Raw register access.
+
+use core::marker::PhantomData;
+use core::ptr::addr_of_mut;
+
+use crate::{Can, Fifo, FilterOwner, Instance, RegisterBlock};
+
+/// A control or status register of the bxCAN peripheral, as named in the reference manual.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum Register {
+    /// `CAN_MCR`, the master control register.
+    Mcr,
+    /// `CAN_MSR`, the master status register.
+    Msr,
+    /// `CAN_TSR`, the transmit status register.
+    Tsr,
+    /// `CAN_RF0R` or `CAN_RF1R`, the receive FIFO register of a FIFO.
+    Rfr(Fifo),
+    /// `CAN_IER`, the interrupt enable register.
+    Ier,
+    /// `CAN_ESR`, the error status register.
+    Esr,
+    /// `CAN_BTR`, the bit timing register.
+    Btr,
+}
+
+impl Register {
+    fn ptr(self, block: *mut RegisterBlock) -> *mut u32 {
+        // Safety: `block` points to a register block, so the fields are in bounds.
+        unsafe {
+            match self {
+                Register::Mcr => addr_of_mut!((*block).mcr) as *mut u32,
+                Register::Msr => addr_of_mut!((*block).msr) as *mut u32,
+                Register::Tsr => addr_of_mut!((*block).tsr) as *mut u32,
+                Register::Rfr(fifo) => addr_of_mut!((*block).rfr[fifo as usize]) as *mut u32,
+                Register::Ier => addr_of_mut!((*block).ier) as *mut u32,
+                Register::Esr => addr_of_mut!((*block).esr) as *mut u32,
+                Register::Btr => addr_of_mut!((*block).btr) as *mut u32,
+            }
+        }
+    }
+}
+
+/// A filter configuration register of the bxCAN peripheral, as named in the reference manual.
+///
+/// The filter registers are shared by all instances, and can only be accessed through the
+/// instance that owns them (see [`FilterOwner`]).
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum FilterRegister {
+    /// `CAN_FMR`, the filter master register.
+    Fmr,
+    /// `CAN_FM1R`, the filter mode register.
+    Fm1r,
+    /// `CAN_FS1R`, the filter scale register.
+    Fs1r,
+    /// `CAN_FFA1R`, the filter FIFO assignment register.
+    Ffa1r,
+    /// `CAN_FA1R`, the filter activation register.
+    Fa1r,
+}
+
+impl FilterRegister {
+    fn ptr(self, block: *mut RegisterBlock) -> *mut u32 {
+        // Safety: `block` points to a register block, so the fields are in bounds.
+        unsafe {
+            match self {
+                FilterRegister::Fmr => addr_of_mut!((*block).fmr) as *mut u32,
+                FilterRegister::Fm1r => addr_of_mut!((*block).fm1r) as *mut u32,
+                FilterRegister::Fs1r => addr_of_mut!((*block).fs1r) as *mut u32,
+                FilterRegister::Ffa1r => addr_of_mut!((*block).ffa1r) as *mut u32,
+                FilterRegister::Fa1r => addr_of_mut!((*block).fa1r) as *mut u32,
+            }
+        }
+    }
+}
+
+/// Direct access to the registers of a bxCAN peripheral, returned by [`Can::peripheral`].
+///
+/// This is an escape hatch for features that are not covered by the [`Can`] API, and for mixing
+/// `bxcan` with the register definitions of a peripheral access crate. It borrows the [`Can`]
+/// instance mutably, so it cannot be used concurrently with the driver.
+///
+/// Reading a register has no side effects on the bxCAN peripheral, so [`Registers::read`] is safe.
+/// Writing registers is `unsafe`, since it can violate assumptions the driver makes about the
+/// state of the peripheral, like the current mode or the contents of the transmit mailboxes. The
+/// filter registers are only accessible for instances that implement [`FilterOwner`].
+///
+/// ```no_run
+/// # use bxcan::{Instance, RegisterBlock};
+/// # struct CAN1;
+/// # unsafe impl Instance for CAN1 {
+/// #     const REGISTERS: *mut RegisterBlock = 0x4000_6400 as *mut _;
+/// # }
+/// use bxcan::{Can, Register};
+///
+/// fn debug_freeze(can: &mut Can<CAN1>) {
+///     let mut registers = can.peripheral();
+///     let mcr = registers.read(Register::Mcr);
+///     log(mcr);
+///     // Safety: Setting the `DBF` bit only affects the behavior while the core is halted.
+///     unsafe { registers.modify(Register::Mcr, |mcr| mcr | 1 << 16) };
+/// }
+/// # fn log(_: u32) {}
+/// ```
+pub struct Registers<'a, I: Instance> {
+    _can: PhantomData<&'a mut Can<I>>,
+}
+
+impl<I: Instance> Registers<'_, I> {
+    /// Returns a pointer to the register block of the peripheral.
+    ///
+    /// It is the pointer returned by [`Instance::register_block`], and can be cast to the register
+    /// block type of a peripheral access crate. Accesses through the pointer are subject to the
+    /// same rules as [`Registers::write`].
+    #[inline]
+    pub fn as_ptr(&self) -> *mut RegisterBlock {
+        I::register_block()
+    }
+
+    /// Returns the value of `register`.
+    pub fn read(&self, register: Register) -> u32 {
+        // Safety: The `Instance` impl guarantees that the register block can be accessed while the
+        // `Can` is borrowed, and reads have no side effects.
+        unsafe { register.ptr(self.as_ptr()).read_volatile() }
+    }
+
+    /// Writes `value` to `register`.
+    ///
+    /// # Safety
+    ///
+    /// The write must not change the configuration or state of the peripheral in a way the driver
+    /// does not expect. This includes the mode, and the contents of the transmit mailboxes and
+    /// receive FIFOs.
+    pub unsafe fn write(&mut self, register: Register, value: u32) {
+        register.ptr(self.as_ptr()).write_volatile(value)
+    }
+
+    /// Reads `register`, and writes back the value returned by `f`.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as for [`Registers::write`] apply. Note that some bits of the status
+    /// registers are cleared by writing 1 to them, so writing back a value that was read can clear
+    /// pending flags.
+    pub unsafe fn modify(&mut self, register: Register, f: impl FnOnce(u32) -> u32) {
+        let value = self.read(register);
+        self.write(register, f(value))
+    }
+}
+
+impl<I: FilterOwner> Registers<'_, I> {
+    /// Returns the value of the filter register `register`.
+    pub fn read_filter(&self, register: FilterRegister) -> u32 {
+        // Safety: As in `read`, and `I` owns the filter registers.
+        unsafe { register.ptr(self.as_ptr()).read_volatile() }
+    }
+
+    /// Writes `value` to the filter register `register`.
+    ///
+    /// # Safety
+    ///
+    /// The write must not change the filter banks assigned to other instances, and must leave the
+    /// filters in the state the driver expects (eg. not in filter initialization mode).
+    pub unsafe fn write_filter(&mut self, register: FilterRegister, value: u32) {
+        register.ptr(self.as_ptr()).write_volatile(value)
+    }
+
+    /// Reads the filter register `register`, and writes back the value returned by `f`.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as for [`Registers::write_filter`] apply.
+    pub unsafe fn modify_filter(&mut self, register: FilterRegister, f: impl FnOnce(u32) -> u32) {
+        let value = self.read_filter(register);
+        self.write_filter(register, f(value))
+    }
+}
+
+impl<I: Instance> Can<I> {
+    /// Returns a handle for accessing the registers of the peripheral directly.
+    ///
+    /// See [`Registers`] for details.
+    #[inline]
+    pub fn peripheral(&mut self) -> Registers<'_, I> {
+        Registers { _can: PhantomData }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::mem::MaybeUninit;
+
+    #[test]
+    fn offsets() {
+        let mut block = MaybeUninit::<RegisterBlock>::uninit();
+        let base = block.as_mut_ptr();
+        let offset = |ptr: *mut u32| ptr as usize - base as usize;
+
+        // Offsets from the reference manual.
+        for &(register, expected) in &[
+            (Register::Mcr, 0x00),
+            (Register::Msr, 0x04),
+            (Register::Tsr, 0x08),
+            (Register::Rfr(Fifo::Fifo0), 0x0c),
+            (Register::Rfr(Fifo::Fifo1), 0x10),
+            (Register::Ier, 0x14),
+            (Register::Esr, 0x18),
+            (Register::Btr, 0x1c),
+        ] {
+            assert_eq!(offset(register.ptr(base)), expected, "{:?}", register);
+        }
+        for &(register, expected) in &[
+            (FilterRegister::Fmr, 0x200),
+            (FilterRegister::Fm1r, 0x204),
+            (FilterRegister::Fs1r, 0x20c),
+            (FilterRegister::Ffa1r, 0x214),
+            (FilterRegister::Fa1r, 0x21c),
+        ] {
+            assert_eq!(offset(register.ptr(base)), expected, "{:?}", register);
+        }
+    }
+}
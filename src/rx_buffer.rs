@@ -0,0 +1,190 @@
+//! Lock-free receive buffer.
+
+use crate::counting_queue::{CountingConsumer, CountingProducer, CountingQueue};
+use crate::{Frame, OverrunError};
+
+/// A ring buffer for received frames, to be filled from an interrupt handler and drained from the
+/// main loop.
+///
+/// The buffer is split into an [`RxProducer`] and an [`RxConsumer`] half. The producer is meant
+/// to be used by the **RX FIFO** interrupt handler, and the consumer by the application. They can
+/// be used concurrently without a critical section, as long as there is only a single context
+/// using each half.
+///
+/// The buffer holds up to `N - 1` frames. Frames that arrive while it is full are dropped and
+/// counted, see [`RxConsumer::overflows`].
+///
+/// ```no_run
+/// # use bxcan::{Instance, RegisterBlock};
+/// # struct CAN1;
+/// # unsafe impl Instance for CAN1 {
+/// #     const REGISTERS: *mut RegisterBlock = 0x4000_6400 as *mut _;
+/// # }
+/// use bxcan::{Rx0, RxProducer, RxRingBuffer};
+///
+/// static mut BUFFER: RxRingBuffer<16> = RxRingBuffer::new();
+///
+/// // Called from the RX FIFO 0 interrupt handler.
+/// fn can1_rx0(rx: &mut Rx0<CAN1>, producer: &mut RxProducer<'static, 16>) {
+///     if producer.fill(|| rx.receive()).is_err() {
+///         // The hardware FIFO overran, which is not counted by the buffer.
+///     }
+/// }
+///
+/// # fn main() {
+/// // Safety: `BUFFER` is only accessed here, once.
+/// let (mut producer, mut consumer) = unsafe { (*core::ptr::addr_of_mut!(BUFFER)).split() };
+/// // Move `producer` to the interrupt handler, then:
+/// loop {
+///     while let Some(frame) = consumer.pop() {
+///         // Process `frame`.
+///     }
+/// }
+/// # }
+/// ```
+pub struct RxRingBuffer<const N: usize> {
+    queue: CountingQueue<Frame, N>,
+}
+
+impl<const N: usize> RxRingBuffer<N> {
+    /// Creates an empty buffer.
+    pub const fn new() -> Self {
+        Self {
+            queue: CountingQueue::new(),
+        }
+    }
+
+    /// Splits the buffer into its producer and consumer halves.
+    pub fn split(&mut self) -> (RxProducer<'_, N>, RxConsumer<'_, N>) {
+        let (producer, consumer) = self.queue.split();
+        (RxProducer { producer }, RxConsumer { consumer })
+    }
+}
+
+impl<const N: usize> Default for RxRingBuffer<N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The producer half of an [`RxRingBuffer`], used by the interrupt handler.
+pub struct RxProducer<'a, const N: usize> {
+    producer: CountingProducer<'a, Frame, N>,
+}
+
+impl<const N: usize> RxProducer<'_, N> {
+    /// Adds a frame to the buffer.
+    ///
+    /// If the buffer is full, the frame is returned in `Err` and counted as an overflow.
+    pub fn push(&mut self, frame: Frame) -> Result<(), Frame> {
+        self.producer.push(frame)
+    }
+
+    /// Moves all frames returned by `receive` into the buffer, until it returns
+    /// [`WouldBlock`][nb::Error::WouldBlock].
+    ///
+    /// `receive` is typically a closure calling [`Rx0::receive`][crate::Rx0::receive] or
+    /// [`Rx1::receive`][crate::Rx1::receive]. Frames that do not fit into the buffer are dropped
+    /// and counted as overflows.
+    ///
+    /// If `receive` reports an overrun of the hardware FIFO, the error is returned immediately.
+    /// The frames remaining in the FIFO can be moved by calling this method again.
+    pub fn fill(
+        &mut self,
+        mut receive: impl FnMut() -> nb::Result<Frame, OverrunError>,
+    ) -> Result<(), OverrunError> {
+        loop {
+            match receive() {
+                Ok(frame) => {
+                    self.push(frame).ok();
+                }
+                Err(nb::Error::WouldBlock) => return Ok(()),
+                Err(nb::Error::Other(e)) => return Err(e),
+            }
+        }
+    }
+
+    /// Returns `true` if there is space for another frame in the buffer.
+    #[inline]
+    pub fn ready(&self) -> bool {
+        self.producer.ready()
+    }
+}
+
+/// The consumer half of an [`RxRingBuffer`], used by the application.
+pub struct RxConsumer<'a, const N: usize> {
+    consumer: CountingConsumer<'a, Frame, N>,
+}
+
+impl<const N: usize> RxConsumer<'_, N> {
+    /// Removes the oldest frame from the buffer.
+    #[inline]
+    pub fn pop(&mut self) -> Option<Frame> {
+        self.consumer.pop()
+    }
+
+    /// Returns the number of frames in the buffer.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.consumer.len()
+    }
+
+    /// Returns `true` if the buffer contains no frames.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.consumer.is_empty()
+    }
+
+    /// Returns the total number of frames that were dropped because the buffer was full.
+    ///
+    /// The counter wraps around at `u32::MAX`.
+    pub fn overflows(&self) -> u32 {
+        self.consumer.overflows()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Fifo, OverrunMode, StandardId};
+
+    #[test]
+    fn overflow() {
+        let mut buffer = RxRingBuffer::<3>::new();
+        let (mut producer, mut consumer) = buffer.split();
+        let frames = [1, 2, 3].map(|byte| Frame::new_data(StandardId::ZERO, [byte]));
+
+        producer.push(frames[0].clone()).unwrap();
+        producer.push(frames[1].clone()).unwrap();
+        assert!(!producer.ready());
+        assert_eq!(producer.push(frames[2].clone()), Err(frames[2].clone()));
+        assert_eq!(consumer.len(), 2);
+        assert_eq!(consumer.overflows(), 1);
+
+        assert_eq!(consumer.pop(), Some(frames[0].clone()));
+        assert_eq!(consumer.pop(), Some(frames[1].clone()));
+        assert_eq!(consumer.pop(), None);
+        assert!(consumer.is_empty());
+    }
+
+    #[test]
+    fn fill() {
+        let mut buffer = RxRingBuffer::<2>::new();
+        let (mut producer, consumer) = buffer.split();
+        let frame = Frame::new_data(StandardId::ZERO, []);
+
+        let mut results = IntoIterator::into_iter([
+            Ok(frame.clone()),
+            Ok(frame.clone()),
+            Err(nb::Error::WouldBlock),
+        ]);
+        assert_eq!(producer.fill(|| results.next().unwrap()), Ok(()));
+        assert_eq!(consumer.len(), 1);
+        assert_eq!(consumer.overflows(), 1);
+
+        let overrun = OverrunError::new(Fifo::Fifo1, OverrunMode::Overwrite);
+        let mut results = IntoIterator::into_iter([Err(nb::Error::Other(overrun))]);
+        assert_eq!(producer.fill(|| results.next().unwrap()), Err(overrun));
+    }
+}
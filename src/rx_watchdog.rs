@@ -0,0 +1,192 @@
+//! Detection of missing periodic frames.
+
+use heapless::Vec;
+
+use crate::{Frame, Id};
+
+/// Monitors identifiers that are expected to be received periodically, eg. heartbeats of other
+/// nodes.
+///
+/// Each entry is an identifier with a timeout, measured in calls to [`RxWatchdog::tick`]. Up to
+/// `N` identifiers can be watched. Received frames have to be passed to [`RxWatchdog::feed`].
+/// When no frame with a watched identifier is received within its timeout, the identifier is
+/// marked as stale until the next such frame arrives.
+///
+/// ```
+/// use bxcan::{standard_id, Frame, Id, RxWatchdog};
+///
+/// let mut watchdog = RxWatchdog::<4>::new();
+/// // Expect the heartbeat of node 5 at least every 150 ticks.
+/// watchdog.watch(standard_id!(0x705), 150).unwrap();
+///
+/// // Called from the RX FIFO interrupt handler for every received frame.
+/// watchdog.feed(&Frame::new_data(standard_id!(0x705), [0x05]));
+///
+/// // Called from a timer interrupt handler every millisecond.
+/// watchdog.tick(|id: Id| {
+///     // No frame with `id` was received within its timeout.
+/// });
+/// assert_eq!(watchdog.is_stale(standard_id!(0x705)), Some(false));
+/// ```
+pub struct RxWatchdog<const N: usize> {
+    entries: Vec<Entry, N>,
+}
+
+struct Entry {
+    id: Id,
+    timeout: u32,
+    /// Ticks since the last frame was received.
+    elapsed: u32,
+    stale: bool,
+}
+
+impl<const N: usize> RxWatchdog<N> {
+    /// Creates a watchdog without any watched identifiers.
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Starts watching `id` with a timeout of `timeout` ticks.
+    ///
+    /// The timeout starts running immediately, so a frame with `id` has to be received within
+    /// `timeout` ticks from now. If `id` is already watched, its timeout is replaced and restarted.
+    ///
+    /// Returns `Err` with `id` if the maximum number of identifiers is already watched.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if `timeout` is 0.
+    pub fn watch(&mut self, id: impl Into<Id>, timeout: u32) -> Result<(), Id> {
+        assert!(timeout != 0, "timeout must not be 0");
+
+        let id = id.into();
+        let entry = Entry {
+            id,
+            timeout,
+            elapsed: 0,
+            stale: false,
+        };
+        match self.entries.iter_mut().find(|e| e.id == id) {
+            Some(existing) => {
+                *existing = entry;
+                Ok(())
+            }
+            None => self.entries.push(entry).map_err(|entry| entry.id),
+        }
+    }
+
+    /// Stops watching `id`.
+    ///
+    /// Returns `false` if `id` was not watched.
+    pub fn unwatch(&mut self, id: impl Into<Id>) -> bool {
+        let id = id.into();
+        match self.entries.iter().position(|e| e.id == id) {
+            Some(index) => {
+                self.entries.swap_remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stops watching all identifiers.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Records the reception of `frame`, restarting the timeout of its identifier.
+    ///
+    /// Returns `true` if the identifier of `frame` is watched.
+    pub fn feed(&mut self, frame: &Frame) -> bool {
+        let id = frame.id();
+        match self.entries.iter_mut().find(|e| e.id == id) {
+            Some(entry) => {
+                entry.elapsed = 0;
+                entry.stale = false;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Advances time by one tick, and calls `on_timeout` for every identifier whose timeout has
+    /// just expired.
+    ///
+    /// `on_timeout` is only called once per timeout. It is called again if the identifier becomes
+    /// stale again after a frame has been received.
+    pub fn tick(&mut self, mut on_timeout: impl FnMut(Id)) {
+        for entry in self.entries.iter_mut().filter(|e| !e.stale) {
+            entry.elapsed += 1;
+            if entry.elapsed >= entry.timeout {
+                entry.stale = true;
+                on_timeout(entry.id);
+            }
+        }
+    }
+
+    /// Returns whether no frame with `id` was received within its timeout.
+    ///
+    /// Returns `None` if `id` is not watched.
+    pub fn is_stale(&self, id: impl Into<Id>) -> Option<bool> {
+        let id = id.into();
+        self.entries.iter().find(|e| e.id == id).map(|e| e.stale)
+    }
+
+    /// Returns an iterator over all stale identifiers.
+    pub fn stale(&self) -> impl Iterator<Item = Id> + '_ {
+        self.entries.iter().filter(|e| e.stale).map(|e| e.id)
+    }
+}
+
+impl<const N: usize> Default for RxWatchdog<N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExtendedId, StandardId};
+
+    fn tick(watchdog: &mut RxWatchdog<2>, timeouts: &mut Vec<Id, 4>) {
+        watchdog.tick(|id| timeouts.push(id).unwrap());
+    }
+
+    #[test]
+    fn timeouts() {
+        let mut watchdog = RxWatchdog::<2>::new();
+        let a = StandardId::new(0x701).unwrap();
+        let b = ExtendedId::new(0x701).unwrap();
+        watchdog.watch(a, 2).unwrap();
+        watchdog.watch(b, 3).unwrap();
+        assert_eq!(
+            watchdog.watch(StandardId::ZERO, 1),
+            Err(Id::from(StandardId::ZERO))
+        );
+
+        let mut timeouts = Vec::new();
+        tick(&mut watchdog, &mut timeouts);
+        assert!(watchdog.feed(&Frame::new_data(b, [])));
+        tick(&mut watchdog, &mut timeouts);
+        tick(&mut watchdog, &mut timeouts);
+        assert_eq!(watchdog.is_stale(a), Some(true));
+        assert_eq!(watchdog.is_stale(b), Some(false));
+        tick(&mut watchdog, &mut timeouts);
+        tick(&mut watchdog, &mut timeouts);
+        assert_eq!(watchdog.stale().count(), 2);
+
+        assert!(watchdog.feed(&Frame::new_data(a, [])));
+        assert!(!watchdog.feed(&Frame::new_data(StandardId::ZERO, [])));
+        assert_eq!(watchdog.is_stale(a), Some(false));
+        assert!(watchdog.unwatch(b));
+        assert_eq!(watchdog.is_stale(b), None);
+        tick(&mut watchdog, &mut timeouts);
+        tick(&mut watchdog, &mut timeouts);
+
+        assert_eq!(&timeouts[..], [Id::from(a), Id::from(b), Id::from(a)]);
+    }
+}
@@ -0,0 +1,258 @@
+//! Periodic transmission of frames.
+
+use heapless::Vec;
+
+use crate::{Data, Frame, Id, Instance, TxQueue};
+
+/// A table of frames that are transmitted periodically, eg. cyclic PDOs or heartbeats.
+///
+/// Each entry is a frame with a period, measured in calls to [`Scheduler::tick`]. Up to `N`
+/// entries can be stored. `tick` is meant to be called from a timer interrupt handler, and
+/// enqueues all frames that are due into a [`TxQueue`], which sends them in priority order.
+///
+/// ```no_run
+/// # use bxcan::{Instance, RegisterBlock};
+/// # struct CAN1;
+/// # unsafe impl Instance for CAN1 {
+/// #     const REGISTERS: *mut RegisterBlock = 0x4000_6400 as *mut _;
+/// # }
+/// use bxcan::{standard_id, Frame, Scheduler, TxQueue};
+///
+/// fn setup(scheduler: &mut Scheduler<4>) {
+///     // Heartbeat every 100 ticks, status frames every 10 ticks.
+///     scheduler
+///         .set(Frame::new_data(standard_id!(0x705), [0x05]), 100, 0)
+///         .unwrap();
+///     scheduler
+///         .set(Frame::new_data(standard_id!(0x185), [0; 4]), 10, 0)
+///         .unwrap();
+///     // Shifted by 5 ticks, so it is not sent in the same tick as 0x185.
+///     scheduler
+///         .set(Frame::new_data(standard_id!(0x285), [0; 4]), 10, 5)
+///         .unwrap();
+/// }
+///
+/// // Called from a timer interrupt handler every millisecond.
+/// fn timer(scheduler: &mut Scheduler<4>, queue: &mut TxQueue<CAN1, 8>) {
+///     scheduler.tick(queue);
+/// }
+/// ```
+///
+/// Frames are scheduled relative to the tick in which they were due, not the one in which they
+/// were enqueued, so delays do not accumulate. If a frame cannot be enqueued because the
+/// [`TxQueue`] is full, it is retried on every tick until it fits. Transmissions that were missed
+/// entirely are skipped instead of being sent in a burst.
+pub struct Scheduler<const N: usize> {
+    entries: Vec<Entry, N>,
+    now: u32,
+}
+
+struct Entry {
+    frame: Frame,
+    period: u32,
+    /// Tick in which the frame is sent next.
+    due: u32,
+}
+
+impl Entry {
+    fn is_due(&self, now: u32) -> bool {
+        // Ticks wrap around, so compare by distance.
+        (now.wrapping_sub(self.due) as i32) >= 0
+    }
+
+    /// Schedules the next transmission after the frame was enqueued in tick `now`.
+    fn reschedule(&mut self, now: u32) {
+        let late = now.wrapping_sub(self.due) % self.period;
+        self.due = now.wrapping_add(self.period - late);
+    }
+}
+
+impl<const N: usize> Scheduler<N> {
+    /// Creates an empty table.
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            now: 0,
+        }
+    }
+
+    /// Registers `frame` for transmission every `period` ticks.
+    ///
+    /// The frame is first enqueued `offset + 1` ticks from now, ie. an `offset` of 0 enqueues it on
+    /// the next call to [`Scheduler::tick`]. Giving frames of the same period different offsets
+    /// spreads the bus load.
+    ///
+    /// If there already is an entry for the identifier of `frame`, it is replaced and the previous
+    /// frame is returned. Returns `Err` with `frame` if the table is full.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if `period` is 0, or if `period` or `offset` is greater than `i32::MAX`.
+    /// Larger values could not be told apart from due times in the past.
+    pub fn set(&mut self, frame: Frame, period: u32, offset: u32) -> Result<Option<Frame>, Frame> {
+        assert!(period != 0, "period must not be 0");
+        assert!(period <= i32::MAX as u32, "period out of range");
+        assert!(offset <= i32::MAX as u32, "offset out of range");
+
+        let due = self.now.wrapping_add(1).wrapping_add(offset);
+        match self.entries.iter_mut().find(|e| e.frame.id() == frame.id()) {
+            Some(entry) => {
+                entry.period = period;
+                entry.due = due;
+                Ok(Some(core::mem::replace(&mut entry.frame, frame)))
+            }
+            None => self
+                .entries
+                .push(Entry { frame, period, due })
+                .map(|()| None)
+                .map_err(|entry| entry.frame),
+        }
+    }
+
+    /// Replaces the data of the frame registered for `id`, without changing its schedule.
+    ///
+    /// Returns `false` if there is no entry for `id`.
+    pub fn set_data(&mut self, id: impl Into<Id>, data: impl Into<Data>) -> bool {
+        let id = id.into();
+        match self.entries.iter_mut().find(|e| e.frame.id() == id) {
+            Some(entry) => {
+                entry.frame.set_data(data);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes the entry for `id`, returning its frame.
+    pub fn remove(&mut self, id: impl Into<Id>) -> Option<Frame> {
+        let id = id.into();
+        let index = self.entries.iter().position(|e| e.frame.id() == id)?;
+        Some(self.entries.swap_remove(index).frame)
+    }
+
+    /// Returns the frame registered for `id`.
+    pub fn get(&self, id: impl Into<Id>) -> Option<&Frame> {
+        let id = id.into();
+        self.entries.iter().map(|e| &e.frame).find(|f| f.id() == id)
+    }
+
+    /// Removes all entries.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Advances time by one tick and enqueues all frames that are due into `queue`.
+    ///
+    /// Returns the number of frames that were enqueued.
+    pub fn tick<I, const M: usize>(&mut self, queue: &mut TxQueue<I, M>) -> usize
+    where
+        I: Instance,
+    {
+        self.tick_with(|frame| queue.transmit(frame).is_ok())
+    }
+
+    /// Advances time by one tick and passes all due frames to `enqueue`, which returns whether the
+    /// frame was accepted.
+    fn tick_with(&mut self, mut enqueue: impl FnMut(&Frame) -> bool) -> usize {
+        self.now = self.now.wrapping_add(1);
+        let now = self.now;
+
+        let mut count = 0;
+        for entry in self.entries.iter_mut().filter(|e| e.is_due(now)) {
+            if enqueue(&entry.frame) {
+                entry.reschedule(now);
+                count += 1;
+            }
+        }
+        count
+    }
+}
+
+impl<const N: usize> Default for Scheduler<N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StandardId;
+
+    /// Runs `ticks` ticks and returns the identifier of each enqueued frame along with the tick,
+    /// counted from 1 in each call. The queue rejects all frames in the ticks listed in `full`.
+    fn run(scheduler: &mut Scheduler<2>, ticks: u32, full: &[u32]) -> Vec<(u16, u32), 32> {
+        let mut sent = Vec::new();
+        for tick in 1..=ticks {
+            scheduler.tick_with(|frame| {
+                if full.contains(&tick) {
+                    return false;
+                }
+                if let Id::Standard(id) = frame.id() {
+                    sent.push((id.as_raw(), tick)).unwrap();
+                }
+                true
+            });
+        }
+        sent
+    }
+
+    #[test]
+    fn periods_and_offsets() {
+        let mut scheduler = Scheduler::<2>::new();
+        let a = Frame::new_data(StandardId::new(0x1).unwrap(), []);
+        let b = Frame::new_data(StandardId::new(0x2).unwrap(), []);
+        assert_eq!(scheduler.set(a.clone(), 3, 0), Ok(None));
+        assert_eq!(scheduler.set(b.clone(), 4, 1), Ok(None));
+
+        let full = Frame::new_data(StandardId::ZERO, []);
+        assert_eq!(scheduler.set(full.clone(), 1, 0), Err(full));
+
+        assert_eq!(
+            &run(&mut scheduler, 9, &[])[..],
+            [(1, 1), (2, 2), (1, 4), (2, 6), (1, 7)]
+        );
+    }
+
+    #[test]
+    fn retries_without_drift() {
+        let mut scheduler = Scheduler::<2>::new();
+        let a = Frame::new_data(StandardId::new(0x1).unwrap(), []);
+        scheduler.set(a, 4, 0).unwrap();
+
+        // Delayed by one tick, then back on schedule.
+        assert_eq!(&run(&mut scheduler, 9, &[5])[..], [(1, 1), (1, 6), (1, 9)]);
+        // Due in tick 13, but the queue is full until tick 17, when the frame is due again. It is
+        // only sent once.
+        assert_eq!(
+            &run(&mut scheduler, 12, &[4, 5, 6, 7])[..],
+            [(1, 8), (1, 12)]
+        );
+    }
+
+    #[test]
+    fn entries() {
+        let mut scheduler = Scheduler::<2>::new();
+        let id = StandardId::new(0x100).unwrap();
+        let first = Frame::new_data(id, [1]);
+        let second = Frame::new_data(id, [2]);
+
+        scheduler.set(first.clone(), 10, 0).unwrap();
+        assert_eq!(scheduler.set(second.clone(), 10, 0), Ok(Some(first)));
+        assert!(scheduler.set_data(id, [3]));
+        assert_eq!(scheduler.get(id), Some(&Frame::new_data(id, [3])));
+        assert!(!scheduler.set_data(StandardId::ZERO, []));
+        assert!(scheduler.remove(id).is_some());
+        assert_eq!(scheduler.get(id), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "period out of range")]
+    fn period_out_of_range() {
+        let frame = Frame::new_data(StandardId::ZERO, []);
+        Scheduler::<1>::new()
+            .set(frame, i32::MAX as u32 + 1, 0)
+            .ok();
+    }
+}
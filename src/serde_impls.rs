@@ -0,0 +1,188 @@
+//! `serde` trait impls.
+//!
+//! Frames are serialized as an enum with a `Data` and a `Remote` variant, so that the
+//! representation can only describe valid frames:
+//!
+//! * `Data { id: Id, data: Data }`, where `data` is serialized as a byte string.
+//! * `Remote { id: Id, dlc: u8 }`.
+//!
+//! [`StandardId`] and [`ExtendedId`] are serialized as their raw integer value.
+
+use core::fmt;
+
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+
+use crate::{Data, ExtendedId, Frame, Id, StandardId};
+
+impl Serialize for StandardId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u16(self.as_raw())
+    }
+}
+
+impl<'de> Deserialize<'de> for StandardId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = u16::deserialize(deserializer)?;
+        StandardId::new(raw)
+            .ok_or_else(|| de::Error::custom(format_args!("standard ID {:#x} out of range", raw)))
+    }
+}
+
+impl Serialize for ExtendedId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(self.as_raw())
+    }
+}
+
+impl<'de> Deserialize<'de> for ExtendedId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = u32::deserialize(deserializer)?;
+        ExtendedId::new(raw)
+            .ok_or_else(|| de::Error::custom(format_args!("extended ID {:#x} out of range", raw)))
+    }
+}
+
+impl Serialize for Data {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Data {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct DataVisitor;
+
+        impl<'de> Visitor<'de> for DataVisitor {
+            type Value = Data;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("at most 8 bytes")
+            }
+
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Data, E> {
+                Data::new(v).ok_or_else(|| E::invalid_length(v.len(), &self))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Data, A::Error> {
+                let mut data = Data::empty();
+                while let Some(byte) = seq.next_element()? {
+                    if data.len() == 8 {
+                        return Err(de::Error::invalid_length(9, &self));
+                    }
+                    data.bytes[data.len()] = byte;
+                    data.len += 1;
+                }
+                Ok(data)
+            }
+        }
+
+        deserializer.deserialize_bytes(DataVisitor)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Frame")]
+enum FrameRepr {
+    Data { id: Id, data: Data },
+    Remote { id: Id, dlc: u8 },
+}
+
+impl Serialize for Frame {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let repr = match self.data() {
+            Some(data) => FrameRepr::Data {
+                id: self.id(),
+                data: *data,
+            },
+            None => FrameRepr::Remote {
+                id: self.id(),
+                dlc: self.dlc(),
+            },
+        };
+        repr.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Frame {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match FrameRepr::deserialize(deserializer)? {
+            FrameRepr::Data { id, data } => Ok(Frame::new_data(id, data)),
+            FrameRepr::Remote { id, dlc } if dlc <= 8 => Ok(Frame::new_remote(id, dlc)),
+            FrameRepr::Remote { dlc, .. } => Err(de::Error::invalid_value(
+                de::Unexpected::Unsigned(dlc.into()),
+                &"a DLC of at most 8",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_test::{assert_de_tokens_error, assert_tokens, Token};
+
+    #[test]
+    fn ids() {
+        assert_tokens(&StandardId::MAX, &[Token::U16(0x7FF)]);
+        assert_tokens(&ExtendedId::MAX, &[Token::U32(0x1FFF_FFFF)]);
+        assert_de_tokens_error::<StandardId>(
+            &[Token::U16(0x800)],
+            "standard ID 0x800 out of range",
+        );
+    }
+
+    #[test]
+    fn frames() {
+        let frame = Frame::new_data(StandardId::new(0x123).unwrap(), [1, 2]);
+        assert_tokens(
+            &frame,
+            &[
+                Token::StructVariant {
+                    name: "Frame",
+                    variant: "Data",
+                    len: 2,
+                },
+                Token::Str("id"),
+                Token::NewtypeVariant {
+                    name: "Id",
+                    variant: "Standard",
+                },
+                Token::U16(0x123),
+                Token::Str("data"),
+                Token::Bytes(&[1, 2]),
+                Token::StructVariantEnd,
+            ],
+        );
+
+        let frame = Frame::new_remote(ExtendedId::new(0x12345).unwrap(), 3);
+        assert_tokens(
+            &frame,
+            &[
+                Token::StructVariant {
+                    name: "Frame",
+                    variant: "Remote",
+                    len: 2,
+                },
+                Token::Str("id"),
+                Token::NewtypeVariant {
+                    name: "Id",
+                    variant: "Extended",
+                },
+                Token::U32(0x12345),
+                Token::Str("dlc"),
+                Token::U8(3),
+                Token::StructVariantEnd,
+            ],
+        );
+    }
+
+    #[test]
+    fn invalid_data() {
+        assert_de_tokens_error::<Data>(
+            &[Token::Bytes(&[0; 9])],
+            "invalid length 9, expected at most 8 bytes",
+        );
+    }
+}
@@ -0,0 +1,121 @@
+//! Transmit half shared between interrupt handlers.
+
+use core::cell::RefCell;
+use core::convert::Infallible;
+
+use critical_section::Mutex;
+
+use crate::{Frame, Instance, Mailbox, TransmitStatus, Tx};
+
+#[allow(unused_imports)] // for intra-doc links only
+use crate::Can;
+
+/// A transmit half that can be used through a shared reference from multiple contexts.
+///
+/// Every access to the wrapped [`Tx`] half runs in a critical section of the
+/// [`critical-section`] crate, so a `static SharedCan` can be used from any number of interrupt
+/// handlers (or threads) to transmit frames. The receive halves returned by [`Can::split`] stay
+/// exclusive, and are typically moved to the **RX FIFO** interrupt handlers.
+///
+/// ```no_run
+/// # use bxcan::{Instance, RegisterBlock};
+/// # struct CAN1;
+/// # unsafe impl Instance for CAN1 {
+/// #     const REGISTERS: *mut RegisterBlock = 0x4000_6400 as *mut _;
+/// # }
+/// use bxcan::{Can, Frame, SharedCan, StandardId};
+///
+/// static CAN1_TX: SharedCan<CAN1> = SharedCan::new();
+///
+/// fn init(can: Can<CAN1>) {
+///     let (tx, rx0, rx1) = can.split();
+///     CAN1_TX.init(tx).ok();
+///     // Move `rx0` and `rx1` to the RX FIFO interrupt handlers.
+/// }
+///
+/// // Called from any interrupt handler.
+/// fn send_heartbeat() {
+///     let frame = Frame::new_data(StandardId::new(0x700).unwrap(), [0x05]);
+///     CAN1_TX.transmit(&frame).ok();
+/// }
+/// ```
+///
+/// [`critical-section`]: https://docs.rs/critical-section
+pub struct SharedCan<I> {
+    tx: Mutex<RefCell<Option<Tx<I>>>>,
+}
+
+impl<I: Instance> SharedCan<I> {
+    /// Creates an empty wrapper, which has to be initialized with [`SharedCan::init`].
+    pub const fn new() -> Self {
+        Self {
+            tx: Mutex::new(RefCell::new(None)),
+        }
+    }
+
+    /// Moves the transmit half into the wrapper.
+    ///
+    /// If the wrapper already holds a transmit half, `tx` is returned in `Err`.
+    pub fn init(&self, tx: Tx<I>) -> Result<(), Tx<I>> {
+        critical_section::with(|cs| {
+            let mut slot = self.tx.borrow_ref_mut(cs);
+            if slot.is_some() {
+                return Err(tx);
+            }
+            *slot = Some(tx);
+            Ok(())
+        })
+    }
+
+    /// Removes the transmit half from the wrapper again.
+    ///
+    /// Returns `None` if the wrapper has not been initialized.
+    pub fn take(&self) -> Option<Tx<I>> {
+        critical_section::with(|cs| self.tx.borrow_ref_mut(cs).take())
+    }
+
+    /// Runs `f` with exclusive access to the transmit half, inside a critical section.
+    ///
+    /// Returns `None` if the wrapper has not been initialized.
+    pub fn with<R>(&self, f: impl FnOnce(&mut Tx<I>) -> R) -> Option<R> {
+        critical_section::with(|cs| self.tx.borrow_ref_mut(cs).as_mut().map(f))
+    }
+
+    /// Puts a CAN frame in a free transmit mailbox.
+    ///
+    /// See [`Tx::transmit`] for details.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if the wrapper has not been initialized.
+    pub fn transmit(&self, frame: &Frame) -> nb::Result<TransmitStatus, Infallible> {
+        self.with(|tx| tx.transmit(frame))
+            .expect("`SharedCan` not initialized")
+    }
+
+    /// Attempts to abort the sending of a frame that is pending in a mailbox.
+    ///
+    /// See [`Tx::abort`] for details.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if the wrapper has not been initialized.
+    pub fn abort(&self, mailbox: Mailbox) -> bool {
+        self.with(|tx| tx.abort(mailbox))
+            .expect("`SharedCan` not initialized")
+    }
+
+    /// Returns `true` if no frame is pending for transmission.
+    ///
+    /// Returns `true` as well if the wrapper has not been initialized.
+    pub fn is_idle(&self) -> bool {
+        self.with(|tx| tx.is_idle()).unwrap_or(true)
+    }
+}
+
+impl<I: Instance> Default for SharedCan<I> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,107 @@
+//! Shutting down the peripheral when it is dropped.
+
+use core::mem::ManuallyDrop;
+use core::ops::{Deref, DerefMut};
+
+use crate::{Can, Instance, Interrupts, Mailbox};
+
+/// The state a [`ShutdownOnDrop`] leaves the peripheral in.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub enum ShutdownMode {
+    /// Sleep mode, see [`Can::sleep`].
+    ///
+    /// If automatic wakeup is enabled, bus activity wakes the peripheral up again.
+    Sleep,
+    /// Initialization mode, in which the peripheral does not take part in bus communication until
+    /// it is configured again.
+    Init,
+}
+
+/// A [`Can`] instance that shuts down the peripheral when dropped.
+///
+/// Dropping a plain [`Can`] leaves the peripheral running, so frames still pending in the transmit
+/// mailboxes keep being retransmitted, and enabled interrupts keep firing. When this wrapper is
+/// dropped, it aborts all pending transmissions, disables all interrupts and enters the configured
+/// [`ShutdownMode`].
+///
+/// It dereferences to the wrapped [`Can`]. [`ShutdownOnDrop::into_inner`] returns the instance
+/// without shutting it down.
+///
+/// ```no_run
+/// # use bxcan::{Instance, RegisterBlock};
+/// # struct CAN1;
+/// # unsafe impl Instance for CAN1 {
+/// #     const REGISTERS: *mut RegisterBlock = 0x4000_6400 as *mut _;
+/// # }
+/// use bxcan::{Can, Frame, ShutdownMode, StandardId};
+///
+/// fn send(can: Can<CAN1>) {
+///     let mut can = can.shutdown_on_drop(ShutdownMode::Sleep);
+///     let frame = Frame::new_data(StandardId::new(0x123).unwrap(), [1, 2, 3]);
+///     can.transmit(&frame).ok();
+///     // `frame` is aborted if it is still pending here.
+/// }
+/// ```
+pub struct ShutdownOnDrop<I: Instance> {
+    can: ManuallyDrop<Can<I>>,
+    mode: ShutdownMode,
+}
+
+impl<I: Instance> ShutdownOnDrop<I> {
+    /// Wraps `can`, which will enter `mode` when dropped.
+    pub fn new(can: Can<I>, mode: ShutdownMode) -> Self {
+        Self {
+            can: ManuallyDrop::new(can),
+            mode,
+        }
+    }
+
+    /// Returns the wrapped instance without shutting it down.
+    pub fn into_inner(self) -> Can<I> {
+        let mut this = ManuallyDrop::new(self);
+        // Safety: `this` is never used or dropped again.
+        unsafe { ManuallyDrop::take(&mut this.can) }
+    }
+}
+
+impl<I: Instance> Deref for ShutdownOnDrop<I> {
+    type Target = Can<I>;
+
+    #[inline]
+    fn deref(&self) -> &Can<I> {
+        &self.can
+    }
+}
+
+impl<I: Instance> DerefMut for ShutdownOnDrop<I> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Can<I> {
+        &mut self.can
+    }
+}
+
+impl<I: Instance> Drop for ShutdownOnDrop<I> {
+    fn drop(&mut self) {
+        let can = &mut *self.can;
+        for mailbox in [Mailbox::Mailbox0, Mailbox::Mailbox1, Mailbox::Mailbox2] {
+            can.abort(mailbox);
+        }
+        can.disable_interrupts(Interrupts::all());
+        match self.mode {
+            ShutdownMode::Sleep => can.sleep(),
+            ShutdownMode::Init => can.enter_init_mode(),
+        }
+
+        // Safety: `self.can` is not used after this.
+        unsafe { ManuallyDrop::drop(&mut self.can) }
+    }
+}
+
+impl<I: Instance> Can<I> {
+    /// Wraps this instance in a [`ShutdownOnDrop`], which shuts down the peripheral when dropped.
+    #[inline]
+    pub fn shutdown_on_drop(self, mode: ShutdownMode) -> ShutdownOnDrop<I> {
+        ShutdownOnDrop::new(self, mode)
+    }
+}
@@ -0,0 +1,168 @@
+//! SLCAN (Lawicel) ASCII encoding of frames.
+//!
+//! SLCAN is a simple line-based protocol spoken by many USB-serial CAN adapters, and supported by
+//! the Linux `slcand` daemon. Each frame is encoded as a single command terminated by `\r`:
+//!
+//! | Frame                 | Format            |
+//! |-----------------------|-------------------|
+//! | Standard data frame   | `tIIILDD...`      |
+//! | Extended data frame   | `TIIIIIIIILDD...` |
+//! | Standard remote frame | `rIIIL`           |
+//! | Extended remote frame | `RIIIIIIIIL`      |
+//!
+//! `I` are the hexadecimal digits of the identifier, `L` is the DLC and `DD` are the `L` data bytes
+//! in hexadecimal.
+//!
+//! ```
+//! use bxcan::{slcan, Frame, StandardId};
+//!
+//! let frame = Frame::new_data(StandardId::new(0x123).unwrap(), [0xAB, 0xCD]);
+//! let mut buf = [0; slcan::MAX_ENCODED_LEN];
+//! let encoded = slcan::encode(&frame, &mut buf);
+//! assert_eq!(encoded, b"t1232ABCD\r");
+//! assert_eq!(slcan::decode(encoded), Some(frame));
+//! ```
+
+use crate::{Data, ExtendedId, Frame, Id, StandardId};
+
+/// The maximum length of an encoded frame, including the terminating `\r`.
+pub const MAX_ENCODED_LEN: usize = 1 + 8 + 1 + 16 + 1;
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+
+/// Encodes `frame` into `buf` and returns the encoded command, including the terminating `\r`.
+pub fn encode<'a>(frame: &Frame, buf: &'a mut [u8; MAX_ENCODED_LEN]) -> &'a [u8] {
+    let (command, id, id_digits) = match (frame.id(), frame.is_remote_frame()) {
+        (Id::Standard(id), false) => (b't', u32::from(id.as_raw()), 3),
+        (Id::Extended(id), false) => (b'T', id.as_raw(), 8),
+        (Id::Standard(id), true) => (b'r', u32::from(id.as_raw()), 3),
+        (Id::Extended(id), true) => (b'R', id.as_raw(), 8),
+    };
+
+    buf[0] = command;
+    let mut len = 1;
+    for shift in (0..id_digits).rev() {
+        buf[len] = HEX_DIGITS[(id >> (shift * 4)) as usize & 0xF];
+        len += 1;
+    }
+    buf[len] = HEX_DIGITS[usize::from(frame.dlc())];
+    len += 1;
+    for &byte in frame.data().map_or(&[][..], |data| data) {
+        buf[len] = HEX_DIGITS[usize::from(byte >> 4)];
+        buf[len + 1] = HEX_DIGITS[usize::from(byte & 0xF)];
+        len += 2;
+    }
+    buf[len] = b'\r';
+    len += 1;
+
+    &buf[..len]
+}
+
+/// Decodes a frame from an SLCAN transmit command.
+///
+/// The terminating `\r` is optional, and hexadecimal digits may be upper- or lowercase.
+///
+/// Returns `None` if `command` is not a well-formed `t`, `T`, `r` or `R` command.
+pub fn decode(command: &[u8]) -> Option<Frame> {
+    let command = command.strip_suffix(b"\r").unwrap_or(command);
+    let (&kind, rest) = command.split_first()?;
+    let id_digits = match kind {
+        b't' | b'r' => 3,
+        b'T' | b'R' => 8,
+        _ => return None,
+    };
+    if rest.len() < id_digits + 1 {
+        return None;
+    }
+    let (id, rest) = rest.split_at(id_digits);
+    let id = parse_hex(id)?;
+    let id: Id = if id_digits == 3 {
+        StandardId::new(id as u16)?.into()
+    } else {
+        ExtendedId::new(id)?.into()
+    };
+
+    let (dlc, data) = rest.split_first()?;
+    let dlc = parse_hex(core::slice::from_ref(dlc))? as u8;
+    if dlc > 8 {
+        return None;
+    }
+
+    if kind == b'r' || kind == b'R' {
+        if !data.is_empty() {
+            return None;
+        }
+        return Some(Frame::new_remote(id, dlc));
+    }
+
+    if data.len() != usize::from(dlc) * 2 {
+        return None;
+    }
+    let mut bytes = [0; 8];
+    for (byte, digits) in bytes.iter_mut().zip(data.chunks(2)) {
+        *byte = parse_hex(digits)? as u8;
+    }
+    Some(Frame::new_data(id, Data::new(&bytes[..usize::from(dlc)])?))
+}
+
+fn parse_hex(digits: &[u8]) -> Option<u32> {
+    digits.iter().try_fold(0, |value, &digit| {
+        let nibble = (digit as char).to_digit(16)?;
+        Some(value << 4 | nibble)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(frame: Frame, expected: &[u8]) {
+        let mut buf = [0; MAX_ENCODED_LEN];
+        assert_eq!(encode(&frame, &mut buf), expected);
+        assert_eq!(decode(expected), Some(frame));
+    }
+
+    #[test]
+    fn encode_decode() {
+        roundtrip(Frame::new_data(StandardId::ZERO, []), b"t0000\r");
+        roundtrip(
+            Frame::new_data(
+                StandardId::MAX,
+                [0x01, 0x23, 0x45, 0x67, 0x89, 0xAB, 0xCD, 0xEF],
+            ),
+            b"t7FF80123456789ABCDEF\r",
+        );
+        roundtrip(Frame::new_data(ExtendedId::MAX, [0xFF]), b"T1FFFFFFF1FF\r");
+        roundtrip(
+            Frame::new_remote(StandardId::new(0x42).unwrap(), 4),
+            b"r0424\r",
+        );
+        roundtrip(
+            Frame::new_remote(ExtendedId::new(0x42).unwrap(), 8),
+            b"R000000428\r",
+        );
+    }
+
+    #[test]
+    fn decode_lenient() {
+        let frame = Frame::new_data(StandardId::new(0x2BC).unwrap(), [0xEF]);
+        assert_eq!(decode(b"t2bc1ef"), Some(frame));
+    }
+
+    #[test]
+    fn decode_invalid() {
+        assert_eq!(decode(b""), None);
+        assert_eq!(decode(b"x1230\r"), None);
+        // Identifier out of range.
+        assert_eq!(decode(b"t8000\r"), None);
+        assert_eq!(decode(b"T200000000\r"), None);
+        // DLC out of range, or not matching the data.
+        assert_eq!(decode(b"t1239\r"), None);
+        assert_eq!(decode(b"t1232AB\r"), None);
+        assert_eq!(decode(b"t1231ABCD\r"), None);
+        assert_eq!(decode(b"r1232AB\r"), None);
+        // Invalid hex digits.
+        assert_eq!(decode(b"t12G0\r"), None);
+        assert_eq!(decode(b"t1231+1\r"), None);
+    }
+}
@@ -0,0 +1,133 @@
+//! Statistics counters.
+
+use crate::Lec;
+
+#[allow(unused_imports)] // for intra-doc links only
+use crate::{Can, Interrupt};
+
+/// Counters of the events observed by a [`Can`] instance.
+///
+/// The counters are updated by the methods of [`Can`] that handle the corresponding events, so
+/// only events handled through the `Can` instance are counted, and not those handled by the halves
+/// returned by [`Can::split`]. All counters wrap around at `u32::MAX`.
+///
+/// Returned by [`Can::stats`].
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub struct Stats {
+    frames_received: u32,
+    frames_transmitted: u32,
+    rx_overruns: u32,
+    tx_aborts: u32,
+    arbitration_losses: u32,
+    bus_off_events: u32,
+    errors: [u32; 6],
+}
+
+impl Stats {
+    /// Returns the number of frames returned by [`Can::receive`] and related methods.
+    #[inline]
+    pub fn frames_received(&self) -> u32 {
+        self.frames_received
+    }
+
+    /// Returns the number of successfully transmitted frames.
+    ///
+    /// Transmissions are counted when their completion is acknowledged via
+    /// [`Can::clear_request_completed_flag`] or [`Can::clear_tx_interrupt`].
+    #[inline]
+    pub fn frames_transmitted(&self) -> u32 {
+        self.frames_transmitted
+    }
+
+    /// Returns the number of receive FIFO overruns reported by [`Can::receive`] and related
+    /// methods.
+    #[inline]
+    pub fn rx_overruns(&self) -> u32 {
+        self.rx_overruns
+    }
+
+    /// Returns the number of transmissions canceled by [`Can::abort`].
+    #[inline]
+    pub fn tx_aborts(&self) -> u32 {
+        self.tx_aborts
+    }
+
+    /// Returns the number of transmissions that lost arbitration.
+    ///
+    /// Like [`Stats::frames_transmitted`], these are counted when the completion of the
+    /// transmission request is acknowledged.
+    #[inline]
+    pub fn arbitration_losses(&self) -> u32 {
+        self.arbitration_losses
+    }
+
+    /// Returns how often the peripheral entered the bus-off state, as detected by
+    /// [`Can::on_error_state_change`].
+    #[inline]
+    pub fn bus_off_events(&self) -> u32 {
+        self.bus_off_events
+    }
+
+    /// Returns the number of bus errors of the given type.
+    ///
+    /// Errors are counted when they are cleared via [`Can::clear_last_error_code`], which should
+    /// be called from the **SCE** interrupt handler when [`Interrupt::LastErrorCode`] is enabled.
+    /// [`Lec::SoftwareSet`] is never counted.
+    #[inline]
+    pub fn errors(&self, lec: Lec) -> u32 {
+        match lec {
+            Lec::SoftwareSet => 0,
+            lec => self.errors[lec as usize],
+        }
+    }
+
+    pub(crate) fn count_received(&mut self) {
+        self.frames_received = self.frames_received.wrapping_add(1);
+    }
+
+    pub(crate) fn count_transmitted(&mut self) {
+        self.frames_transmitted = self.frames_transmitted.wrapping_add(1);
+    }
+
+    pub(crate) fn count_rx_overrun(&mut self) {
+        self.rx_overruns = self.rx_overruns.wrapping_add(1);
+    }
+
+    pub(crate) fn count_tx_abort(&mut self) {
+        self.tx_aborts = self.tx_aborts.wrapping_add(1);
+    }
+
+    pub(crate) fn count_arbitration_loss(&mut self) {
+        self.arbitration_losses = self.arbitration_losses.wrapping_add(1);
+    }
+
+    pub(crate) fn count_bus_off(&mut self) {
+        self.bus_off_events = self.bus_off_events.wrapping_add(1);
+    }
+
+    pub(crate) fn count_error(&mut self, lec: Lec) {
+        if let Some(count) = self.errors.get_mut(lec as usize) {
+            *count = count.wrapping_add(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_by_lec() {
+        let mut stats = Stats::default();
+        stats.count_error(Lec::Stuff);
+        stats.count_error(Lec::Crc);
+        stats.count_error(Lec::Crc);
+        stats.count_error(Lec::SoftwareSet);
+
+        assert_eq!(stats.errors(Lec::Stuff), 1);
+        assert_eq!(stats.errors(Lec::Form), 0);
+        assert_eq!(stats.errors(Lec::Crc), 2);
+        assert_eq!(stats.errors(Lec::SoftwareSet), 0);
+    }
+}
@@ -0,0 +1,176 @@
+//! Protection against interrupt storms.
+
+use crate::{Interrupt, Interrupts};
+
+/// The interrupt sources that are counted, in the order of [`StormGuard::counts`].
+const SOURCES: [Interrupt; 3] = [
+    Interrupt::Fifo0Overrun,
+    Interrupt::Fifo1Overrun,
+    Interrupt::LastErrorCode,
+];
+
+/// Configuration of the interrupt storm protection, see [`Can::set_storm_protection`].
+///
+/// [`Interrupt::Fifo0Overrun`], [`Interrupt::Fifo1Overrun`] and [`Interrupt::LastErrorCode`] can
+/// fire continuously on a flooded or faulty bus. When one of them is reported more than `limit`
+/// times between two calls to [`Can::tick`], it is disabled for `backoff` ticks.
+///
+/// [`Can::set_storm_protection`]: crate::Can::set_storm_protection
+/// [`Can::tick`]: crate::Can::tick
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub struct StormProtection {
+    limit: u16,
+    backoff: u16,
+}
+
+impl StormProtection {
+    /// Creates a configuration that disables an interrupt source for `backoff` ticks after it
+    /// fired more than `limit` times within a tick.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if `backoff` is 0.
+    pub const fn new(limit: u16, backoff: u16) -> Self {
+        assert!(backoff != 0, "backoff must not be 0");
+        Self { limit, backoff }
+    }
+
+    /// Returns the number of events per tick that are tolerated.
+    #[inline]
+    pub fn limit(&self) -> u16 {
+        self.limit
+    }
+
+    /// Returns the number of ticks an interrupt source stays disabled.
+    #[inline]
+    pub fn backoff(&self) -> u16 {
+        self.backoff
+    }
+}
+
+/// State of the interrupt storm protection of a [`Can`](crate::Can) instance.
+#[derive(Default)]
+pub(crate) struct StormGuard {
+    protection: Option<StormProtection>,
+    /// Events of each source since the last tick.
+    counts: [u16; 3],
+    /// Remaining ticks until each source is enabled again, 0 if it is not suspended.
+    backoff: [u16; 3],
+}
+
+impl StormGuard {
+    /// Replaces the configuration, and returns the sources that were suspended.
+    pub(crate) fn set_protection(&mut self, protection: Option<StormProtection>) -> Interrupts {
+        let suspended = self.suspended();
+        *self = Self {
+            protection,
+            ..Self::default()
+        };
+        suspended
+    }
+
+    /// Counts an event of `source`, and returns `true` if the source has to be suspended.
+    pub(crate) fn record(&mut self, source: Interrupt) -> bool {
+        let protection = match self.protection {
+            Some(protection) => protection,
+            None => return false,
+        };
+        let index = match SOURCES.iter().position(|&s| s == source) {
+            Some(index) => index,
+            None => return false,
+        };
+        if self.backoff[index] != 0 {
+            // Already suspended.
+            return false;
+        }
+
+        self.counts[index] = self.counts[index].saturating_add(1);
+        if self.counts[index] > protection.limit {
+            self.backoff[index] = protection.backoff;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Starts a new tick, and returns the sources whose backoff has expired.
+    pub(crate) fn tick(&mut self) -> Interrupts {
+        let mut expired = Interrupts::empty();
+        self.counts = [0; 3];
+        for (backoff, &source) in self.backoff.iter_mut().zip(&SOURCES) {
+            if *backoff != 0 {
+                *backoff -= 1;
+                if *backoff == 0 {
+                    expired |= source;
+                }
+            }
+        }
+        expired
+    }
+
+    /// Returns the sources that are currently suspended.
+    pub(crate) fn suspended(&self) -> Interrupts {
+        let mut suspended = Interrupts::empty();
+        for (&backoff, &source) in self.backoff.iter().zip(&SOURCES) {
+            if backoff != 0 {
+                suspended |= source;
+            }
+        }
+        suspended
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled() {
+        let mut guard = StormGuard::default();
+        for _ in 0..1000 {
+            assert!(!guard.record(Interrupt::Fifo0Overrun));
+        }
+        assert_eq!(guard.suspended(), Interrupts::empty());
+    }
+
+    #[test]
+    fn suspend_and_resume() {
+        let mut guard = StormGuard::default();
+        guard.set_protection(Some(StormProtection::new(2, 2)));
+
+        assert!(!guard.record(Interrupt::LastErrorCode));
+        assert!(!guard.record(Interrupt::LastErrorCode));
+        // Other sources are counted separately.
+        assert!(!guard.record(Interrupt::Fifo1Overrun));
+        assert!(guard.record(Interrupt::LastErrorCode));
+        assert!(!guard.record(Interrupt::LastErrorCode));
+        assert_eq!(guard.suspended(), Interrupts::LAST_ERROR_CODE);
+
+        assert_eq!(guard.tick(), Interrupts::empty());
+        assert_eq!(guard.suspended(), Interrupts::LAST_ERROR_CODE);
+        assert_eq!(guard.tick(), Interrupts::LAST_ERROR_CODE);
+        assert_eq!(guard.suspended(), Interrupts::empty());
+
+        // Counts are reset every tick.
+        for _ in 0..3 {
+            assert!(!guard.record(Interrupt::Fifo1Overrun));
+            assert!(!guard.record(Interrupt::Fifo1Overrun));
+            guard.tick();
+        }
+        assert!(!guard.record(Interrupt::Sleep));
+    }
+
+    #[test]
+    fn set_protection() {
+        let mut guard = StormGuard::default();
+        guard.set_protection(Some(StormProtection::new(0, 10)));
+        assert!(guard.record(Interrupt::Fifo0Overrun));
+        assert!(guard.record(Interrupt::Fifo1Overrun));
+        assert_eq!(
+            guard.set_protection(None),
+            Interrupts::FIFO0_OVERRUN | Interrupts::FIFO1_OVERRUN
+        );
+        assert_eq!(guard.suspended(), Interrupts::empty());
+    }
+}
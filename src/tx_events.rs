@@ -0,0 +1,260 @@
+//! Lock-free queue of transmit completions.
+
+use crate::counting_queue::{CountingConsumer, CountingProducer, CountingQueue};
+use crate::{Mailbox, TxEvent};
+
+#[allow(unused_imports)] // for intra-doc links only
+use crate::{Can, CanConfig, TxQueue};
+
+/// A completed transmission request, returned by [`Can::on_tx_interrupt_timestamped`].
+///
+/// A [`TxQueue`] also reports frames that were dropped because their deadline passed, see
+/// [`TxCompletion::is_expired`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub struct TxCompletion {
+    /// `None` if the frame expired in a `TxQueue`.
+    event: Option<TxEvent>,
+    timestamp: u16,
+    cookie: u32,
+}
+
+impl TxCompletion {
+    /// Creates a completion record from its parts.
+    #[inline]
+    pub const fn new(event: TxEvent, timestamp: u16, cookie: u32) -> Self {
+        Self {
+            event: Some(event),
+            timestamp,
+            cookie,
+        }
+    }
+
+    /// Creates a completion record for a frame that was dropped from a [`TxQueue`] because its
+    /// deadline passed.
+    #[inline]
+    pub const fn expired(cookie: u32) -> Self {
+        Self {
+            event: None,
+            timestamp: 0,
+            cookie,
+        }
+    }
+
+    /// Returns how the transmission request was completed.
+    ///
+    /// Returns `None` if the frame expired before it was put into a mailbox.
+    #[inline]
+    pub fn event(&self) -> Option<TxEvent> {
+        self.event
+    }
+
+    /// Returns the mailbox that held the frame.
+    ///
+    /// Returns `None` if the frame expired before it was put into a mailbox.
+    #[inline]
+    pub fn mailbox(&self) -> Option<Mailbox> {
+        self.event.map(|event| event.mailbox())
+    }
+
+    /// Returns `true` if the frame was transmitted successfully.
+    #[inline]
+    pub fn is_transmitted(&self) -> bool {
+        matches!(self.event, Some(TxEvent::Transmitted(_)))
+    }
+
+    /// Returns `true` if the frame was dropped from a [`TxQueue`] because its deadline passed,
+    /// see [`TxQueue::transmit_with_deadline`].
+    #[inline]
+    pub fn is_expired(&self) -> bool {
+        self.event.is_none()
+    }
+
+    /// Returns the value of the internal 16-bit timer at the start of frame.
+    ///
+    /// This is only meaningful for transmitted frames, and only if time triggered communication
+    /// mode is enabled (see [`CanConfig::set_time_triggered_communication_mode`]).
+    #[inline]
+    pub fn timestamp(&self) -> u16 {
+        self.timestamp
+    }
+
+    /// Returns the cookie that was passed to [`Can::transmit_with_cookie`] or
+    /// [`TxQueue::transmit_with_deadline`] with the frame.
+    ///
+    /// This is 0 for frames that were enqueued without a cookie.
+    #[inline]
+    pub fn cookie(&self) -> u32 {
+        self.cookie
+    }
+}
+
+/// A queue of completed transmissions, to be filled from the **TX** interrupt handler and drained
+/// from the main loop.
+///
+/// This works like an [`RxRingBuffer`][crate::RxRingBuffer]: it is split into a
+/// [`TxCompletionProducer`] used by the interrupt handler and a [`TxCompletionConsumer`] used by
+/// the application, which can be used concurrently without a critical section.
+///
+/// The queue holds up to `N - 1` completions. Completions that arrive while it is full are
+/// dropped and counted, see [`TxCompletionConsumer::overflows`].
+///
+/// ```no_run
+/// # use bxcan::{Instance, RegisterBlock};
+/// # struct CAN1;
+/// # unsafe impl Instance for CAN1 {
+/// #     const REGISTERS: *mut RegisterBlock = 0x4000_6400 as *mut _;
+/// # }
+/// use bxcan::{Can, TxCompletionProducer, TxCompletionQueue};
+///
+/// static mut COMPLETIONS: TxCompletionQueue<8> = TxCompletionQueue::new();
+///
+/// // Called from the TX interrupt handler.
+/// fn can1_tx(can: &mut Can<CAN1>, producer: &mut TxCompletionProducer<'static, 8>) {
+///     producer.fill(|| can.on_tx_interrupt_timestamped());
+/// }
+///
+/// # fn main() {
+/// // Safety: `COMPLETIONS` is only accessed here, once.
+/// let (mut producer, mut consumer) = unsafe { (*core::ptr::addr_of_mut!(COMPLETIONS)).split() };
+/// // Move `producer` to the interrupt handler, then:
+/// loop {
+///     while let Some(completion) = consumer.pop() {
+///         if !completion.is_transmitted() {
+///             // The frame with `completion.cookie()` was not sent.
+///         }
+///     }
+/// }
+/// # }
+/// ```
+pub struct TxCompletionQueue<const N: usize> {
+    queue: CountingQueue<TxCompletion, N>,
+}
+
+impl<const N: usize> TxCompletionQueue<N> {
+    /// Creates an empty queue.
+    pub const fn new() -> Self {
+        Self {
+            queue: CountingQueue::new(),
+        }
+    }
+
+    /// Splits the queue into its producer and consumer halves.
+    pub fn split(&mut self) -> (TxCompletionProducer<'_, N>, TxCompletionConsumer<'_, N>) {
+        let (producer, consumer) = self.queue.split();
+        (
+            TxCompletionProducer { producer },
+            TxCompletionConsumer { consumer },
+        )
+    }
+}
+
+impl<const N: usize> Default for TxCompletionQueue<N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The producer half of a [`TxCompletionQueue`], used by the interrupt handler.
+pub struct TxCompletionProducer<'a, const N: usize> {
+    producer: CountingProducer<'a, TxCompletion, N>,
+}
+
+impl<const N: usize> TxCompletionProducer<'_, N> {
+    /// Adds a completion to the queue.
+    ///
+    /// If the queue is full, the completion is returned in `Err` and counted as an overflow.
+    pub fn push(&mut self, completion: TxCompletion) -> Result<(), TxCompletion> {
+        self.producer.push(completion)
+    }
+
+    /// Moves all completions returned by `next` into the queue, until it returns `None`.
+    ///
+    /// `next` is typically a closure calling [`Can::on_tx_interrupt_timestamped`], which
+    /// acknowledges the TX interrupt once it returns `None`. Completions that do not fit into the
+    /// queue are dropped and counted as overflows.
+    pub fn fill(&mut self, mut next: impl FnMut() -> Option<TxCompletion>) {
+        while let Some(completion) = next() {
+            self.push(completion).ok();
+        }
+    }
+
+    /// Returns `true` if there is space for another completion in the queue.
+    #[inline]
+    pub fn ready(&self) -> bool {
+        self.producer.ready()
+    }
+}
+
+/// The consumer half of a [`TxCompletionQueue`], used by the application.
+pub struct TxCompletionConsumer<'a, const N: usize> {
+    consumer: CountingConsumer<'a, TxCompletion, N>,
+}
+
+impl<const N: usize> TxCompletionConsumer<'_, N> {
+    /// Removes the oldest completion from the queue.
+    #[inline]
+    pub fn pop(&mut self) -> Option<TxCompletion> {
+        self.consumer.pop()
+    }
+
+    /// Returns the number of completions in the queue.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.consumer.len()
+    }
+
+    /// Returns `true` if the queue contains no completions.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.consumer.is_empty()
+    }
+
+    /// Returns the total number of completions that were dropped because the queue was full.
+    ///
+    /// The counter wraps around at `u32::MAX`.
+    pub fn overflows(&self) -> u32 {
+        self.consumer.overflows()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fill_and_overflow() {
+        let mut queue = TxCompletionQueue::<3>::new();
+        let (mut producer, mut consumer) = queue.split();
+        let completions = [
+            TxCompletion::new(TxEvent::Transmitted(Mailbox::Mailbox1), 100, 7),
+            TxCompletion::new(TxEvent::Aborted(Mailbox::Mailbox0), 0, 0),
+            TxCompletion::new(TxEvent::Error(Mailbox::Mailbox2), 0, 0),
+        ];
+
+        let mut next = completions.iter().copied();
+        producer.fill(|| next.next());
+        assert!(!producer.ready());
+        assert_eq!(consumer.len(), 2);
+        assert_eq!(consumer.overflows(), 1);
+
+        let first = consumer.pop().unwrap();
+        assert!(first.is_transmitted());
+        assert_eq!(first.mailbox(), Some(Mailbox::Mailbox1));
+        assert_eq!(first.timestamp(), 100);
+        assert_eq!(first.cookie(), 7);
+        assert_eq!(consumer.pop(), Some(completions[1]));
+        assert_eq!(consumer.pop(), None);
+        assert!(consumer.is_empty());
+
+        producer.push(TxCompletion::expired(3)).unwrap();
+        let expired = consumer.pop().unwrap();
+        assert!(expired.is_expired());
+        assert!(!expired.is_transmitted());
+        assert_eq!(expired.event(), None);
+        assert_eq!(expired.mailbox(), None);
+        assert_eq!(expired.cookie(), 3);
+        assert!(consumer.is_empty());
+    }
+}
@@ -0,0 +1,598 @@
+//! Software transmit queue.
+
+use core::cmp::Reverse;
+use core::convert::Infallible;
+use core::mem;
+
+use heapless::Vec;
+
+use crate::{
+    Frame, FramePriority, Instance, ReplacementPolicy, Tx, TxCompletion, TxCompletionProducer,
+};
+
+/// The order in which a [`TxQueue`] moves frames from its backlog into the transmit mailboxes.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub enum BacklogOrdering {
+    /// Frames are sent in order of their [`FramePriority`], like on the bus.
+    ///
+    /// This is the default.
+    Priority,
+    /// Frames are sent in order of their deadline, see [`TxQueue::transmit_with_deadline`].
+    ///
+    /// Frames with the same deadline are sent in order of their priority, and frames without a
+    /// deadline are sent last. Frames in the transmit mailboxes are never replaced by more urgent
+    /// ones, since the mailboxes are arbitrated by priority.
+    EarliestDeadline,
+}
+
+/// A crossing of a watermark of the [`TxQueue`] backlog, see [`TxQueue::set_watermarks`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "unstable-defmt", derive(defmt::Format))]
+pub enum Watermark {
+    /// The backlog has filled up to the high watermark. Frame production should be throttled.
+    High,
+    /// The backlog has drained to the low watermark after reaching the high watermark.
+    Low,
+}
+
+/// Watermark configuration and state of a [`TxQueue`].
+struct Watermarks {
+    low: usize,
+    high: usize,
+    congested: bool,
+    event: Option<Watermark>,
+}
+
+impl Watermarks {
+    /// Updates the state for a backlog of length `len`.
+    fn update(&mut self, len: usize) {
+        if !self.congested && len >= self.high {
+            self.congested = true;
+            self.event = Some(Watermark::High);
+        } else if self.congested && len <= self.low {
+            self.congested = false;
+            self.event = Some(Watermark::Low);
+        }
+    }
+}
+
+/// A frame in the backlog.
+struct Entry {
+    frame: Frame,
+    meta: Meta,
+}
+
+/// Deadline and cookie of a frame, which are restored when it is dequeued from a mailbox.
+#[derive(Copy, Clone, Default)]
+struct Meta {
+    /// Remaining ticks until the frame expires.
+    deadline: Option<u32>,
+    cookie: u32,
+}
+
+/// A priority-ordered software transmit queue layered over the 3 hardware transmit mailboxes.
+///
+/// Frames passed to [`TxQueue::transmit`] are kept in a backlog of up to `N` frames that is sorted
+/// by [`FramePriority`][crate::FramePriority]. Whenever a mailbox becomes free, the
+/// highest-priority frame in the backlog is moved into it. Frames with identical priority are
+/// transmitted in the order they were enqueued.
+///
+/// If a lower-priority frame is dequeued from a mailbox to make space for a higher-priority one
+/// (see [`Tx::transmit`]), it is put back into the backlog instead of being handed to the caller,
+/// so no frame is ever lost in the process.
+///
+/// To keep the mailboxes filled, [`Interrupt::TransmitMailboxEmpty`] should be enabled, and
+/// [`TxQueue::on_interrupt`] has to be called from the **TX** interrupt handler.
+///
+/// Frames can be enqueued with a deadline, after which they are dropped from the backlog instead
+/// of being sent late. With [`BacklogOrdering::EarliestDeadline`], the backlog is sorted by
+/// deadline instead of priority:
+///
+/// ```no_run
+/// # use bxcan::{Instance, RegisterBlock};
+/// # struct CAN1;
+/// # unsafe impl Instance for CAN1 {
+/// #     const REGISTERS: *mut RegisterBlock = 0x4000_6400 as *mut _;
+/// # }
+/// use bxcan::{standard_id, BacklogOrdering, Frame, TxCompletionProducer, TxQueue};
+///
+/// fn setpoint(queue: &mut TxQueue<CAN1, 8>, value: u16) {
+///     queue.set_ordering(BacklogOrdering::EarliestDeadline);
+///     let frame = Frame::new_data(standard_id!(0x200), value.to_le_bytes());
+///     // Drop the frame if it could not be sent within 5 ticks, and report it with cookie 1.
+///     queue.transmit_with_deadline(&frame, 5, 1).ok();
+/// }
+///
+/// // Called from a timer interrupt handler every millisecond.
+/// fn timer(queue: &mut TxQueue<CAN1, 8>, producer: &mut TxCompletionProducer<'static, 4>) {
+///     queue.tick(producer);
+/// }
+/// ```
+///
+/// To throttle frame production before the backlog overflows, watermarks can be configured with
+/// [`TxQueue::set_watermarks`].
+///
+/// [`Interrupt::TransmitMailboxEmpty`]: crate::Interrupt::TransmitMailboxEmpty
+pub struct TxQueue<I, const N: usize> {
+    tx: Tx<I>,
+    /// Pending frames, sorted in ascending urgency (the next frame to send is last).
+    backlog: Vec<Entry, N>,
+    ordering: BacklogOrdering,
+    /// Deadline and cookie of the frame last put into each mailbox.
+    mailboxes: [Meta; 3],
+    watermarks: Option<Watermarks>,
+}
+
+impl<I, const N: usize> TxQueue<I, N>
+where
+    I: Instance,
+{
+    /// Creates a new transmit queue that will send frames via `tx`.
+    pub fn new(tx: Tx<I>) -> Self {
+        Self {
+            tx,
+            backlog: Vec::new(),
+            ordering: BacklogOrdering::Priority,
+            mailboxes: [Meta::default(); 3],
+            watermarks: None,
+        }
+    }
+
+    /// Configures backpressure notifications when the backlog length crosses `high` or `low`.
+    ///
+    /// Once the backlog holds `high` or more frames, the queue is congested and
+    /// [`Watermark::High`] is reported. It stays congested until the backlog has drained to `low`
+    /// or fewer frames, at which point [`Watermark::Low`] is reported. The state can be polled
+    /// with [`TxQueue::is_congested`] and [`TxQueue::take_watermark_event`].
+    ///
+    /// # Panics
+    ///
+    /// This will panic if `low` is not less than `high`, or if `high` exceeds the capacity `N`.
+    pub fn set_watermarks(&mut self, low: usize, high: usize) {
+        assert!(low < high, "low watermark must be less than high watermark");
+        assert!(high <= N, "high watermark exceeds the capacity");
+
+        let mut watermarks = Watermarks {
+            low,
+            high,
+            congested: false,
+            event: None,
+        };
+        watermarks.update(self.backlog.len());
+        self.watermarks = Some(watermarks);
+    }
+
+    /// Disables the watermarks configured with [`TxQueue::set_watermarks`].
+    pub fn clear_watermarks(&mut self) {
+        self.watermarks = None;
+    }
+
+    /// Returns `true` if the backlog has reached the high watermark and not yet drained to the low
+    /// watermark.
+    ///
+    /// Returns `false` if no watermarks are configured.
+    pub fn is_congested(&self) -> bool {
+        self.watermarks.as_ref().is_some_and(|w| w.congested)
+    }
+
+    /// Returns and clears the latest watermark crossing.
+    ///
+    /// If both watermarks were crossed since the previous call, only the latest crossing is
+    /// returned.
+    pub fn take_watermark_event(&mut self) -> Option<Watermark> {
+        self.watermarks.as_mut().and_then(|w| w.event.take())
+    }
+
+    fn update_watermarks(&mut self) {
+        if let Some(watermarks) = &mut self.watermarks {
+            watermarks.update(self.backlog.len());
+        }
+    }
+
+    /// Changes the order in which frames are sent from the backlog.
+    ///
+    /// Frames that are already in the backlog are reordered.
+    pub fn set_ordering(&mut self, ordering: BacklogOrdering) {
+        self.ordering = ordering;
+        let mut backlog = mem::take(&mut self.backlog);
+        // Reinsert the frames in order of transmission, so that frames of equal urgency keep their
+        // relative order.
+        while let Some(entry) = backlog.pop() {
+            insert(&mut self.backlog, entry, ordering).ok();
+        }
+    }
+
+    /// Returns the order in which frames are sent from the backlog.
+    #[inline]
+    pub fn ordering(&self) -> BacklogOrdering {
+        self.ordering
+    }
+
+    /// Enqueues a frame for transmission.
+    ///
+    /// The frame is put into a transmit mailbox immediately if its priority allows it. Otherwise,
+    /// it is added to the backlog and transmitted once all higher-priority frames have been sent.
+    ///
+    /// Returns [`WouldBlock`][nb::Error::WouldBlock] if the backlog is full.
+    pub fn transmit(&mut self, frame: &Frame) -> nb::Result<(), Infallible> {
+        self.enqueue(frame, Meta::default())
+    }
+
+    /// Enqueues a frame that has to be put into a transmit mailbox within `deadline` ticks.
+    ///
+    /// If it is still in the backlog after `deadline` calls to [`TxQueue::tick`], it is dropped
+    /// and reported as an expired [`TxCompletion`] with `cookie`. Once the frame is in a mailbox,
+    /// it is sent even if its deadline passes.
+    ///
+    /// Returns [`WouldBlock`][nb::Error::WouldBlock] if the backlog is full.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if `deadline` is 0.
+    pub fn transmit_with_deadline(
+        &mut self,
+        frame: &Frame,
+        deadline: u32,
+        cookie: u32,
+    ) -> nb::Result<(), Infallible> {
+        assert!(deadline != 0, "deadline must not be 0");
+
+        let meta = Meta {
+            deadline: Some(deadline),
+            cookie,
+        };
+        self.enqueue(frame, meta)
+    }
+
+    fn enqueue(&mut self, frame: &Frame, meta: Meta) -> nb::Result<(), Infallible> {
+        let entry = Entry {
+            frame: frame.clone(),
+            meta,
+        };
+        insert(&mut self.backlog, entry, self.ordering).map_err(|_| nb::Error::WouldBlock)?;
+        self.refill();
+        self.update_watermarks();
+        Ok(())
+    }
+
+    /// Advances time by one tick, and drops the frames whose deadline has passed.
+    ///
+    /// The dropped frames are reported to `expired` as completions for which
+    /// [`TxCompletion::is_expired`] returns `true`, carrying the cookie passed to
+    /// [`TxQueue::transmit_with_deadline`]. Returns the number of dropped frames.
+    pub fn tick<const M: usize>(&mut self, expired: &mut TxCompletionProducer<'_, M>) -> usize {
+        for meta in &mut self.mailboxes {
+            meta.tick();
+        }
+
+        let mut count = 0;
+        let mut i = 0;
+        while i < self.backlog.len() {
+            let meta = &mut self.backlog[i].meta;
+            meta.tick();
+            if meta.deadline == Some(0) {
+                let entry = self.backlog.remove(i);
+                expired.push(TxCompletion::expired(entry.meta.cookie)).ok();
+                count += 1;
+            } else {
+                i += 1;
+            }
+        }
+        self.update_watermarks();
+        count
+    }
+
+    /// Acknowledges a pending TX interrupt and moves frames from the backlog into free mailboxes.
+    ///
+    /// This should be called from the **TX** interrupt handler whenever
+    /// [`Interrupt::TransmitMailboxEmpty`] fires.
+    ///
+    /// [`Interrupt::TransmitMailboxEmpty`]: crate::Interrupt::TransmitMailboxEmpty
+    pub fn on_interrupt(&mut self) {
+        self.tx.clear_interrupt_flags();
+        self.refill();
+        self.update_watermarks();
+    }
+
+    /// Moves as many frames as possible from the backlog into the transmit mailboxes.
+    fn refill(&mut self) {
+        let policy = match self.ordering {
+            BacklogOrdering::Priority => ReplacementPolicy::DequeueLower,
+            BacklogOrdering::EarliestDeadline => ReplacementPolicy::RejectWhenFull,
+        };
+        while let Some(entry) = self.backlog.last() {
+            match self.tx.transmit_with_policy(&entry.frame, policy) {
+                Ok(status) => {
+                    let meta = self.backlog.pop().unwrap().meta;
+                    let mailbox = status.mailbox() as usize;
+                    let dequeued_meta = mem::replace(&mut self.mailboxes[mailbox], meta);
+                    if let Some(dequeued) = status.into_dequeued_frame() {
+                        let entry = Entry {
+                            frame: dequeued,
+                            meta: dequeued_meta,
+                        };
+                        // We just made space for this frame by popping one off the backlog.
+                        reinsert(&mut self.backlog, entry, self.ordering).ok();
+                    }
+                }
+                Err(nb::Error::WouldBlock) => break,
+                Err(nb::Error::Other(infallible)) => match infallible {},
+            }
+        }
+    }
+
+    /// Returns the number of frames in the backlog that have not yet been put into a mailbox.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.backlog.len()
+    }
+
+    /// Returns `true` if there are no frames in the backlog.
+    ///
+    /// Frames that have already been put into a transmit mailbox might still be pending. Use
+    /// [`TxQueue::is_idle`] to check whether all transmissions have finished.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.backlog.is_empty()
+    }
+
+    /// Returns `true` when the backlog is empty and no frame is pending in any mailbox.
+    pub fn is_idle(&self) -> bool {
+        self.backlog.is_empty() && self.tx.is_idle()
+    }
+
+    /// Returns the maximum number of frames the backlog can hold.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Destroys the queue and returns the [`Tx`] half it was created from.
+    ///
+    /// Frames still in the backlog are dropped. Frames that were already put into a transmit
+    /// mailbox stay pending.
+    pub fn free(self) -> Tx<I> {
+        self.tx
+    }
+}
+
+impl Meta {
+    fn tick(&mut self) {
+        if let Some(deadline) = &mut self.deadline {
+            *deadline = deadline.saturating_sub(1);
+        }
+    }
+}
+
+/// The sort key of a backlog entry. Greater keys are more urgent.
+fn urgency(entry: &Entry, ordering: BacklogOrdering) -> (Option<Reverse<u32>>, FramePriority) {
+    let deadline = match ordering {
+        BacklogOrdering::Priority => None,
+        // Any deadline is more urgent than none.
+        BacklogOrdering::EarliestDeadline => entry.meta.deadline.map(Reverse),
+    };
+    (deadline, entry.frame.priority())
+}
+
+/// Inserts `entry` into `backlog`, maintaining ascending urgency order.
+///
+/// The entry is placed in front of already enqueued entries of the same urgency, so that those are
+/// transmitted first.
+fn insert<const N: usize>(
+    backlog: &mut Vec<Entry, N>,
+    entry: Entry,
+    ordering: BacklogOrdering,
+) -> Result<(), Entry> {
+    let key = urgency(&entry, ordering);
+    let index = backlog
+        .iter()
+        .position(|e| urgency(e, ordering) >= key)
+        .unwrap_or(backlog.len());
+    backlog.insert(index, entry)
+}
+
+/// Puts an entry that was dequeued from a transmit mailbox back into `backlog`.
+///
+/// Unlike [`insert`], the entry is placed behind entries of the same urgency, since it was enqueued
+/// before them and has to be transmitted first.
+fn reinsert<const N: usize>(
+    backlog: &mut Vec<Entry, N>,
+    entry: Entry,
+    ordering: BacklogOrdering,
+) -> Result<(), Entry> {
+    let key = urgency(&entry, ordering);
+    let index = backlog
+        .iter()
+        .position(|e| urgency(e, ordering) > key)
+        .unwrap_or(backlog.len());
+    backlog.insert(index, entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExtendedId, StandardId};
+
+    fn entry(frame: &Frame, deadline: Option<u32>) -> Entry {
+        Entry {
+            frame: frame.clone(),
+            meta: Meta {
+                deadline,
+                cookie: 0,
+            },
+        }
+    }
+
+    fn pop<const N: usize>(backlog: &mut Vec<Entry, N>) -> Option<Frame> {
+        backlog.pop().map(|entry| entry.frame)
+    }
+
+    #[test]
+    fn backlog_ordering() {
+        let mut backlog = Vec::<Entry, 8>::new();
+        let low = Frame::new_data(StandardId::new(0x700).unwrap(), []);
+        let high = Frame::new_data(StandardId::new(0x001).unwrap(), []);
+        let ext = Frame::new_data(ExtendedId::new(0x005 << 18).unwrap(), []);
+        let first = Frame::new_data(StandardId::new(0x010).unwrap(), [1]);
+        let second = Frame::new_data(StandardId::new(0x010).unwrap(), [2]);
+
+        // Deadlines are ignored when ordering by priority.
+        let ordering = BacklogOrdering::Priority;
+        insert(&mut backlog, entry(&low, Some(1)), ordering)
+            .ok()
+            .unwrap();
+        insert(&mut backlog, entry(&first, None), ordering)
+            .ok()
+            .unwrap();
+        insert(&mut backlog, entry(&ext, Some(9)), ordering)
+            .ok()
+            .unwrap();
+        insert(&mut backlog, entry(&second, Some(2)), ordering)
+            .ok()
+            .unwrap();
+        insert(&mut backlog, entry(&high, None), ordering)
+            .ok()
+            .unwrap();
+
+        // Popped in order of transmission.
+        assert_eq!(pop(&mut backlog), Some(high));
+        assert_eq!(pop(&mut backlog), Some(ext));
+        assert_eq!(pop(&mut backlog), Some(first));
+        assert_eq!(pop(&mut backlog), Some(second));
+        assert_eq!(pop(&mut backlog), Some(low));
+        assert_eq!(pop(&mut backlog), None);
+    }
+
+    #[test]
+    fn backlog_ordering_deadline() {
+        let mut backlog = Vec::<Entry, 8>::new();
+        let low = Frame::new_data(StandardId::new(0x700).unwrap(), []);
+        let high = Frame::new_data(StandardId::new(0x001).unwrap(), []);
+        let urgent = Frame::new_data(StandardId::new(0x100).unwrap(), [1]);
+        let later = Frame::new_data(StandardId::new(0x100).unwrap(), [2]);
+        let first = Frame::new_data(StandardId::new(0x200).unwrap(), [1]);
+        let second = Frame::new_data(StandardId::new(0x200).unwrap(), [2]);
+
+        let ordering = BacklogOrdering::EarliestDeadline;
+        insert(&mut backlog, entry(&high, None), ordering)
+            .ok()
+            .unwrap();
+        insert(&mut backlog, entry(&later, Some(10)), ordering)
+            .ok()
+            .unwrap();
+        insert(&mut backlog, entry(&first, Some(3)), ordering)
+            .ok()
+            .unwrap();
+        insert(&mut backlog, entry(&low, Some(3)), ordering)
+            .ok()
+            .unwrap();
+        insert(&mut backlog, entry(&urgent, Some(1)), ordering)
+            .ok()
+            .unwrap();
+        insert(&mut backlog, entry(&second, Some(3)), ordering)
+            .ok()
+            .unwrap();
+
+        assert_eq!(pop(&mut backlog), Some(urgent));
+        // Same deadline: by priority, then in enqueueing order.
+        assert_eq!(pop(&mut backlog), Some(first));
+        assert_eq!(pop(&mut backlog), Some(second));
+        assert_eq!(pop(&mut backlog), Some(low));
+        assert_eq!(pop(&mut backlog), Some(later));
+        // Frames without deadline are sent last.
+        assert_eq!(pop(&mut backlog), Some(high));
+        assert_eq!(pop(&mut backlog), None);
+    }
+
+    #[test]
+    fn backlog_reinsert() {
+        let mut backlog = Vec::<Entry, 8>::new();
+        let id = StandardId::new(0x010).unwrap();
+        let dequeued = Frame::new_data(id, [0]);
+        let first = Frame::new_data(id, [1]);
+        let second = Frame::new_data(id, [2]);
+        let high = Frame::new_data(StandardId::new(0x001).unwrap(), []);
+
+        let ordering = BacklogOrdering::Priority;
+        insert(&mut backlog, entry(&first, None), ordering)
+            .ok()
+            .unwrap();
+        insert(&mut backlog, entry(&second, None), ordering)
+            .ok()
+            .unwrap();
+        insert(&mut backlog, entry(&high, None), ordering)
+            .ok()
+            .unwrap();
+        // Enqueued before `first` and `second`, but dequeued from its mailbox afterwards.
+        reinsert(&mut backlog, entry(&dequeued, None), ordering)
+            .ok()
+            .unwrap();
+
+        assert_eq!(pop(&mut backlog), Some(high));
+        assert_eq!(pop(&mut backlog), Some(dequeued));
+        assert_eq!(pop(&mut backlog), Some(first));
+        assert_eq!(pop(&mut backlog), Some(second));
+        assert_eq!(pop(&mut backlog), None);
+    }
+
+    #[test]
+    fn backlog_full() {
+        let mut backlog = Vec::<Entry, 1>::new();
+        let frame = Frame::new_data(StandardId::ZERO, []);
+        let ordering = BacklogOrdering::Priority;
+        insert(&mut backlog, entry(&frame, None), ordering)
+            .ok()
+            .unwrap();
+        let rejected = insert(&mut backlog, entry(&frame, None), ordering);
+        assert_eq!(rejected.err().map(|entry| entry.frame), Some(frame));
+    }
+
+    #[test]
+    fn watermarks() {
+        let mut watermarks = Watermarks {
+            low: 2,
+            high: 5,
+            congested: false,
+            event: None,
+        };
+        for len in 0..5 {
+            watermarks.update(len);
+            assert!(!watermarks.congested);
+        }
+        assert_eq!(watermarks.event, None);
+
+        watermarks.update(5);
+        assert!(watermarks.congested);
+        assert_eq!(watermarks.event.take(), Some(Watermark::High));
+        // Hysteresis: no events between the watermarks.
+        for len in [6, 4, 3, 5, 3] {
+            watermarks.update(len);
+            assert!(watermarks.congested);
+        }
+        assert_eq!(watermarks.event, None);
+
+        watermarks.update(2);
+        assert!(!watermarks.congested);
+        assert_eq!(watermarks.event.take(), Some(Watermark::Low));
+        watermarks.update(1);
+        assert_eq!(watermarks.event, None);
+    }
+
+    #[test]
+    fn deadline_tick() {
+        let mut meta = Meta {
+            deadline: Some(2),
+            cookie: 0,
+        };
+        meta.tick();
+        assert_eq!(meta.deadline, Some(1));
+        meta.tick();
+        meta.tick();
+        assert_eq!(meta.deadline, Some(0));
+
+        let mut meta = Meta::default();
+        meta.tick();
+        assert_eq!(meta.deadline, None);
+    }
+}
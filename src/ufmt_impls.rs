@@ -0,0 +1,292 @@
+//! `ufmt` trait impls.
+//!
+//! Identifiers and frames are displayed like in `candump` logs, eg. `123#DEADBEEF`. Error types
+//! are displayed like by their `Display` impl.
+
+use ufmt::{uDebug, uDisplay, uWrite, uwrite, Formatter};
+
+use crate::bit_timing::BitTimingError;
+use crate::{
+    ConfigError, ExtendedId, Fifo, Frame, Id, MailboxStatus, OverrunError, OverrunMode,
+    SelfTestError, StandardId, TryError,
+};
+
+impl uDebug for StandardId {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        f.debug_tuple("StandardId")?.field(&self.as_raw())?.finish()
+    }
+}
+
+impl uDisplay for StandardId {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        uwrite!(f, "{:03X}", self.as_raw())
+    }
+}
+
+impl uDebug for ExtendedId {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        f.debug_tuple("ExtendedId")?.field(&self.as_raw())?.finish()
+    }
+}
+
+impl uDisplay for ExtendedId {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        uwrite!(f, "{:08X}", self.as_raw())
+    }
+}
+
+impl uDebug for Id {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        match self {
+            Id::Standard(id) => f.debug_tuple("Standard")?.field(id)?.finish(),
+            Id::Extended(id) => f.debug_tuple("Extended")?.field(id)?.finish(),
+        }
+    }
+}
+
+impl uDisplay for Id {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        match self {
+            Id::Standard(id) => uDisplay::fmt(id, f),
+            Id::Extended(id) => uDisplay::fmt(id, f),
+        }
+    }
+}
+
+impl uDebug for Frame {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        f.debug_struct("Frame")?
+            .field("id", &self.id())?
+            .field("dlc", &self.dlc())?
+            .field("data", &self.data().map(|data| &**data))?
+            .finish()
+    }
+}
+
+impl uDisplay for Frame {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        uwrite!(f, "{}#", self.id())?;
+        match self.data() {
+            Some(data) => {
+                for byte in data.iter() {
+                    uwrite!(f, "{:02X}", *byte)?;
+                }
+                Ok(())
+            }
+            None if self.dlc() == 0 => f.write_char('R'),
+            None => uwrite!(f, "R{}", self.dlc()),
+        }
+    }
+}
+
+impl uDebug for Fifo {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        f.write_str(match self {
+            Fifo::Fifo0 => "Fifo0",
+            Fifo::Fifo1 => "Fifo1",
+        })
+    }
+}
+
+impl uDebug for OverrunMode {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        f.write_str(match self {
+            OverrunMode::Overwrite => "Overwrite",
+            OverrunMode::Discard => "Discard",
+        })
+    }
+}
+
+impl uDebug for MailboxStatus {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        f.debug_struct("MailboxStatus")?
+            .field("empty", &self.is_empty())?
+            .field("request_completed", &self.is_request_completed())?
+            .field("transmitted", &self.is_transmitted())?
+            .field("arbitration_lost", &self.is_arbitration_lost())?
+            .field("error", &self.is_transmit_error())?
+            .finish()
+    }
+}
+
+impl uDebug for OverrunError {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        f.debug_struct("OverrunError")?
+            .field("fifo", &self.fifo())?
+            .field("mode", &self.mode())?
+            .field("count", &self.count())?
+            .finish()
+    }
+}
+
+impl uDisplay for OverrunError {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        let lost = match self.mode() {
+            OverrunMode::Overwrite => "newest frame overwritten",
+            OverrunMode::Discard => "received frame discarded",
+        };
+        uwrite!(f, "receive FIFO {} overran, {}", self.fifo() as u8, lost)
+    }
+}
+
+impl uDebug for TryError {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        match self {
+            TryError::Busy => f.write_str("Busy"),
+            TryError::Empty => f.write_str("Empty"),
+            TryError::Overrun(e) => f.debug_tuple("Overrun")?.field(e)?.finish(),
+        }
+    }
+}
+
+impl uDisplay for TryError {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        match self {
+            TryError::Busy => f.write_str("all transmit mailboxes are busy"),
+            TryError::Empty => f.write_str("no frame received"),
+            TryError::Overrun(e) => uDisplay::fmt(e, f),
+        }
+    }
+}
+
+impl uDebug for SelfTestError {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        match self {
+            SelfTestError::Busy => f.write_str("Busy"),
+            SelfTestError::TransmitFailed(status) => {
+                f.debug_tuple("TransmitFailed")?.field(status)?.finish()
+            }
+            SelfTestError::NotReceived => f.write_str("NotReceived"),
+            SelfTestError::Mismatch(frame) => f.debug_tuple("Mismatch")?.field(frame)?.finish(),
+        }
+    }
+}
+
+impl uDisplay for SelfTestError {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        f.write_str(match self {
+            SelfTestError::Busy => "peripheral busy, self-test not run",
+            SelfTestError::TransmitFailed(_) => "self-test frame could not be transmitted",
+            SelfTestError::NotReceived => "self-test frame was not received",
+            SelfTestError::Mismatch(_) => "received frame does not match the self-test frame",
+        })
+    }
+}
+
+impl uDebug for ConfigError {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        f.write_str(match self {
+            ConfigError::InvalidBitTiming => "InvalidBitTiming",
+            ConfigError::NoFilters => "NoFilters",
+        })
+    }
+}
+
+impl uDisplay for ConfigError {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        f.write_str(match self {
+            ConfigError::InvalidBitTiming => "resynchronization jump width exceeds a bit segment",
+            ConfigError::NoFilters => "no filter bank enabled",
+        })
+    }
+}
+
+impl uDebug for BitTimingError {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        f.write_str(match self {
+            BitTimingError::InvalidBitrate => "InvalidBitrate",
+            BitTimingError::InvalidSamplePoint => "InvalidSamplePoint",
+            BitTimingError::NoSolution => "NoSolution",
+        })
+    }
+}
+
+impl uDisplay for BitTimingError {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "candump")]
+mod candump {
+    use super::*;
+    use crate::candump::ParseError;
+
+    impl uDebug for ParseError {
+        fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+            f.write_str(match self {
+                ParseError::Malformed => "Malformed",
+                ParseError::InvalidTimestamp => "InvalidTimestamp",
+                ParseError::InvalidId => "InvalidId",
+                ParseError::InvalidData => "InvalidData",
+            })
+        }
+    }
+
+    impl uDisplay for ParseError {
+        fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+            f.write_str(self.as_str())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::convert::Infallible;
+
+    struct Buf(heapless::String<128>);
+
+    impl uWrite for Buf {
+        type Error = Infallible;
+
+        fn write_str(&mut self, s: &str) -> Result<(), Infallible> {
+            self.0.push_str(s).unwrap();
+            Ok(())
+        }
+    }
+
+    fn display(value: &impl uDisplay) -> heapless::String<128> {
+        let mut buf = Buf(heapless::String::new());
+        uwrite!(buf, "{}", value).unwrap();
+        buf.0
+    }
+
+    fn debug(value: &impl uDebug) -> heapless::String<128> {
+        let mut buf = Buf(heapless::String::new());
+        uwrite!(buf, "{:?}", value).unwrap();
+        buf.0
+    }
+
+    #[test]
+    fn frames() {
+        let frame = Frame::new_data(StandardId::new(0x123).unwrap(), [0xDE, 0xAD]);
+        assert_eq!(display(&frame), "123#DEAD");
+        assert_eq!(
+            debug(&frame),
+            "Frame { id: Standard(StandardId(291)), dlc: 2, data: Some([222, 173]) }"
+        );
+
+        let remote = Frame::new_remote(ExtendedId::MAX, 3);
+        assert_eq!(display(&remote), "1FFFFFFF#R3");
+        assert_eq!(display(&Frame::new_remote(StandardId::ZERO, 0)), "000#R");
+    }
+
+    #[test]
+    fn errors() {
+        let e = TryError::Overrun(OverrunError::new(Fifo::Fifo1, OverrunMode::Discard));
+        assert_eq!(
+            display(&e),
+            "receive FIFO 1 overran, received frame discarded"
+        );
+        assert_eq!(
+            debug(&e),
+            "Overrun(OverrunError { fifo: Fifo1, mode: Discard, count: None })"
+        );
+        assert_eq!(debug(&SelfTestError::NotReceived), "NotReceived");
+        assert_eq!(
+            display(&BitTimingError::NoSolution),
+            "no bit timing achieves the exact bitrate"
+        );
+    }
+}
@@ -9,11 +9,13 @@
 
 pub mod interrupt;
 
+use core::ptr;
+
 use cortex_m::peripheral::NVIC;
 use defmt_rtt as _;
 use panic_probe as _;
 
-use bxcan::{Can, FilterOwner, Frame, Instance, MasterInstance};
+use bxcan::{Can, FilterOwner, Frame, Instance, MasterInstance, Mode};
 
 pub use stm32f1::stm32f107 as pac;
 
@@ -86,6 +88,13 @@ fn init(p: pac::Peripherals) -> (CAN1, CAN2) {
     (CAN1 { _private: () }, CAN2 { _private: () })
 }
 
+fn enable_can1(can1: CAN1) -> Can<CAN1> {
+    Can::builder(can1)
+        .set_mode(Mode::SilentLoopback)
+        .set_bit_timing(BTR_FAST)
+        .enable()
+}
+
 pub struct State {
     pub can1: Can<CAN1>,
     pub can2: Can<CAN2>,
@@ -98,14 +107,9 @@ impl State {
     pub fn init() -> Self {
         let periph = defmt::unwrap!(pac::Peripherals::take());
         let (can1, can2) = init(periph);
-        let mut can1 = Can::builder(can1)
-            .set_loopback(true)
-            .set_silent(true)
-            .set_bit_timing(BTR_FAST)
-            .enable();
+        let mut can1 = enable_can1(can1);
         let can2 = Can::builder(can2)
-            .set_loopback(true)
-            .set_silent(true)
+            .set_mode(Mode::SilentLoopback)
             .set_bit_timing(BTR_FAST)
             .enable();
         can1.modify_filters().clear();
@@ -121,14 +125,12 @@ impl State {
     pub fn go_slow(&mut self) {
         self.can1
             .modify_config()
-            .set_loopback(true)
-            .set_silent(true)
+            .set_mode(Mode::SilentLoopback)
             .set_bit_timing(BTR_SLOW)
             .enable();
         self.can2
             .modify_config()
-            .set_loopback(true)
-            .set_silent(true)
+            .set_mode(Mode::SilentLoopback)
             .set_bit_timing(BTR_SLOW)
             .enable();
     }
@@ -137,18 +139,31 @@ impl State {
     pub fn go_fast(&mut self) {
         self.can1
             .modify_config()
-            .set_loopback(true)
-            .set_silent(true)
+            .set_mode(Mode::SilentLoopback)
             .set_bit_timing(BTR_FAST)
             .enable();
         self.can2
             .modify_config()
-            .set_loopback(true)
-            .set_silent(true)
+            .set_mode(Mode::SilentLoopback)
             .set_bit_timing(BTR_FAST)
             .enable();
     }
 
+    /// Moves `can1` out of the state, for testing APIs that consume the [`Can`] instance.
+    ///
+    /// `f` can return the instance again (eg. after joining the parts it was split into). If it
+    /// returns `None` instead, CAN1 is set up again like in [`State::init`]. The filter and interrupt
+    /// configuration is left unchanged in that case.
+    pub fn with_can1(&mut self, f: impl FnOnce(Can<CAN1>) -> Option<Can<CAN1>>) {
+        // Safety: `self.can1` is overwritten before it is used again. Panics abort the test, so the
+        // moved-out instance is never dropped twice.
+        unsafe {
+            let can1 =
+                f(ptr::read(&self.can1)).unwrap_or_else(|| enable_can1(CAN1 { _private: () }));
+            ptr::write(&mut self.can1, can1);
+        }
+    }
+
     pub fn roundtrip_frame(&mut self, frame: &Frame) -> bool {
         nb::block!(self.can1.transmit(frame)).unwrap();
         defmt::assert!(!self.can1.is_transmitter_idle());
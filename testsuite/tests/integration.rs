@@ -3,11 +3,17 @@
 
 #[defmt_test::tests]
 mod tests {
-    use bxcan::filter::{ListEntry32, Mask16, Mask32};
-    use bxcan::{ExtendedId, Frame, Mailbox, StandardId};
+    use bxcan::bit_timing::{BitTiming, BitTimingError};
+    use bxcan::filter::{ListEntry16, ListEntry32, Mask16, Mask32, StaleFilterHandle};
+    use bxcan::{
+        AutoReply, Can, ConfigError, ErrorState, Event, ExtendedId, Fifo, FilterRegister, Frame,
+        Interrupt, Interrupts, Lec, Mailbox, Mode, OverrunMode, Register, ReplacementPolicy,
+        RxEvent, SceEvent, SelfTestError, ShutdownMode, StandardId, StormProtection, TryError,
+        TxCompletionQueue, TxEvent, TxOrdering, TxQueue,
+    };
 
     use nb::block;
-    use testsuite::State;
+    use testsuite::{pac, State};
 
     #[init]
     fn init() -> State {
@@ -37,6 +43,82 @@ mod tests {
         filt.set_split(28);
         defmt::assert_eq!(filt.num_banks(), 28);
         defmt::assert_eq!(filt.slave_filters().num_banks(), 0);
+
+        // Banks moved to the slave instance are disabled.
+        filt.clear()
+            .enable_bank(13, Fifo::Fifo0, Mask32::accept_all())
+            .set_split(13)
+            .set_split(14);
+        drop(filt);
+        let frame = Frame::new_data(StandardId::new(0).unwrap(), []);
+        defmt::assert!(!state.roundtrip_frame(&frame));
+    }
+
+    #[test]
+    fn read_back_filters(state: &mut State) {
+        let list16 = [ListEntry16::data_frames_with_id(StandardId::new(0x42).unwrap()); 4];
+        let list32 = [
+            ListEntry32::data_frames_with_id(ExtendedId::new(0x1234).unwrap()),
+            ListEntry32::remote_frames_with_id(StandardId::new(0x42).unwrap()),
+        ];
+        let mask16 = [Mask16::frames_with_std_id_prefix(StandardId::new(0x100).unwrap(), 4); 2];
+        let mask32 = Mask32::frames_with_ext_id_prefix(ExtendedId::new(0x1000).unwrap(), 20);
+
+        let mut filt = state.can1.modify_filters();
+        filt.clear()
+            .enable_bank(0, Fifo::Fifo0, list16)
+            .enable_bank(1, Fifo::Fifo1, list32)
+            .enable_bank(3, Fifo::Fifo0, mask16)
+            .enable_bank(4, Fifo::Fifo1, mask32);
+
+        defmt::assert_eq!(filt.get(0), Some((Fifo::Fifo0, list16.into())));
+        defmt::assert_eq!(filt.get(1), Some((Fifo::Fifo1, list32.into())));
+        defmt::assert_eq!(filt.get(2), None);
+        defmt::assert_eq!(filt.get(3), Some((Fifo::Fifo0, mask16.into())));
+        defmt::assert_eq!(filt.get(4), Some((Fifo::Fifo1, mask32.into())));
+
+        let mut iter = filt.iter();
+        defmt::assert_eq!(iter.next().map(|(index, _, _)| index), Some(0));
+        defmt::assert_eq!(iter.next().map(|(index, _, _)| index), Some(1));
+        defmt::assert_eq!(iter.next().map(|(index, _, _)| index), Some(3));
+        defmt::assert_eq!(iter.next().map(|(index, _, _)| index), Some(4));
+        defmt::assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn add_remove_filter(state: &mut State) {
+        state.can1.modify_filters().clear();
+
+        let frame_a = Frame::new_data(StandardId::new(0x10).unwrap(), []);
+        let frame_b = Frame::new_data(StandardId::new(0x20).unwrap(), []);
+        let a = state
+            .can1
+            .add_filter(
+                Fifo::Fifo0,
+                Mask32::frames_with_std_id(StandardId::new(0x10).unwrap(), StandardId::MAX),
+            )
+            .unwrap();
+        let b = state
+            .can1
+            .add_filter(
+                Fifo::Fifo1,
+                Mask32::frames_with_std_id(StandardId::new(0x20).unwrap(), StandardId::MAX),
+            )
+            .unwrap();
+        defmt::assert_ne!(a.index(), b.index());
+        defmt::assert!(state.roundtrip_frame(&frame_a));
+        defmt::assert!(state.roundtrip_frame(&frame_b));
+
+        state.can1.remove_filter(a).unwrap();
+        defmt::assert!(!state.roundtrip_frame(&frame_a));
+        defmt::assert!(state.roundtrip_frame(&frame_b));
+
+        // Modifying the filters invalidates the handle, and the bank stays enabled.
+        state.can1.modify_filters().set_split(14);
+        defmt::assert_eq!(state.can1.remove_filter(b), Err(StaleFilterHandle));
+        defmt::assert!(state.roundtrip_frame(&frame_b));
+        state.can1.modify_filters().clear();
+        defmt::assert!(!state.roundtrip_frame(&frame_b));
     }
 
     #[test]
@@ -45,7 +127,7 @@ mod tests {
             .can1
             .modify_filters()
             .clear()
-            .enable_bank(0, Mask32::accept_all());
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
 
         let frame = Frame::new_data(StandardId::new(0).unwrap(), []);
         defmt::assert!(state.roundtrip_frame(&frame));
@@ -54,6 +136,132 @@ mod tests {
         defmt::assert!(state.roundtrip_frame(&frame));
     }
 
+    #[test]
+    fn enable_bank_const(state: &mut State) {
+        state
+            .can1
+            .modify_filters()
+            .set_split(14)
+            .clear()
+            .enable_bank_const::<13>(Fifo::Fifo0, Mask32::accept_all());
+
+        let frame = Frame::new_data(StandardId::new(0x13).unwrap(), []);
+        defmt::assert!(state.roundtrip_frame(&frame));
+        defmt::assert!(state.can1.modify_filters().get(13).is_some());
+    }
+
+    #[test]
+    fn split_by_ref_roundtrip(state: &mut State) {
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+
+        let (tx, rx0, rx1) = state.can1.split_by_ref();
+        let frame = Frame::new_data(StandardId::new(0x123).unwrap(), [4, 5, 6]);
+        block!(tx.transmit(&frame)).unwrap();
+        while !tx.is_idle() {}
+
+        // Bank 0 is assigned to FIFO 0 by default.
+        defmt::assert!(matches!(rx1.receive(), Err(nb::Error::WouldBlock)));
+        defmt::assert_eq!(rx0.status().pending(), 1);
+        defmt::assert_eq!(block!(rx0.receive()).unwrap(), frame);
+    }
+
+    #[test]
+    fn auto_reply(state: &mut State) {
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+
+        let id = StandardId::new(0x321).unwrap();
+        let response = Frame::new_data(id, [1, 2]);
+        let mut replies = AutoReply::<2>::new();
+        replies.set(response.clone()).unwrap();
+
+        let (tx, rx0, _) = state.can1.split_by_ref();
+
+        // Data frames and unknown IDs are ignored.
+        let data = Frame::new_data(id, []);
+        defmt::assert!(block!(replies.handle(&data, tx)).unwrap().is_none());
+        let other = Frame::new_remote(StandardId::new(0x322).unwrap(), 2);
+        defmt::assert!(block!(replies.handle(&other, tx)).unwrap().is_none());
+
+        let request = Frame::new_remote(id, 2);
+        block!(tx.transmit(&request)).unwrap();
+        while !tx.is_idle() {}
+        let received = block!(rx0.receive()).unwrap();
+        defmt::assert_eq!(received, request);
+
+        let status = block!(replies.handle(&received, tx)).unwrap().unwrap();
+        defmt::assert!(status.dequeued_frame().is_none());
+        while !tx.is_idle() {}
+        defmt::assert_eq!(block!(rx0.receive()).unwrap(), response);
+
+        // The request is answered while receiving, and only the response is returned.
+        block!(state.can1.transmit(&request)).unwrap();
+        while !state.can1.is_transmitter_idle() {}
+        let received = loop {
+            match state.can1.receive_with_auto_reply(&replies) {
+                Err(nb::Error::WouldBlock) => {}
+                result => break result.unwrap(),
+            }
+        };
+        defmt::assert_eq!(received, response);
+        defmt::assert!(matches!(state.can1.receive(), Err(nb::Error::WouldBlock)));
+
+        // Data frames are returned unchanged.
+        block!(state.can1.transmit(&data)).unwrap();
+        while !state.can1.is_transmitter_idle() {}
+        defmt::assert_eq!(
+            block!(state.can1.receive_with_auto_reply(&replies)).unwrap(),
+            data
+        );
+    }
+
+    #[test]
+    fn filter_fifo_assignment(state: &mut State) {
+        let fifo0_id = StandardId::new(0x100).unwrap();
+        let fifo1_id = StandardId::new(0x200).unwrap();
+        let mask = StandardId::MAX;
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, Mask32::frames_with_std_id(fifo0_id, mask))
+            .enable_bank(1, Fifo::Fifo1, Mask32::frames_with_std_id(fifo1_id, mask));
+
+        let (tx, rx0, rx1) = state.can1.split_by_ref();
+        let frame0 = Frame::new_data(fifo0_id, [0]);
+        let frame1 = Frame::new_data(fifo1_id, [1]);
+        block!(tx.transmit(&frame1)).unwrap();
+        block!(tx.transmit(&frame0)).unwrap();
+        while !tx.is_idle() {}
+
+        // Each FIFO has one `Mask32` filter with index 0.
+        let (received, meta) = block!(rx0.receive_with_meta()).unwrap();
+        defmt::assert_eq!(received, frame0);
+        defmt::assert_eq!(meta.fifo(), Fifo::Fifo0);
+        defmt::assert_eq!(meta.filter_match_index(), 0);
+        let (received, meta) = block!(rx1.receive_with_meta()).unwrap();
+        defmt::assert_eq!(received, frame1);
+        defmt::assert_eq!(meta.fifo(), Fifo::Fifo1);
+        defmt::assert_eq!(meta.filter_match_index(), 0);
+        defmt::assert!(matches!(rx0.receive(), Err(nb::Error::WouldBlock)));
+        defmt::assert!(matches!(rx1.receive(), Err(nb::Error::WouldBlock)));
+
+        // Reassigning bank 1 takes effect.
+        state.can1.modify_filters().enable_bank(
+            1,
+            Fifo::Fifo0,
+            Mask32::frames_with_std_id(fifo1_id, mask),
+        );
+        defmt::assert!(state.roundtrip_frame(&frame1));
+    }
+
     #[test]
     fn no_filters_no_frames(state: &mut State) {
         state.can1.modify_filters().clear();
@@ -69,11 +277,11 @@ mod tests {
         let target_id = StandardId::new(42).unwrap();
         let mask = StandardId::MAX; // Exact match required
 
-        state
-            .can1
-            .modify_filters()
-            .clear()
-            .enable_bank(0, Mask32::frames_with_std_id(target_id, mask));
+        state.can1.modify_filters().clear().enable_bank(
+            0,
+            Fifo::Fifo0,
+            Mask32::frames_with_std_id(target_id, mask),
+        );
 
         // Data frames with matching IDs should be accepted.
         let frame = Frame::new_data(target_id, []);
@@ -113,11 +321,11 @@ mod tests {
         let target_id = ExtendedId::new(0).unwrap();
         let mask = ExtendedId::MAX; // Exact match required
 
-        state
-            .can1
-            .modify_filters()
-            .clear()
-            .enable_bank(0, Mask32::frames_with_ext_id(target_id, mask));
+        state.can1.modify_filters().clear().enable_bank(
+            0,
+            Fifo::Fifo0,
+            Mask32::frames_with_ext_id(target_id, mask),
+        );
 
         // Data frames with matching IDs should be accepted.
         let frame = Frame::new_data(target_id, []);
@@ -157,6 +365,7 @@ mod tests {
 
         state.can1.modify_filters().clear().enable_bank(
             0,
+            Fifo::Fifo0,
             [
                 Mask16::frames_with_std_id(target_id_1, mask),
                 Mask16::frames_with_std_id(target_id_2, mask),
@@ -188,6 +397,86 @@ mod tests {
         defmt::assert!(!state.roundtrip_frame(&frame));
     }
 
+    /// `List16` filter mode accepting up to 4 standard CAN frames per bank.
+    #[test]
+    fn filter_list16(state: &mut State) {
+        let target_id_1 = StandardId::new(16).unwrap();
+        let target_id_2 = StandardId::new(17).unwrap();
+        let target_id_3 = StandardId::MAX;
+        let target_id_4 = StandardId::ZERO;
+
+        state.can1.modify_filters().clear().enable_bank(
+            0,
+            Fifo::Fifo0,
+            [
+                ListEntry16::data_frames_with_id(target_id_1),
+                ListEntry16::data_frames_with_id(target_id_2),
+                ListEntry16::remote_frames_with_id(target_id_3),
+                ListEntry16::data_frames_with_id(target_id_4),
+            ],
+        );
+
+        // Frames with matching IDs should be accepted.
+        let frame = Frame::new_data(target_id_1, []);
+        defmt::assert!(state.roundtrip_frame(&frame));
+        let frame = Frame::new_data(target_id_2, []);
+        defmt::assert!(state.roundtrip_frame(&frame));
+        let frame = Frame::new_remote(target_id_3, 8);
+        defmt::assert!(state.roundtrip_frame(&frame));
+        let frame = Frame::new_data(target_id_4, []);
+        defmt::assert!(state.roundtrip_frame(&frame));
+
+        // Data/Remote frame type must match.
+        let frame = Frame::new_remote(target_id_1, 0);
+        defmt::assert!(!state.roundtrip_frame(&frame));
+        let frame = Frame::new_data(target_id_3, []);
+        defmt::assert!(!state.roundtrip_frame(&frame));
+
+        // Other IDs are rejected.
+        let frame = Frame::new_data(StandardId::new(18).unwrap(), []);
+        defmt::assert!(!state.roundtrip_frame(&frame));
+
+        // Extended frames with the same ID are rejected.
+        let frame = Frame::new_data(ExtendedId::new(16).unwrap(), []);
+        defmt::assert!(!state.roundtrip_frame(&frame));
+        let frame = Frame::new_data(ExtendedId::new(16 << 18).unwrap(), []);
+        defmt::assert!(!state.roundtrip_frame(&frame));
+    }
+
+    #[test]
+    fn filter_match_index(state: &mut State) {
+        let ids = [16, 17, 18, 19].map(|id| StandardId::new(id).unwrap());
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(
+                0,
+                Fifo::Fifo0,
+                Mask32::frames_with_std_id(ids[0], StandardId::MAX),
+            )
+            .enable_bank(
+                1,
+                Fifo::Fifo0,
+                [
+                    ListEntry16::data_frames_with_id(ids[1]),
+                    ListEntry16::data_frames_with_id(ids[1]),
+                    ListEntry16::data_frames_with_id(ids[2]),
+                    ListEntry16::data_frames_with_id(ids[3]),
+                ],
+            );
+
+        for (id, expected_index) in ids.iter().zip([0, 1, 3, 4]) {
+            let frame = Frame::new_data(*id, []);
+            block!(state.can1.transmit(&frame)).unwrap();
+            while !state.can1.is_transmitter_idle() {}
+
+            let (received, meta) = block!(state.can1.receive_with_meta()).unwrap();
+            defmt::assert_eq!(received, frame);
+            defmt::assert_eq!(meta.filter_match_index(), expected_index);
+        }
+    }
+
     /// `List32` filter mode accepting standard CAN frames.
     #[test]
     fn filter_list32_std(state: &mut State) {
@@ -196,6 +485,7 @@ mod tests {
 
         state.can1.modify_filters().clear().enable_bank(
             0,
+            Fifo::Fifo0,
             [
                 ListEntry32::data_frames_with_id(target_id_1),
                 ListEntry32::remote_frames_with_id(target_id_2),
@@ -229,6 +519,7 @@ mod tests {
 
         state.can1.modify_filters().clear().enable_bank(
             0,
+            Fifo::Fifo0,
             [
                 ListEntry32::data_frames_with_id(target_id_1),
                 ListEntry32::remote_frames_with_id(target_id_2),
@@ -266,7 +557,7 @@ mod tests {
             .can1
             .modify_filters()
             .clear()
-            .enable_bank(0, Mask32::accept_all());
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
 
         defmt::assert!(state.can1.is_transmitter_idle());
 
@@ -314,62 +605,1208 @@ mod tests {
     }
 
     #[test]
-    fn enable_non_blocking(state: &mut State) {
-        drop(state.can1.modify_config());
-        block!(state.can1.enable_non_blocking()).unwrap();
+    fn dequeue_cookie_blocking(state: &mut State) {
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+        while state.can1.on_tx_interrupt_timestamped().is_some() {}
+
+        state.go_slow();
+
+        // Same sequence as in `dequeue_lower_priority_frame`, but with cookies.
+        let frames = [4, 3, 2, 1].map(|id| Frame::new_data(ExtendedId::new(id).unwrap(), []));
+        for (cookie, frame) in (1..).zip(&frames) {
+            let status = state.can1.transmit_with_cookie(frame, cookie).unwrap();
+            defmt::assert!(status.dequeued_frame().is_none());
+        }
+
+        let frame0 = Frame::new_data(ExtendedId::new(0).unwrap(), []);
+        let status = state.can1.transmit_blocking(&frame0, || false).unwrap();
+        defmt::assert_eq!(status.dequeued_frame(), Some(&frames[1]));
+        defmt::assert_eq!(status.dequeued_cookie(), 2);
+
+        for _ in 0..4 {
+            block!(state.can1.receive()).unwrap();
+        }
+        defmt::assert!(state.can1.is_transmitter_idle());
+
+        // The cookie of the dequeued frame is not reported for `frame0`.
+        while let Some(completion) = state.can1.on_tx_interrupt_timestamped() {
+            defmt::assert_ne!(completion.cookie(), 2);
+        }
+
+        state.go_fast();
+    }
+
+    #[test]
+    fn reject_when_full(state: &mut State) {
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+
+        state.go_slow();
+
+        let frames = [4, 3, 2, 1].map(|id| Frame::new_data(ExtendedId::new(id).unwrap(), []));
+        for frame in &frames {
+            state.can1.transmit(frame).unwrap();
+        }
+
+        // All mailboxes are occupied by lower-priority frames, which are kept.
+        let frame0 = Frame::new_data(ExtendedId::new(0).unwrap(), []);
+        let result = state
+            .can1
+            .transmit_with_policy(&frame0, ReplacementPolicy::RejectWhenFull);
+        defmt::assert!(matches!(result, Err(nb::Error::WouldBlock)));
+
+        for i in [0, 3, 2, 1] {
+            defmt::assert_eq!(block!(state.can1.receive()).unwrap(), frames[i]);
+        }
+        defmt::assert!(state.can1.is_transmitter_idle());
+
+        state.go_fast();
+    }
+
+    #[test]
+    fn transmit_all(state: &mut State) {
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+
+        state.go_slow();
+
+        // Like in `dequeue_lower_priority_frame`, the first frame frees its mailbox immediately,
+        // so 4 frames fit. The last one does not, and nothing is dequeued for it.
+        let frames = [4, 3, 2, 1, 0].map(|id| Frame::new_data(ExtendedId::new(id).unwrap(), []));
+        let count = state.can1.transmit_all(frames.iter());
+        defmt::assert_eq!(count, 4);
+
+        let count = state.can1.transmit_all(frames[count..].iter());
+        defmt::assert_eq!(count, 0);
+
+        for i in [0, 3, 2, 1] {
+            defmt::assert_eq!(block!(state.can1.receive()).unwrap(), frames[i]);
+        }
         defmt::assert!(state.can1.is_transmitter_idle());
 
+        state.go_fast();
+    }
+
+    #[test]
+    fn mailbox_status(state: &mut State) {
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+
         let frame = Frame::new_data(StandardId::new(0).unwrap(), []);
-        defmt::assert!(state.roundtrip_frame(&frame));
+        let tx_status = block!(state.can1.transmit(&frame)).unwrap();
+        while !state.can1.is_transmitter_idle() {}
+
+        let status = state.can1.mailbox_status(tx_status.mailbox());
+        defmt::assert!(status.is_empty());
+        defmt::assert!(status.is_request_completed());
+        defmt::assert!(status.is_transmitted());
+        defmt::assert!(!status.is_arbitration_lost());
+        defmt::assert!(!status.is_transmit_error());
+
+        state.can1.clear_mailbox_status(tx_status.mailbox());
+        let status = state.can1.mailbox_status(tx_status.mailbox());
+        defmt::assert!(!status.is_request_completed());
+        defmt::assert!(!status.is_transmitted());
+
+        defmt::assert_eq!(block!(state.can1.receive()).unwrap(), frame);
     }
 
-    /// Performs an external roundtrip from CAN1 to CAN2 and vice-versa.
-    ///
-    /// Requires that both are hooked up to the same CAN bus.
     #[test]
-    fn ext_roundtrip(state: &mut State) {
+    fn inject_last_error_code(state: &mut State) {
+        state.can1.enable_interrupt(Interrupt::LastErrorCode);
+        // Discard flags left over from previous tests, eg. the sleep acknowledgement.
+        while state.can1.on_sce_interrupt().is_some() {}
+
+        state.can1.inject_last_error_code(Lec::Crc);
+        defmt::assert_eq!(state.can1.last_error_code(), Some(Lec::Crc));
+        defmt::assert_eq!(
+            state.can1.on_sce_interrupt(),
+            Some(SceEvent::BusError(Lec::Crc))
+        );
+        defmt::assert_eq!(state.can1.last_error_code(), None);
+        defmt::assert_eq!(state.can1.on_sce_interrupt(), None);
+
+        state.can1.disable_interrupt(Interrupt::LastErrorCode);
+    }
+
+    #[test]
+    fn take_error_events(state: &mut State) {
+        state.can1.enable_interrupt(Interrupt::LastErrorCode);
+        while state.can1.on_sce_interrupt().is_some() {}
+        defmt::assert!(state.can1.take_error_events().is_empty());
+
+        // Only the first bus error is buffered, further ones are not reported until the events
+        // are taken.
+        state.can1.inject_last_error_code(Lec::Crc);
+        defmt::assert_eq!(state.can1.on_sce_interrupt_buffered(), None);
+        state.can1.inject_last_error_code(Lec::Form);
+        defmt::assert_eq!(state.can1.on_sce_interrupt_buffered(), None);
+        defmt::assert_eq!(state.can1.last_error_code(), Some(Lec::Form));
+
+        let events = state.can1.take_error_events();
+        defmt::assert_eq!(events.bus_error(), Some(Lec::Crc));
+        defmt::assert_eq!(events.error_state(), None);
+        defmt::assert!(!events.entered_bus_off());
+        defmt::assert!(state.can1.take_error_events().is_empty());
+
+        // Taking the events enables the interrupt again.
+        defmt::assert_eq!(state.can1.on_sce_interrupt_buffered(), None);
+        defmt::assert_eq!(state.can1.take_error_events().bus_error(), Some(Lec::Form));
+
+        state.can1.take_error_events();
+        state.can1.disable_interrupt(Interrupt::LastErrorCode);
+    }
+
+    #[test]
+    fn storm_protection(state: &mut State) {
+        state.can1.enable_interrupt(Interrupt::LastErrorCode);
+        while state.can1.on_sce_interrupt().is_some() {}
         state
             .can1
-            .modify_config()
-            .set_loopback(false)
-            .set_silent(false)
-            .set_bit_timing(0x00050000)
-            .enable();
+            .set_storm_protection(Some(StormProtection::new(1, 1)));
+
+        for lec in [Lec::Crc, Lec::Form] {
+            state.can1.inject_last_error_code(lec);
+            defmt::assert_eq!(state.can1.on_sce_interrupt(), Some(SceEvent::BusError(lec)));
+        }
+        defmt::assert_eq!(
+            state.can1.suspended_interrupts(),
+            Interrupts::LAST_ERROR_CODE
+        );
+        state.can1.inject_last_error_code(Lec::Stuff);
+        defmt::assert_eq!(state.can1.on_sce_interrupt(), None);
+
+        state.can1.tick();
+        defmt::assert_eq!(state.can1.suspended_interrupts(), Interrupts::empty());
+        defmt::assert_eq!(
+            state.can1.on_sce_interrupt(),
+            Some(SceEvent::BusError(Lec::Stuff))
+        );
+
+        state.can1.set_storm_protection(None);
+        state.can1.disable_interrupt(Interrupt::LastErrorCode);
+    }
+
+    #[test]
+    fn wakeup_filter(state: &mut State) {
+        let entries = [ListEntry32::data_frames_with_id(StandardId::new(0x100).unwrap()); 2];
+        state.can1.sleep_with_wakeup_filter(entries);
+        defmt::assert!(state.can1.is_sleeping());
+        defmt::assert_eq!(
+            state.can1.modify_filters().get(0),
+            Some((Fifo::Fifo0, entries.into()))
+        );
+
+        // Nothing arrives, so the peripheral goes back to sleep.
+        state.can1.wakeup();
+        defmt::assert_eq!(state.can1.confirm_wakeup(|| true), None);
+        defmt::assert!(state.can1.is_sleeping());
+
+        state.can1.wakeup();
+        state.can1.set_automatic_wakeup(false);
+    }
+
+    #[test]
+    fn try_api(state: &mut State) {
         state
-            .can2
-            .modify_config()
-            .set_loopback(false)
-            .set_silent(false)
-            .set_bit_timing(0x00050000)
-            .enable();
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
 
+        defmt::assert_eq!(state.can1.try_receive(), Err(TryError::Empty));
+
+        let frame = Frame::new_data(StandardId::new(0).unwrap(), [1]);
+        state.can1.try_transmit(&frame).unwrap();
+        while !state.can1.is_transmitter_idle() {}
+        defmt::assert_eq!(state.can1.try_receive(), Ok(frame));
+    }
+
+    #[test]
+    fn interrupt_events(state: &mut State) {
         state
             .can1
             .modify_filters()
-            .set_split(1)
             .clear()
-            .enable_bank(0, Mask32::accept_all());
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+
+        let frame = Frame::new_data(StandardId::new(0).unwrap(), []);
+        let tx_status = block!(state.can1.transmit(&frame)).unwrap();
+        while !state.can1.is_transmitter_idle() {}
+
+        defmt::assert_eq!(
+            state.can1.on_tx_interrupt(),
+            Some(TxEvent::Transmitted(tx_status.mailbox()))
+        );
+        defmt::assert_eq!(state.can1.on_tx_interrupt(), None);
+
+        defmt::assert_eq!(
+            state.can1.on_rx_interrupt(Fifo::Fifo0),
+            Some(RxEvent::FramePending)
+        );
+        defmt::assert_eq!(block!(state.can1.receive()).unwrap(), frame);
+        defmt::assert_eq!(state.can1.on_rx_interrupt(Fifo::Fifo0), None);
+        defmt::assert_eq!(state.can1.on_rx_interrupt(Fifo::Fifo1), None);
+    }
 
+    #[test]
+    fn poll_all_events(state: &mut State) {
         state
             .can1
             .modify_filters()
-            .slave_filters()
             .clear()
-            .enable_bank(1, Mask32::accept_all());
+            .enable_bank(0, Fifo::Fifo1, Mask32::accept_all());
 
-        let frame = Frame::new_data(ExtendedId::new(123).unwrap(), [9, 8, 7]);
-        block!(state.can2.transmit(&frame)).unwrap();
+        let frame = Frame::new_data(StandardId::new(0).unwrap(), []);
+        let tx_status = block!(state.can1.transmit(&frame)).unwrap();
+        while !state.can1.is_transmitter_idle() {}
 
-        while !state.can2.is_transmitter_idle() {}
+        let mut events = state.can1.poll_all_events();
+        defmt::assert_eq!(
+            events.next(),
+            Some(Event::Tx(TxEvent::Transmitted(tx_status.mailbox())))
+        );
+        defmt::assert_eq!(
+            events.next(),
+            Some(Event::Rx(Fifo::Fifo1, RxEvent::FramePending))
+        );
+        defmt::assert!(events.all(|event| matches!(event, Event::Sce(_))));
 
-        let received = state.can1.receive().unwrap();
-        defmt::assert_eq!(frame, received);
+        defmt::assert_eq!(block!(state.can1.receive()).unwrap(), frame);
+        let mut events = state.can1.poll_all_events();
+        defmt::assert!(events.all(|event| matches!(event, Event::Sce(_))));
+    }
 
-        block!(state.can1.transmit(&frame)).unwrap();
+    #[test]
+    fn tx_completion_queue(state: &mut State) {
+        let mut queue = TxCompletionQueue::<4>::new();
+        let (mut producer, mut consumer) = queue.split();
 
+        let frame = Frame::new_data(StandardId::new(0).unwrap(), []);
+        let tx_status = block!(state.can1.transmit_with_cookie(&frame, 0xC0FFEE)).unwrap();
         while !state.can1.is_transmitter_idle() {}
 
-        let received = state.can2.receive().unwrap();
-        defmt::assert_eq!(frame, received);
+        producer.fill(|| state.can1.on_tx_interrupt_timestamped());
+        defmt::assert_eq!(consumer.len(), 1);
+        let completion = consumer.pop().unwrap();
+        defmt::assert!(completion.is_transmitted());
+        defmt::assert_eq!(completion.mailbox(), Some(tx_status.mailbox()));
+        defmt::assert_eq!(completion.cookie(), 0xC0FFEE);
+        defmt::assert_eq!(state.can1.on_tx_interrupt(), None);
+
+        // Drain the received frame.
+        while state.can1.receive().is_ok() {}
+    }
+
+    #[test]
+    fn tx_queue(state: &mut State) {
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+
+        state.go_slow();
+
+        let mut completions = TxCompletionQueue::<4>::new();
+        let (mut producer, mut consumer) = completions.split();
+        let frame = |id| Frame::new_data(ExtendedId::new(id).unwrap(), []);
+        state.with_can1(|can1| {
+            let (tx, mut rx0, rx1, control) = can1.split_with_control();
+            let mut queue = TxQueue::<_, 4>::new(tx);
+
+            // `5` starts transmitting right away, `6` and `7` fill the other mailboxes.
+            for id in 5..=7 {
+                queue.transmit(&frame(id)).unwrap();
+            }
+            defmt::assert!(queue.is_empty());
+            queue.transmit(&frame(8)).unwrap();
+            defmt::assert_eq!(queue.len(), 1);
+            // `2` replaces `7`, which goes back into the backlog.
+            queue.transmit(&frame(2)).unwrap();
+            defmt::assert_eq!(queue.len(), 2);
+
+            queue.transmit_with_deadline(&frame(9), 1, 42).unwrap();
+            defmt::assert_eq!(queue.len(), 3);
+            defmt::assert_eq!(queue.tick(&mut producer), 1);
+            defmt::assert_eq!(queue.len(), 2);
+            let completion = consumer.pop().unwrap();
+            defmt::assert!(completion.is_expired());
+            defmt::assert_eq!(completion.cookie(), 42);
+
+            // The backlog is moved into the mailboxes as they become free.
+            let expected = [5, 2, 6, 7, 8].map(frame);
+            let mut received = 0;
+            while received < expected.len() {
+                queue.on_interrupt();
+                if let Ok(frame) = rx0.receive() {
+                    defmt::assert_eq!(frame, expected[received]);
+                    received += 1;
+                }
+            }
+            defmt::assert!(queue.is_idle());
+
+            Some(Can::join(queue.free(), rx0, rx1, control))
+        });
+
+        state.go_fast();
+    }
+
+    /// Tests that a pending frame can be aborted via the mailbox reported by `transmit`.
+    #[test]
+    fn abort_pending_frame(state: &mut State) {
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+
+        state.go_slow();
+
+        // `frame1` starts transmitting immediately, `frame2` stays pending in its mailbox.
+        let frame1 = Frame::new_data(ExtendedId::new(1).unwrap(), []);
+        state.can1.transmit(&frame1).unwrap();
+        let frame2 = Frame::new_data(ExtendedId::new(2).unwrap(), []);
+        let tx_status = state.can1.transmit(&frame2).unwrap();
+        defmt::assert!(tx_status.dequeued_frame().is_none());
+
+        defmt::assert!(state.can1.abort(tx_status.mailbox()));
+        let status = state.can1.mailbox_status(tx_status.mailbox());
+        defmt::assert!(status.is_empty());
+        defmt::assert!(status.is_request_completed());
+        defmt::assert!(!status.is_transmitted());
+        // Aborting an empty mailbox has no effect.
+        defmt::assert!(!state.can1.abort(Mailbox::Mailbox2));
+
+        // Only `frame1` should arrive.
+        defmt::assert_eq!(block!(state.can1.receive()).unwrap(), frame1);
+        while !state.can1.is_transmitter_idle() {}
+        defmt::assert!(matches!(state.can1.receive(), Err(nb::Error::WouldBlock)));
+
+        state.go_fast();
+    }
+
+    #[test]
+    fn flush(state: &mut State) {
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+
+        state.go_slow();
+
+        let frame1 = Frame::new_data(ExtendedId::new(1).unwrap(), []);
+        let frame2 = Frame::new_data(ExtendedId::new(2).unwrap(), []);
+        state.can1.transmit(&frame1).unwrap();
+        state.can1.transmit(&frame2).unwrap();
+        state.can1.flush();
+        defmt::assert!(state.can1.is_transmitter_idle());
+        defmt::assert_eq!(block!(state.can1.receive()).unwrap(), frame1);
+        defmt::assert_eq!(block!(state.can1.receive()).unwrap(), frame2);
+
+        // `frame1` is already being transmitted when the timeout expires, `frame2` is aborted.
+        state.can1.transmit(&frame1).unwrap();
+        state.can1.transmit(&frame2).unwrap();
+        defmt::assert!(!state.can1.flush_with_timeout(|| true));
+        defmt::assert!(state.can1.is_transmitter_idle());
+        defmt::assert_eq!(block!(state.can1.receive()).unwrap(), frame1);
+        defmt::assert!(matches!(state.can1.receive(), Err(nb::Error::WouldBlock)));
+
+        state.go_fast();
+    }
+
+    #[test]
+    fn enable_non_blocking(state: &mut State) {
+        drop(state.can1.modify_config());
+        block!(state.can1.enable_non_blocking()).unwrap();
+        defmt::assert!(state.can1.is_transmitter_idle());
+
+        let frame = Frame::new_data(StandardId::new(0).unwrap(), []);
+        defmt::assert!(state.roundtrip_frame(&frame));
+    }
+
+    #[test]
+    fn try_enable(state: &mut State) {
+        state.can1.modify_filters().clear();
+        match state.can1.modify_config().try_enable() {
+            Err((config, e)) => {
+                defmt::assert_eq!(e, ConfigError::NoFilters);
+                drop(config);
+            }
+            Ok(()) => defmt::panic!("enabled without filters"),
+        }
+        block!(state.can1.enable_non_blocking()).unwrap();
+
+        state
+            .can1
+            .modify_filters()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+
+        // SJW of 4 time quanta, but bit segment 1 is only 1 time quantum long.
+        let config = match state
+            .can1
+            .modify_config()
+            .set_bit_timing(0x0300_0000)
+            .try_enable()
+        {
+            Err((config, e)) => {
+                defmt::assert_eq!(e, ConfigError::InvalidBitTiming);
+                config
+            }
+            Ok(()) => defmt::panic!("enabled with invalid bit timing"),
+        };
+        defmt::assert!(config.set_bit_timing(0x0005_0000).try_enable().is_ok());
+
+        let frame = Frame::new_data(StandardId::new(0).unwrap(), []);
+        defmt::assert!(state.roundtrip_frame(&frame));
+    }
+
+    #[test]
+    fn receive_timestamped(state: &mut State) {
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+
+        state
+            .can1
+            .modify_config()
+            .set_time_triggered_communication_mode(true)
+            .enable();
+
+        let frame = Frame::new_data(StandardId::new(0).unwrap(), [1, 2, 3]);
+        block!(state.can1.transmit(&frame)).unwrap();
+        while !state.can1.is_transmitter_idle() {}
+        let (received, time1) = block!(state.can1.receive_timestamped()).unwrap();
+        defmt::assert_eq!(received, frame);
+
+        block!(state.can1.transmit(&frame)).unwrap();
+        while !state.can1.is_transmitter_idle() {}
+        let (received, time2) = block!(state.can1.receive_timestamped()).unwrap();
+        defmt::assert_eq!(received, frame);
+
+        // The timer advances by at least one frame length (in bit times) between receptions.
+        defmt::assert!(time2.wrapping_sub(time1) >= 44);
+
+        state
+            .can1
+            .modify_config()
+            .set_time_triggered_communication_mode(false)
+            .enable();
+    }
+
+    #[test]
+    fn tx_ordering_fifo(state: &mut State) {
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+        state
+            .can1
+            .modify_config()
+            .set_tx_ordering(TxOrdering::Fifo)
+            .enable();
+
+        // Enqueue frames with ascending priority, which would be reordered in priority mode.
+        let frames = [3, 2, 1].map(|id| Frame::new_data(StandardId::new(id).unwrap(), []));
+        for frame in &frames {
+            let status = block!(state.can1.transmit(frame)).unwrap();
+            defmt::assert!(status.dequeued_frame().is_none());
+        }
+        while !state.can1.is_transmitter_idle() {}
+
+        for frame in &frames {
+            defmt::assert_eq!(block!(state.can1.receive()).unwrap(), *frame);
+        }
+
+        state
+            .can1
+            .modify_config()
+            .set_tx_ordering(TxOrdering::Priority)
+            .enable();
+    }
+
+    #[test]
+    fn overrun_mode(state: &mut State) {
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+
+        let overruns = state.can1.overrun_count(Fifo::Fifo0);
+        let frames = [1, 2, 3, 4].map(|id| Frame::new_data(StandardId::new(id).unwrap(), []));
+        let modes = [(OverrunMode::Overwrite, 3), (OverrunMode::Discard, 2)];
+        for (i, (mode, last)) in IntoIterator::into_iter(modes).enumerate() {
+            state.can1.modify_config().set_overrun_mode(mode).enable();
+
+            // The FIFO holds 3 frames, so the 4th one causes an overrun.
+            for frame in &frames {
+                block!(state.can1.transmit(frame)).unwrap();
+                while !state.can1.is_transmitter_idle() {}
+            }
+
+            let error = block!(state.can1.receive()).unwrap_err();
+            defmt::assert_eq!(error.fifo(), Fifo::Fifo0);
+            defmt::assert_eq!(error.mode(), mode);
+            defmt::assert_eq!(error.count(), Some(overruns + i as u32 + 1));
+            defmt::assert_eq!(block!(state.can1.receive()).unwrap(), frames[0]);
+            defmt::assert_eq!(block!(state.can1.receive()).unwrap(), frames[1]);
+            defmt::assert_eq!(block!(state.can1.receive()).unwrap(), frames[last]);
+        }
+
+        state
+            .can1
+            .modify_config()
+            .set_overrun_mode(OverrunMode::Overwrite)
+            .enable();
+        defmt::assert_eq!(state.can1.overrun_count(Fifo::Fifo0), overruns + 2);
+    }
+
+    #[test]
+    fn single_shot(state: &mut State) {
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+
+        let frame = Frame::new_data(StandardId::new(0x100).unwrap(), [1]);
+        let status = state.can1.transmit_single_shot(&frame, || false).unwrap();
+        defmt::assert!(status.unwrap().is_transmitted());
+        defmt::assert_eq!(block!(state.can1.receive()).unwrap(), frame);
+
+        // No attempt is made in sleep mode, so the frame is aborted after the timeout.
+        state.can1.sleep();
+        let mut polls = 0;
+        let status = state.can1.transmit_single_shot(&frame, || {
+            polls += 1;
+            polls == 10
+        });
+        defmt::assert!(status.unwrap().is_none());
+        defmt::assert_eq!(polls, 10);
+        defmt::assert!(state.can1.is_transmitter_idle());
+        state.can1.wakeup();
+
+        // NART was restored, and the peripheral works normally again.
+        defmt::assert!(state.roundtrip_frame(&frame));
+    }
+
+    #[test]
+    fn blocking_with_timeout(state: &mut State) {
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+
+        let mut polls = 0;
+        let received = state.can1.receive_blocking(|| {
+            polls += 1;
+            polls > 100
+        });
+        defmt::assert_eq!(received, Ok(None));
+        defmt::assert_eq!(polls, 101);
+
+        let frame = Frame::new_data(StandardId::new(0x42).unwrap(), [1, 2]);
+        defmt::assert!(state.can1.transmit_blocking(&frame, || false).is_some());
+        let received = state.can1.receive_blocking(|| false);
+        defmt::assert_eq!(received, Ok(Some(frame)));
+    }
+
+    #[test]
+    fn peek(state: &mut State) {
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo1, Mask32::accept_all());
+
+        defmt::assert!(state.can1.peek(Fifo::Fifo1).is_none());
+
+        let frame = Frame::new_data(StandardId::new(0x123).unwrap(), [1, 2, 3]);
+        block!(state.can1.transmit(&frame)).unwrap();
+        while !state.can1.is_transmitter_idle() {}
+
+        // Peeking does not remove the frame from the FIFO.
+        for _ in 0..2 {
+            let (peeked, meta) = state.can1.peek(Fifo::Fifo1).unwrap();
+            defmt::assert_eq!(peeked, frame);
+            defmt::assert_eq!(meta.fifo(), Fifo::Fifo1);
+            defmt::assert_eq!(state.can1.fifo_status(Fifo::Fifo1).pending(), 1);
+        }
+
+        defmt::assert_eq!(state.can1.pending_count(Fifo::Fifo1), 1);
+        defmt::assert_eq!(state.can1.pending_count(Fifo::Fifo0), 0);
+
+        let received = block!(state.can1.receive()).unwrap();
+        defmt::assert_eq!(received, frame);
+        defmt::assert!(state.can1.peek(Fifo::Fifo1).is_none());
+        defmt::assert_eq!(state.can1.pending_count(Fifo::Fifo1), 0);
+    }
+
+    #[test]
+    fn receive_into(state: &mut State) {
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+
+        let frame = Frame::new_data(ExtendedId::new(0x12345).unwrap(), [1, 2, 3]);
+        block!(state.can1.transmit(&frame)).unwrap();
+        while !state.can1.is_transmitter_idle() {}
+        let remote = Frame::new_remote(StandardId::new(0x42).unwrap(), 5);
+        block!(state.can1.transmit(&remote)).unwrap();
+        while !state.can1.is_transmitter_idle() {}
+
+        let mut data = [0xFF; 8];
+        let header = block!(state.can1.receive_into(&mut data)).unwrap();
+        defmt::assert_eq!(header.id(), frame.id());
+        defmt::assert!(!header.is_remote_frame());
+        defmt::assert_eq!(header.dlc(), 3);
+        defmt::assert_eq!(header.meta().fifo(), Fifo::Fifo0);
+        defmt::assert_eq!(data[..3], [1, 2, 3]);
+
+        let mut data = [0xFF; 8];
+        let header = block!(state.can1.receive_into(&mut data)).unwrap();
+        defmt::assert_eq!(header.id(), remote.id());
+        defmt::assert!(header.is_remote_frame());
+        defmt::assert_eq!(header.dlc(), 5);
+        defmt::assert_eq!(data, [0xFF; 8]);
+    }
+
+    #[test]
+    fn drain(state: &mut State) {
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+
+        let frames = [
+            Frame::new_data(StandardId::new(0x100).unwrap(), [1]),
+            Frame::new_data(StandardId::new(0x101).unwrap(), [2]),
+        ];
+        for frame in &frames {
+            block!(state.can1.transmit(frame)).unwrap();
+            while !state.can1.is_transmitter_idle() {}
+        }
+
+        let mut received = 0;
+        let count = state
+            .can1
+            .drain(|frame| {
+                defmt::assert_eq!(frame, frames[received]);
+                received += 1;
+            })
+            .unwrap();
+        defmt::assert_eq!(count, 2);
+        defmt::assert_eq!(received, 2);
+        defmt::assert_eq!(state.can1.drain(|_| {}), Ok(0));
+
+        // An overrun of FIFO 0 does not keep FIFO 1 from being drained.
+        let fifo1_id = StandardId::new(0x200).unwrap();
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(
+                0,
+                Fifo::Fifo1,
+                Mask32::frames_with_std_id(fifo1_id, StandardId::MAX),
+            )
+            .enable_bank(1, Fifo::Fifo0, Mask32::accept_all());
+
+        // FIFO 0 holds 3 frames, so the 4th one causes an overrun.
+        let fifo1_frame = Frame::new_data(fifo1_id, [3]);
+        for frame in (0..4).map(|_| &frames[0]).chain([&fifo1_frame]) {
+            block!(state.can1.transmit(frame)).unwrap();
+            while !state.can1.is_transmitter_idle() {}
+        }
+
+        let mut received = 0;
+        let error = state
+            .can1
+            .drain(|frame| {
+                defmt::assert_eq!(frame, fifo1_frame);
+                received += 1;
+            })
+            .unwrap_err();
+        defmt::assert_eq!(error.fifo(), Fifo::Fifo0);
+        defmt::assert_eq!(received, 1);
+        defmt::assert_eq!(state.can1.drain(|_| {}), Ok(3));
+    }
+
+    #[test]
+    fn pending_interrupts(state: &mut State) {
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+        // Other flags (eg. sleep or error conditions) may be set by previous tests.
+        let tx_rx = Interrupts::TRANSMIT_MAILBOX_EMPTY | Interrupts::FIFO0_MESSAGE_PENDING;
+        state.can1.clear_tx_interrupt();
+        defmt::assert!(!state.can1.pending_interrupts().intersects(tx_rx));
+
+        let frame = Frame::new_data(StandardId::new(0).unwrap(), []);
+        block!(state.can1.transmit(&frame)).unwrap();
+        while !state.can1.is_transmitter_idle() {}
+        defmt::assert!(state.can1.pending_interrupts().contains(tx_rx));
+
+        state.can1.clear_tx_interrupt();
+        block!(state.can1.receive()).unwrap();
+        defmt::assert!(!state.can1.pending_interrupts().intersects(tx_rx));
+    }
+
+    #[test]
+    fn raw_registers(state: &mut State) {
+        let ier = Interrupts::from_bits_truncate(state.can1.peripheral().read(Register::Ier));
+        defmt::assert!(!ier.contains(Interrupts::SLEEP));
+
+        state.can1.enable_interrupt(Interrupt::Sleep);
+        let ier = Interrupts::from_bits_truncate(state.can1.peripheral().read(Register::Ier));
+        defmt::assert!(ier.contains(Interrupts::SLEEP));
+
+        // Safety: Only clears the interrupt enable bit set above.
+        unsafe {
+            state
+                .can1
+                .peripheral()
+                .modify(Register::Ier, |ier| ier & !Interrupts::SLEEP.bits());
+        }
+        let ier = Interrupts::from_bits_truncate(state.can1.peripheral().read(Register::Ier));
+        defmt::assert!(!ier.contains(Interrupts::SLEEP));
+
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+        let fa1r = state.can1.peripheral().read_filter(FilterRegister::Fa1r);
+        defmt::assert_eq!(fa1r, 1);
+    }
+
+    #[test]
+    fn error_state_after_init(state: &mut State) {
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+
+        defmt::assert_eq!(state.can1.error_state(), ErrorState::ErrorActive);
+        defmt::assert_eq!(state.can1.on_error_state_change(), None);
+
+        // Not in bus-off state, so this must not disturb normal operation.
+        state.can1.recover_from_bus_off();
+
+        let frame = Frame::new_data(StandardId::new(0).unwrap(), []);
+        defmt::assert!(state.roundtrip_frame(&frame));
+    }
+
+    #[test]
+    fn switch_mode(state: &mut State) {
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+
+        let frame = Frame::new_data(StandardId::new(0).unwrap(), [1]);
+
+        state.can1.set_mode(Mode::Loopback);
+        defmt::assert_eq!(state.can1.mode(), Mode::Loopback);
+        defmt::assert!(state.roundtrip_frame(&frame));
+
+        state.can1.set_mode(Mode::SilentLoopback);
+        defmt::assert_eq!(state.can1.mode(), Mode::SilentLoopback);
+        defmt::assert!(state.roundtrip_frame(&frame));
+    }
+
+    #[test]
+    fn set_bit_timing(state: &mut State) {
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+
+        let initial = state.can1.bit_timing();
+        state.can1.set_bit_timing(0x0005_0001);
+        defmt::assert_eq!(state.can1.bit_timing(), 0x0005_0001);
+
+        // Filters and test modes are preserved.
+        let frame = Frame::new_data(StandardId::new(0x55).unwrap(), [1]);
+        defmt::assert!(state.roundtrip_frame(&frame));
+
+        let timing = BitTiming::from_btr(0x0005_0002);
+        state.can1.set_bitrate(timing);
+        defmt::assert_eq!(state.can1.bit_timing(), timing.btr());
+        defmt::assert!(state.roundtrip_frame(&frame));
+
+        // Unachievable bitrates leave the bit timing unchanged.
+        let result = state
+            .can1
+            .modify_config()
+            .set_bitrate_from_clock(&8_000_000, 3_000_000 / 7);
+        let (config, error) = result.err().unwrap();
+        defmt::assert_eq!(error, BitTimingError::NoSolution);
+        config.enable();
+        defmt::assert_eq!(state.can1.bit_timing(), timing.btr());
+
+        state.can1.set_bit_timing(initial);
+        defmt::assert_eq!(state.can1.bit_timing(), initial);
+    }
+
+    #[test]
+    fn detect_bit_timing_idle_bus(state: &mut State) {
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+
+        // Nothing is transmitted on the bus during the test, so no candidate can be detected.
+        let initial = state.can1.bit_timing();
+        let mut ticks = 0u32;
+        let detected = state
+            .can1
+            .detect_bit_timing(&[0x0005_0001, 0x0005_0003], 1000, || {
+                ticks += 1;
+                ticks
+            });
+        defmt::assert_eq!(detected, None);
+
+        // The previous configuration is restored.
+        defmt::assert_eq!(state.can1.bit_timing(), initial);
+        let frame = Frame::new_data(StandardId::new(0x55).unwrap(), [1]);
+        defmt::assert!(state.roundtrip_frame(&frame));
+    }
+
+    #[test]
+    fn self_test(state: &mut State) {
+        let frame = Frame::new_data(ExtendedId::new(0x1234).unwrap(), [1, 2, 3, 4]);
+
+        state.can1.modify_filters().clear();
+        defmt::assert_eq!(
+            state.can1.self_test(&frame),
+            Err(SelfTestError::NotReceived)
+        );
+
+        state
+            .can1
+            .modify_filters()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+        defmt::assert_eq!(state.can1.self_test(&frame), Ok(()));
+
+        // The previous mode is restored afterwards.
+        defmt::assert!(state.roundtrip_frame(&frame));
+    }
+
+    #[test]
+    fn split_with_control(state: &mut State) {
+        state.with_can1(|can1| {
+            let (mut tx, mut rx0, mut rx1, mut control) = can1.split_with_control();
+            control
+                .modify_filters()
+                .clear()
+                .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+
+            let frame = Frame::new_data(StandardId::new(0x100).unwrap(), [1]);
+            block!(tx.transmit(&frame)).unwrap();
+            while !tx.is_idle() {}
+            defmt::assert_eq!(block!(rx0.receive()).unwrap(), frame);
+
+            // Mode changes keep the halves working.
+            control.set_mode(Mode::Loopback);
+            defmt::assert_eq!(control.mode(), Mode::Loopback);
+            block!(tx.transmit(&frame)).unwrap();
+            while !tx.is_idle() {}
+            defmt::assert_eq!(block!(rx0.receive()).unwrap(), frame);
+            control.set_mode(Mode::SilentLoopback);
+
+            // Filter changes take effect for the receiving halves.
+            control
+                .modify_filters()
+                .clear()
+                .enable_bank(0, Fifo::Fifo1, Mask32::accept_all());
+            block!(tx.transmit(&frame)).unwrap();
+            while !tx.is_idle() {}
+            defmt::assert!(matches!(rx0.receive(), Err(nb::Error::WouldBlock)));
+            defmt::assert_eq!(block!(rx1.receive()).unwrap(), frame);
+
+            let ier = unsafe { &(*pac::CAN1::ptr()).ier };
+            control.enable_interrupt(Interrupt::Fifo0MessagePending);
+            defmt::assert!(ier.read().fmpie0().bit_is_set());
+            control.disable_interrupt(Interrupt::Fifo0MessagePending);
+            defmt::assert!(ier.read().fmpie0().bit_is_clear());
+
+            Some(Can::join(tx, rx0, rx1, control))
+        });
+    }
+
+    #[test]
+    fn join(state: &mut State) {
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+        while state.can1.on_tx_interrupt_timestamped().is_some() {}
+
+        state.go_slow();
+
+        // Same sequence as in `dequeue_cookie_blocking`, but with a split in between.
+        let frames = [4, 3, 2, 1].map(|id| Frame::new_data(ExtendedId::new(id).unwrap(), []));
+        for (cookie, frame) in (1..).zip(&frames) {
+            state.can1.transmit_with_cookie(frame, cookie).unwrap();
+        }
+
+        let frame0 = Frame::new_data(ExtendedId::new(0).unwrap(), []);
+        state.with_can1(|can1| {
+            let (tx, rx0, rx1, control) = can1.split_with_control();
+            let mut can1 = Can::join(tx, rx0, rx1, control);
+
+            // The pending frames are kept, but their cookies are not.
+            let status = can1.transmit_blocking(&frame0, || false).unwrap();
+            defmt::assert_eq!(status.dequeued_frame(), Some(&frames[1]));
+            defmt::assert_eq!(status.dequeued_cookie(), 0);
+            for _ in 0..4 {
+                block!(can1.receive()).unwrap();
+            }
+            defmt::assert!(can1.is_transmitter_idle());
+            while can1.on_tx_interrupt_timestamped().is_some() {}
+
+            can1.free();
+            None
+        });
+
+        state.go_fast();
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+        defmt::assert!(state.roundtrip_frame(&frame0));
+    }
+
+    #[test]
+    fn shutdown_on_drop(state: &mut State) {
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+
+        let can = unsafe { &*pac::CAN1::ptr() };
+        let frames = [0x100, 0x101].map(|id| Frame::new_data(StandardId::new(id).unwrap(), []));
+        for mode in IntoIterator::into_iter([ShutdownMode::Sleep, ShutdownMode::Init]) {
+            state.go_slow();
+            state.with_can1(|can1| {
+                let mut can1 = can1.shutdown_on_drop(mode);
+                can1.enable_interrupt(Interrupt::Fifo0MessagePending);
+                // The first frame is transmitted right away, the second one stays pending.
+                block!(can1.transmit(&frames[0])).unwrap();
+                let pending = block!(can1.transmit(&frames[1])).unwrap().mailbox();
+                drop(can1);
+
+                let tsr = can.tsr.read();
+                defmt::assert!(tsr.tme0().bit_is_set());
+                defmt::assert!(tsr.tme1().bit_is_set());
+                defmt::assert!(tsr.tme2().bit_is_set());
+                let txok = 1 << (1 + 8 * pending as u32);
+                defmt::assert_eq!(tsr.bits() & txok, 0);
+                defmt::assert_eq!(can.ier.read().bits(), 0);
+                let msr = can.msr.read();
+                match mode {
+                    ShutdownMode::Sleep => defmt::assert!(msr.slak().bit_is_set()),
+                    ShutdownMode::Init => defmt::assert!(msr.inak().bit_is_set()),
+                }
+                None
+            });
+            state.can1.drain(|_| {}).unwrap();
+        }
+
+        // `into_inner` returns the instance without shutting it down.
+        state.go_slow();
+        state.with_can1(|can1| {
+            let mut can1 = can1.shutdown_on_drop(ShutdownMode::Init);
+            can1.enable_interrupt(Interrupt::Fifo0MessagePending);
+            block!(can1.transmit(&frames[0])).unwrap();
+            let can1 = can1.into_inner();
+
+            defmt::assert!(!can1.is_transmitter_idle());
+            defmt::assert!(can.ier.read().fmpie0().bit_is_set());
+            let msr = can.msr.read();
+            defmt::assert!(msr.slak().bit_is_clear());
+            defmt::assert!(msr.inak().bit_is_clear());
+            Some(can1)
+        });
+        while !state.can1.is_transmitter_idle() {}
+        defmt::assert_eq!(block!(state.can1.receive()).unwrap(), frames[0]);
+
+        state.can1.disable_interrupt(Interrupt::Fifo0MessagePending);
+        state.go_fast();
+    }
+
+    /// Transmits a frame on the bus that no node acknowledges.
+    ///
+    /// Requires that CAN1 is hooked up to a CAN bus with no other active nodes (CAN2 is in silent
+    /// mode).
+    #[test]
+    fn last_error_code_ack(state: &mut State) {
+        state
+            .can1
+            .modify_config()
+            .set_mode(Mode::Normal)
+            .set_automatic_retransmit(false)
+            .enable();
+        state.can1.clear_last_error_code();
+        defmt::assert_eq!(state.can1.last_error_code(), None);
+        let tec = state.can1.transmit_error_counter();
+
+        let frame = Frame::new_data(StandardId::new(0).unwrap(), []);
+        let tx_status = block!(state.can1.transmit(&frame)).unwrap();
+        while !state.can1.is_transmitter_idle() {}
+
+        defmt::assert_eq!(state.can1.last_error_code(), Some(Lec::Ack));
+        defmt::assert!(state.can1.transmit_error_counter() > tec);
+        let status = state.can1.mailbox_status(tx_status.mailbox());
+        defmt::assert!(!status.is_transmitted());
+        defmt::assert!(status.is_transmit_error());
+
+        state.can1.clear_last_error_code();
+        defmt::assert_eq!(state.can1.last_error_code(), None);
+
+        state
+            .can1
+            .modify_config()
+            .set_automatic_retransmit(true)
+            .enable();
+        state.go_fast();
+    }
+
+    /// Performs an external roundtrip from CAN1 to CAN2 and vice-versa.
+    ///
+    /// Requires that both are hooked up to the same CAN bus.
+    #[test]
+    fn ext_roundtrip(state: &mut State) {
+        state
+            .can1
+            .modify_config()
+            .set_mode(Mode::Normal)
+            .set_bit_timing(0x00050000)
+            .enable();
+        state
+            .can2
+            .modify_config()
+            .set_mode(Mode::Normal)
+            .set_bit_timing(0x00050000)
+            .enable();
+
+        state
+            .can1
+            .modify_filters()
+            .set_split(1)
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+
+        state
+            .can1
+            .modify_filters()
+            .slave_filters()
+            .clear()
+            .enable_bank(1, Fifo::Fifo0, Mask32::accept_all());
+
+        let frame = Frame::new_data(ExtendedId::new(123).unwrap(), [9, 8, 7]);
+        block!(state.can2.transmit(&frame)).unwrap();
+
+        while !state.can2.is_transmitter_idle() {}
+
+        let received = state.can1.receive().unwrap();
+        defmt::assert_eq!(frame, received);
+
+        block!(state.can1.transmit(&frame)).unwrap();
+
+        while !state.can1.is_transmitter_idle() {}
+
+        let received = state.can2.receive().unwrap();
+        defmt::assert_eq!(frame, received);
+    }
+
+    /// Drives CAN1 into the error passive state and back, and checks that the recovery is reported
+    /// by the SCE handler.
+    ///
+    /// Requires that CAN1 and CAN2 are hooked up to the same CAN bus.
+    #[test]
+    fn ext_error_state_recovery(state: &mut State) {
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+        state
+            .can1
+            .modify_config()
+            .set_mode(Mode::Normal)
+            .set_bit_timing(0x00050000)
+            .enable();
+        // CAN2 does not acknowledge frames in silent loopback mode.
+        state
+            .can2
+            .modify_config()
+            .set_mode(Mode::SilentLoopback)
+            .enable();
+        while state.can1.on_sce_interrupt().is_some() {}
+
+        // Every unacknowledged transmission increments the transmit error counter by 8.
+        let frame = Frame::new_data(StandardId::new(0).unwrap(), []);
+        let status = block!(state.can1.transmit(&frame)).unwrap();
+        while state.can1.error_state() == ErrorState::ErrorActive {}
+        defmt::assert!(state.can1.abort(status.mailbox()));
+        defmt::assert_eq!(
+            state.can1.on_sce_interrupt(),
+            Some(SceEvent::ErrorStateChanged(ErrorState::ErrorPassive))
+        );
+
+        // Every acknowledged transmission decrements it by 1.
+        state
+            .can2
+            .modify_config()
+            .set_mode(Mode::Normal)
+            .set_bit_timing(0x00050000)
+            .enable();
+        for _ in 0..64 {
+            if state.can1.error_state() == ErrorState::ErrorActive {
+                break;
+            }
+            block!(state.can1.transmit(&frame)).unwrap();
+            while !state.can1.is_transmitter_idle() {}
+        }
+        defmt::assert_eq!(
+            state.can1.on_sce_interrupt(),
+            Some(SceEvent::ErrorStateChanged(ErrorState::ErrorActive))
+        );
+
+        while !matches!(state.can2.receive(), Err(nb::Error::WouldBlock)) {}
+        state.go_fast();
     }
 }
@@ -3,16 +3,20 @@
 
 #[defmt_test::tests]
 mod tests {
+    use core::future::Future;
+    use core::pin::pin;
     use core::sync::atomic::{AtomicBool, Ordering};
+    use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
 
-    use bxcan::{filter::Mask32, Interrupts, Mailbox, StandardId};
-    use bxcan::{Frame, Interrupt};
+    use bxcan::asynch::AsyncState;
+    use bxcan::{filter::Mask32, Fifo, Interrupts, Mailbox, StandardId};
+    use bxcan::{Frame, Interrupt, Mode};
 
     use irq::handler;
     use nb::block;
     use testsuite::{
         interrupt::{self, Mutex},
-        State,
+        pac, State, CAN1,
     };
 
     #[init]
@@ -25,10 +29,10 @@ mod tests {
             .modify_filters()
             .set_split(1)
             .clear()
-            .enable_bank(0, Mask32::accept_all())
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all())
             .slave_filters()
             .clear()
-            .enable_bank(1, Mask32::accept_all());
+            .enable_bank(1, Fifo::Fifo0, Mask32::accept_all());
 
         state
     }
@@ -150,7 +154,17 @@ mod tests {
         handler!(
             can1_rx = || {
                 defmt::debug!("interrupt: FIFO 0 overrun");
-                m.lock(|state| state.can1.receive().unwrap_err());
+                m.lock(|state| {
+                    let status = state.can1.fifo_status(Fifo::Fifo0);
+                    defmt::assert!(status.is_full());
+                    defmt::assert!(status.is_overrun());
+
+                    defmt::assert!(matches!(
+                        state.can1.receive(),
+                        Err(nb::Error::Other(e)) if e.fifo() == Fifo::Fifo0
+                    ));
+                    defmt::assert!(!state.can1.fifo_status(Fifo::Fifo0).is_overrun());
+                });
 
                 interrupt_fired.store(true, Ordering::Relaxed);
             }
@@ -226,15 +240,13 @@ mod tests {
         state
             .can1
             .modify_config()
-            .set_loopback(false)
-            .set_silent(false)
+            .set_mode(Mode::Normal)
             .set_bit_timing(0x00050000)
             .enable();
         state
             .can2
             .modify_config()
-            .set_loopback(false)
-            .set_silent(false)
+            .set_mode(Mode::Normal)
             .set_bit_timing(0x00050000)
             .enable();
 
@@ -253,6 +265,7 @@ mod tests {
             m.lock(|state| {
                 state.can1.set_automatic_wakeup(true);
                 state.can1.sleep();
+                defmt::assert!(state.can1.is_sleeping());
             });
             let frame = Frame::new_data(StandardId::new(0).unwrap(), []);
             defmt::unwrap!(block!(m.lock(|state| state.can2.transmit(&frame))));
@@ -261,6 +274,7 @@ mod tests {
                 while !state.can2.is_transmitter_idle() {}
             );
             defmt::assert!(wakeup_interrupt_fired.load(Ordering::Relaxed));
+            defmt::assert!(!m.lock(|state| state.can1.is_sleeping()));
 
             // Frame should still be received correctly.
             let recvd = defmt::unwrap!(block!(m.lock(|state| state.can1.receive())));
@@ -270,4 +284,73 @@ mod tests {
         state.can1.disable_interrupt(Interrupt::Wakeup);
         state.go_fast();
     }
+
+    #[test]
+    fn async_single_fifo(state: &mut State) {
+        static ASYNC_STATE: AsyncState<CAN1> = AsyncState::new();
+        static TX_WOKEN: AtomicBool = AtomicBool::new(false);
+        static RX_WOKEN: AtomicBool = AtomicBool::new(false);
+
+        /// Returns a waker that sets `flag` when woken.
+        fn flag_waker(flag: &'static AtomicBool) -> Waker {
+            const VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake, drop);
+
+            unsafe fn clone(flag: *const ()) -> RawWaker {
+                RawWaker::new(flag, &VTABLE)
+            }
+            unsafe fn wake(flag: *const ()) {
+                (*(flag as *const AtomicBool)).store(true, Ordering::Relaxed);
+            }
+            unsafe fn drop(_: *const ()) {}
+
+            let flag = flag as *const AtomicBool as *const ();
+            unsafe { Waker::from_raw(RawWaker::new(flag, &VTABLE)) }
+        }
+
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo1, Mask32::accept_all());
+
+        handler!(can1_tx = || ASYNC_STATE.on_tx_interrupt());
+        handler!(can1_rx1 = || ASYNC_STATE.on_rx1_interrupt());
+        let frame = Frame::new_data(StandardId::new(0x100).unwrap(), [1, 2]);
+        state.with_can1(|can1| {
+            let (mut tx, mut rx) = can1.split_async_fifo(&ASYNC_STATE, Fifo::Fifo1);
+            let ier = unsafe { &(*pac::CAN1::ptr()).ier };
+            irq::scope(|scope| {
+                scope.register(interrupt::CAN1_TX, can1_tx);
+                scope.register(interrupt::CAN1_RX1, can1_rx1);
+
+                let rx_waker = flag_waker(&RX_WOKEN);
+                let mut receive = pin!(rx.receive());
+                let poll = receive.as_mut().poll(&mut Context::from_waker(&rx_waker));
+                defmt::assert!(poll.is_pending());
+                defmt::assert!(ier.read().fmpie0().bit_is_clear());
+                defmt::assert!(ier.read().fmpie1().bit_is_set());
+
+                let tx_waker = flag_waker(&TX_WOKEN);
+                let poll = pin!(tx.transmit(&frame)).poll(&mut Context::from_waker(&tx_waker));
+                defmt::assert!(poll.is_ready());
+                while !tx.inner().is_idle() {}
+                defmt::assert!(TX_WOKEN.load(Ordering::Relaxed));
+
+                // The RX FIFO 1 interrupt masks itself and wakes up the receiver.
+                defmt::assert!(RX_WOKEN.load(Ordering::Relaxed));
+                defmt::assert!(ier.read().fmpie1().bit_is_clear());
+                defmt::assert!(ier.read().fmpie0().bit_is_clear());
+                let poll = receive.as_mut().poll(&mut Context::from_waker(&rx_waker));
+                defmt::assert!(matches!(poll, Poll::Ready(Ok(ref received)) if *received == frame));
+            });
+            None
+        });
+
+        state.can1.disable_interrupts(Interrupts::all());
+        state
+            .can1
+            .modify_filters()
+            .clear()
+            .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+    }
 }